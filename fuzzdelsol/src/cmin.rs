@@ -0,0 +1,218 @@
+use crate::fuzzer_libafl::{hit_bucket, FuzzConfig, FuzzTarget};
+use crate::triage::stable_hash;
+use crate::txfile::TxFile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Artifact khác trong thư mục finding (report, export, repro harness): không phải input
+const SKIP_SUFFIXES: &[&str] = &[
+    ".report.txt",
+    ".report.json",
+    ".solana.json",
+    ".rs",
+    ".sarif",
+];
+
+/// Một input đã replay
+struct Entry {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    typed: bool,
+    /// compute unit xấp xỉ của lần chạy
+    cost: u64,
+    /// (edge, bucket hit count) kiểu afl-cmin
+    tuples: BTreeSet<(usize, u8)>,
+}
+
+impl Entry {
+    /// Nhỏ hơn rồi rẻ hơn (cost ước lượng) là tốt hơn; cùng thì theo path cho ổn định
+    fn rank(&self) -> (usize, u64, &Path) {
+        (self.bytes.len(), self.cost, &self.path)
+    }
+
+    /// Tên theo nội dung: merge nhiều campaign không đụng tên, input trùng bị gộp
+    fn out_name(&self) -> String {
+        let ext = if self.typed { ".tx.json" } else { "" };
+        format!("{:016x}{ext}", stable_hash(&self.bytes))
+    }
+}
+
+pub(crate) fn collect_files(dir: &str) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            !SKIP_SUFFIXES.iter().any(|s| name.ends_with(s))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Như afl-cmin: tuple hiếm nhất trước, chưa phủ thì lấy input tốt nhất (rank) chứa nó.
+/// Trả về (index các input giữ lại, tập tuple đã phủ)
+fn select(entries: &[Entry]) -> (BTreeSet<usize>, BTreeSet<(usize, u8)>) {
+    // tuple -> input tốt nhất chứa nó, và số input chứa nó
+    let mut best: BTreeMap<(usize, u8), usize> = BTreeMap::new();
+    let mut count: BTreeMap<(usize, u8), usize> = BTreeMap::new();
+    for (i, e) in entries.iter().enumerate() {
+        for t in &e.tuples {
+            *count.entry(*t).or_default() += 1;
+            let b = best.entry(*t).or_insert(i);
+            if e.rank() < entries[*b].rank() {
+                *b = i;
+            }
+        }
+    }
+    let mut order: Vec<(usize, u8)> = count.keys().copied().collect();
+    order.sort_by_key(|t| count[t]);
+
+    let mut covered: BTreeSet<(usize, u8)> = BTreeSet::new();
+    let mut kept: BTreeSet<usize> = BTreeSet::new();
+    for t in order {
+        if covered.contains(&t) {
+            continue;
+        }
+        let i = best[&t];
+        kept.insert(i);
+        covered.extend(&entries[i].tuples);
+    }
+    (kept, covered)
+}
+
+/// Corpus distillation: replay mọi input (raw hoặc typed) trong `in_dirs`, lấy tập edge trên CoverageMap,
+/// giữ tập con nhỏ giữ nguyên tổng coverage (greedy theo edge hiếm nhất, input nhỏ/rẻ nhất) vào `out_dir`
+pub fn cmin(cfg: &FuzzConfig, in_dirs: &[String], out_dir: &str) -> io::Result<()> {
+    if fs::read_dir(out_dir).is_ok_and(|mut d| d.next().is_some()) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{out_dir}: not empty"),
+        ));
+    }
+    let mut target = FuzzTarget::setup(cfg)?;
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut seen = BTreeSet::new();
+    let (mut read, mut skipped) = (0, 0);
+    for dir in in_dirs {
+        for path in collect_files(dir)? {
+            let bytes = fs::read(&path)?;
+            read += 1;
+            if !seen.insert(stable_hash(&bytes)) {
+                continue;
+            }
+            let typed = TxFile::from_json(&bytes).is_some();
+            // JSON khác (triage, ...) không phải input
+            if !typed && serde_json::from_slice::<serde_json::Value>(&bytes).is_ok() {
+                skipped += 1;
+                continue;
+            }
+            let exec = match target.execute_file(&bytes) {
+                Ok(exec) => exec,
+                Err(e) => {
+                    eprintln!("[!] {}: {e}", path.display());
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let tuples = exec
+                .coverage
+                .hits
+                .iter()
+                .enumerate()
+                .filter(|(_, &h)| h > 0)
+                .map(|(i, &h)| (i, hit_bucket(h)))
+                .collect();
+            entries.push(Entry {
+                path,
+                bytes,
+                typed,
+                cost: exec.compute_units,
+                tuples,
+            });
+        }
+    }
+
+    let (kept, covered) = select(&entries);
+
+    fs::create_dir_all(out_dir)?;
+    let mut kept_bytes = 0;
+    for &i in &kept {
+        let e = &entries[i];
+        fs::write(Path::new(out_dir).join(e.out_name()), &e.bytes)?;
+        kept_bytes += e.bytes.len();
+    }
+    let edges: BTreeSet<usize> = covered.iter().map(|(i, _)| *i).collect();
+    println!(
+        "[*] {read} file(s) from {} dir(s), {} unique input(s), {skipped} skipped",
+        in_dirs.len(),
+        entries.len()
+    );
+    println!(
+        "[*] coverage: {} edges, {} tuples",
+        edges.len(),
+        covered.len()
+    );
+    println!(
+        "[*] kept {} / {} input(s) ({} -> {} bytes) in {out_dir}",
+        kept.len(),
+        entries.len(),
+        entries.iter().map(|e| e.bytes.len()).sum::<usize>(),
+        kept_bytes
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, len: usize, cost: u64, tuples: &[(usize, u8)]) -> Entry {
+        Entry {
+            path: PathBuf::from(name),
+            bytes: vec![0; len],
+            typed: false,
+            cost,
+            tuples: tuples.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn select_keeps_small_cover() {
+        let entries = vec![
+            // phủ hết nhưng lớn
+            entry("a", 100, 10, &[(1, 1), (2, 1), (3, 1)]),
+            entry("b", 10, 10, &[(1, 1), (2, 1)]),
+            entry("c", 10, 10, &[(3, 1)]),
+            // tập con của b
+            entry("d", 20, 10, &[(1, 1)]),
+        ];
+        let (kept, covered) = select(&entries);
+        assert_eq!(kept, BTreeSet::from([1, 2]));
+        assert_eq!(covered.len(), 3);
+    }
+
+    #[test]
+    fn select_prefers_cheaper_then_path() {
+        let entries = vec![
+            entry("b", 10, 50, &[(1, 1)]),
+            entry("a", 10, 50, &[(1, 1)]),
+            entry("c", 10, 5, &[(2, 1)]),
+            entry("d", 10, 9, &[(2, 1)]),
+        ];
+        // cùng size: cost thấp hơn, rồi path nhỏ hơn
+        assert_eq!(select(&entries).0, BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn select_distinguishes_hit_buckets() {
+        // cùng edge, bucket hit count khác nhau -> hai tuple, cần cả hai input
+        let entries = vec![entry("a", 1, 0, &[(7, 1)]), entry("b", 1, 0, &[(7, 3)])];
+        let (kept, covered) = select(&entries);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(covered.len(), 2);
+    }
+}
@@ -0,0 +1,87 @@
+//! Xấp xỉ (approximation) compute unit của một lần chạy TraceVM. TraceVM không chạy BPF instruction
+//! của ELF nên đây không phải compute unit runtime đo được: mỗi byte trace được gán một cost theo nhóm op,
+//! dùng hằng số của ComputeBudget mặc định (runtime 1.18) để cùng thang với budget.
+
+/// CU limit mỗi instruction: mặc định / tối đa
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// CPI: phí cố định + phí theo số byte instruction/account data truyền sang callee
+pub const INVOKE_UNITS: u64 = 1_000;
+pub const CPI_BYTES_PER_UNIT: u64 = 250;
+pub const SYSCALL_BASE_COST: u64 = 100;
+pub const SYSVAR_BASE_COST: u64 = 100;
+pub const MEM_OP_BASE_COST: u64 = 10;
+
+/// Builtin program (system / token) tự tiêu thêm cost bên trong CPI
+pub const SYSTEM_PROGRAM_UNITS: u64 = 150;
+pub const TOKEN_PROGRAM_UNITS: u64 = 4_500;
+
+/// Số BPF instruction mỗi vòng lặp của loop op (cost 1 / instruction)
+pub const LOOP_BODY_INSNS: u64 = 8;
+
+/// Cost của một byte trace: basic block `1 + (b & 7)` instruction + syscall/CPI (nếu có).
+/// `ext_op`: byte là extended op (theo version encoding của trace op).
+/// Frame callee của CPI tự tính cost của nó.
+pub fn op_cost(b: u8, arg: u8, cpi_bytes: u64, ext_op: bool) -> u64 {
+    let block = 1 + (b & 7) as u64;
+    let extra = match b >> 5 {
+        4 => INVOKE_UNITS + cpi_bytes / CPI_BYTES_PER_UNIT,
+        7 if ext_op => match b & 0x1f {
+            0..=7 => INVOKE_UNITS + TOKEN_PROGRAM_UNITS,
+            13 => INVOKE_UNITS + SYSTEM_PROGRAM_UNITS,
+            14 => MEM_OP_BASE_COST + (arg as u64 * 64) / CPI_BYTES_PER_UNIT,
+            16 => SYSVAR_BASE_COST + 40, // sol_get_clock_sysvar (+ size Clock)
+            17 => SYSCALL_BASE_COST,     // sol_get_stack_height
+            18 => ((arg as u64) << 10) * LOOP_BODY_INSNS,
+            19 => MEM_OP_BASE_COST, // sol_memcmp
+            _ => 0,
+        },
+        _ => 0,
+    };
+    block + extra
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_op_costs_its_block() {
+        assert_eq!(op_cost(0x00, 0xff, 1_000, false), 1);
+        assert_eq!(op_cost(0x27, 0, 0, false), 8);
+        // sub-op 7.x chưa gán: chỉ basic block
+        assert_eq!(op_cost(0xff, 0, 0, false), 8);
+    }
+
+    #[test]
+    fn cpi_cost_grows_with_bytes() {
+        let small = op_cost(0x80, 0, 8, false);
+        let big = op_cost(0x80, 0, 8 + 10 * CPI_BYTES_PER_UNIT, false);
+        assert_eq!(small, 1 + INVOKE_UNITS);
+        assert_eq!(big - small, 10);
+    }
+
+    #[test]
+    fn builtin_and_syscall_costs() {
+        assert_eq!(
+            op_cost(0xe0, 0, 0, true),
+            1 + INVOKE_UNITS + TOKEN_PROGRAM_UNITS
+        );
+        assert_eq!(
+            op_cost(0xed, 0, 0, true),
+            6 + INVOKE_UNITS + SYSTEM_PROGRAM_UNITS
+        );
+        assert_eq!(op_cost(0xf1, 0, 0, true), 2 + SYSCALL_BASE_COST);
+        // encoding cũ: cùng byte là IntegerOp, chỉ basic block
+        assert_eq!(op_cost(0xe0, 0, 0, false), 1);
+    }
+
+    #[test]
+    fn loop_cost_scales_with_arg() {
+        assert_eq!(op_cost(0xf2, 0, 0, true), 3);
+        assert_eq!(op_cost(0xf2, 1, 0, true), 3 + 1024 * LOOP_BODY_INSNS);
+        // arg tối đa đủ vượt budget tối đa
+        assert!(op_cost(0xf2, 0xff, 0, true) > MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
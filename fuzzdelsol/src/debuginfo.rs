@@ -0,0 +1,212 @@
+use gimli::{AttributeValue, EndianSlice, LittleEndian, SectionId};
+use object::{Object, ObjectSection};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+
+/// Vị trí của finding trong source program, mức function: file + dòng đầu của function.
+/// TraceVM không chạy bytecode của ELF nên finding không có PC thật trong thân function,
+/// dòng là của row `.debug_line` tại `DW_AT_low_pc`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLoc {
+    pub file: String,
+    pub function: String,
+    /// report cũ (trước khi có dòng) không có field này
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+}
+
+/// Tên function -> file source + dòng, đọc từ DWARF của ELF (build có debug info).
+/// File và dòng lấy từ row `.debug_line` tại PC đầu function (`DW_AT_low_pc`), không dùng `DW_AT_decl_file`
+/// (nơi khai báo, có thể là file khác với code)
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    functions: BTreeMap<String, Vec<SourceLoc>>,
+}
+
+impl SourceMap {
+    /// ELF không có `.debug_info` (hoặc parse lỗi, section nén) -> map rỗng.
+    /// Không dùng elf_parser của rbpf: nó giới hạn tên section 16 byte (`.debug_str_offsets`)
+    pub fn from_elf(elf_bytes: &[u8]) -> Self {
+        let Ok(elf) = object::File::parse(elf_bytes) else {
+            return Self::default();
+        };
+        let load = |id: SectionId| -> Result<Cow<[u8]>, gimli::Error> {
+            let data = elf
+                .section_by_name(id.name())
+                .and_then(|s| s.uncompressed_data().ok());
+            Ok(data.unwrap_or(Cow::Borrowed(&[])))
+        };
+        let Ok(sections) = gimli::DwarfSections::load(load) else {
+            return Self::default();
+        };
+        let dwarf = sections.borrow(|s| EndianSlice::new(s, LittleEndian));
+        let mut map = Self::default();
+        // DWARF hỏng giữa chừng: giữ những gì đã đọc được
+        let _ = map.read_units(&dwarf);
+        map
+    }
+
+    fn read_units(&mut self, dwarf: &gimli::Dwarf<Reader>) -> gimli::Result<()> {
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            // PC -> (file, dòng) của row `.debug_line` đầu tiên tại PC đó
+            let mut rows = program.rows();
+            let mut files: BTreeMap<u64, (u64, Option<u64>)> = BTreeMap::new();
+            while let Some((_, row)) = rows.next_row()? {
+                if !row.end_sequence() {
+                    files
+                        .entry(row.address())
+                        .or_insert((row.file_index(), row.line().map(|l| l.get())));
+                }
+            }
+            let header = rows.header();
+
+            let mut entries = unit.entries();
+            while let Some((_, e)) = entries.next_dfs()? {
+                if e.tag() != gimli::DW_TAG_subprogram {
+                    continue;
+                }
+                // chỉ function có code: bỏ declaration và abstract instance của inline
+                let Some(low_pc) = e.attr_value(gimli::DW_AT_low_pc)? else {
+                    continue;
+                };
+                let Some(pc) = dwarf.attr_address(&unit, low_pc)? else {
+                    continue;
+                };
+                let Some(&(idx, line)) = files.get(&pc) else {
+                    continue;
+                };
+                let Some(file) = header.file(idx) else {
+                    continue;
+                };
+                // method / generic: tên nằm ở declaration (DW_AT_specification) hoặc abstract origin
+                let mut name = e.attr_value(gimli::DW_AT_name)?;
+                for at in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+                    if name.is_some() {
+                        break;
+                    }
+                    if let Some(AttributeValue::UnitRef(off)) = e.attr_value(at)? {
+                        name = unit.entry(off)?.attr_value(gimli::DW_AT_name)?;
+                    }
+                }
+                let Some(name) = name else {
+                    continue;
+                };
+                let name = dwarf
+                    .attr_string(&unit, name)?
+                    .to_string_lossy()
+                    .into_owned();
+
+                let mut path = dwarf
+                    .attr_string(&unit, file.path_name())?
+                    .to_string_lossy()
+                    .into_owned();
+                if !path.starts_with('/') {
+                    if let Some(dir) = file.directory(header) {
+                        let dir = dwarf.attr_string(&unit, dir)?;
+                        path = format!("{}/{path}", dir.to_string_lossy());
+                    }
+                }
+                let loc = SourceLoc {
+                    file: path,
+                    function: name.clone(),
+                    line,
+                };
+                let locs = self.functions.entry(name).or_default();
+                if !locs.contains(&loc) {
+                    locs.push(loc);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// File (và dòng đầu) của function `name`; ưu tiên code của program (không nằm trong registry/toolchain)
+    pub fn function(&self, name: &str) -> Option<&SourceLoc> {
+        let locs = self.functions.get(name)?;
+        let external = |l: &SourceLoc| l.file.contains("/.cargo/") || l.file.contains("/rustc/");
+        locs.iter().find(|l| !external(l)).or_else(|| locs.first())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(file: &str, function: &str, line: Option<u64>) -> SourceLoc {
+        SourceLoc {
+            file: file.into(),
+            function: function.into(),
+            line,
+        }
+    }
+
+    #[test]
+    fn no_dwarf_is_empty() {
+        assert!(SourceMap::from_elf(b"not an elf").is_empty());
+        assert!(SourceMap::from_elf(&[]).is_empty());
+    }
+
+    #[test]
+    fn prefers_program_source_over_registry() {
+        let mut map = SourceMap::default();
+        map.functions.insert(
+            "process_instruction".into(),
+            vec![
+                loc(
+                    "/root/.cargo/registry/src/anchor-lang/src/lib.rs",
+                    "process_instruction",
+                    Some(10),
+                ),
+                loc("/work/vault/src/lib.rs", "process_instruction", Some(42)),
+            ],
+        );
+        map.functions.insert(
+            "entrypoint".into(),
+            vec![loc(
+                "/rustc/abc/library/core/src/lib.rs",
+                "entrypoint",
+                None,
+            )],
+        );
+        assert_eq!(map.function("process_instruction").unwrap().line, Some(42));
+        // chỉ có code ngoài -> vẫn trả về
+        assert!(map.function("entrypoint").is_some());
+        assert!(map.function("withdraw").is_none());
+    }
+
+    #[test]
+    fn reads_file_and_line_from_debug_line() {
+        // test binary build có debug info: function này phải map về file này, dòng của `fn`
+        let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let map = SourceMap::from_elf(&exe);
+        let l = map.function("reads_file_and_line_from_debug_line").unwrap();
+        assert!(l.file.ends_with("src/debuginfo.rs"), "{}", l.file);
+        let line = l.line.unwrap() as usize;
+        let src = include_str!("debuginfo.rs");
+        let text = src.lines().nth(line - 1).unwrap();
+        assert!(
+            text.contains("fn reads_file_and_line_from_debug_line"),
+            "{line}: {text}"
+        );
+    }
+
+    #[test]
+    fn old_report_without_line_deserializes() {
+        let l: SourceLoc =
+            serde_json::from_str(r#"{"file":"src/lib.rs","function":"withdraw"}"#).unwrap();
+        assert_eq!(l, loc("src/lib.rs", "withdraw", None));
+        assert!(!serde_json::to_string(&l).unwrap().contains("line"));
+    }
+}
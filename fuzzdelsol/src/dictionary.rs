@@ -0,0 +1,310 @@
+use solana_rbpf::aligned_memory::AlignedMemory;
+use solana_rbpf::ebpf::{self, HOST_ALIGN};
+use solana_rbpf::elf_parser::Elf64;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+
+/// Giới hạn để dictionary không lấn át havoc
+const MAX_TOKENS: usize = 512;
+const MAX_KEYS: usize = 16;
+const MIN_STRING_LEN: usize = 3;
+const MAX_TOKEN_LEN: usize = 32;
+
+/// Dictionary trích từ ELF trước khi fuzz: token cho mutator + pubkey hard-code cho emulator
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    pub tokens: Vec<Vec<u8>>,
+    pub keys: Vec<Pubkey>,
+    seen: BTreeSet<Vec<u8>>,
+}
+
+impl Dictionary {
+    /// Quét `.rodata` (string, discriminator, pubkey) và `.text` (immediate của jump/lddw).
+    /// ELF không parse được -> dictionary rỗng
+    pub fn from_elf(elf_bytes: &[u8]) -> Self {
+        let mut dict = Self::default();
+        // Elf64::parse đòi buffer align theo header
+        let aligned = AlignedMemory::<HOST_ALIGN>::from_slice(elf_bytes);
+        let Ok(elf) = Elf64::parse(aligned.as_slice()) else {
+            return dict;
+        };
+
+        for sh in elf.section_header_table() {
+            let Ok(name) = elf.section_name(sh.sh_name) else {
+                continue;
+            };
+            let Some(bytes) = sh.file_range().and_then(|r| aligned.as_slice().get(r)) else {
+                continue;
+            };
+            if name.starts_with(b".rodata") || name == b".data.rel.ro" {
+                dict.scan_rodata(bytes);
+            } else if name == b".text" {
+                dict.scan_text(bytes);
+            }
+        }
+        dict
+    }
+
+    fn push_token(&mut self, t: &[u8]) {
+        if self.tokens.len() >= MAX_TOKENS || t.is_empty() || t.iter().all(|&b| b == 0) {
+            return;
+        }
+        if self.seen.insert(t.to_vec()) {
+            self.tokens.push(t.to_vec());
+        }
+    }
+
+    fn scan_rodata(&mut self, data: &[u8]) {
+        let printable = |b: &u8| (0x20..0x7f).contains(b);
+        let mut covered = vec![false; data.len()];
+
+        // 1) pubkey hard-code: 32 byte "ngẫu nhiên" (nhiều giá trị khác nhau, ít ký tự in được).
+        // Pubkey align 1 nên phải trượt từng byte; bỏ qua cả run ASCII để window không bắt đầu giữa string
+        let mut off = 0;
+        while off + 32 <= data.len() && self.keys.len() < MAX_KEYS {
+            let run = data[off..].iter().take_while(|b| printable(b)).count();
+            if run > MIN_STRING_LEN {
+                off += run;
+                continue;
+            }
+            let w = &data[off..off + 32];
+            if looks_random(w, 24, 20) {
+                let key = Pubkey::try_from(w).unwrap();
+                if !self.keys.contains(&key) {
+                    self.keys.push(key);
+                }
+                self.push_token(w);
+                covered[off..off + 32].fill(true);
+                off += 32;
+            } else {
+                off += 1;
+            }
+        }
+
+        // 2) discriminator (Anchor: 8 byte hash), align 8, ngoài vùng pubkey
+        for off in (0..data.len().saturating_sub(7)).step_by(8) {
+            let w = &data[off..off + 8];
+            if !covered[off..off + 8].contains(&true) && looks_random(w, 7, 4) {
+                self.push_token(w);
+                covered[off..off + 8].fill(true);
+            }
+        }
+
+        // 3) string literal: rustc nối liền các literal (không có NUL) -> tách thêm theo từ
+        let text: Vec<u8> = data
+            .iter()
+            .zip(&covered)
+            .map(|(&b, &c)| if c { 0 } else { b })
+            .collect();
+        for run in text.split(|b| !printable(b)) {
+            if run.len() < MIN_STRING_LEN {
+                continue;
+            }
+            if run.len() <= MAX_TOKEN_LEN {
+                self.push_token(run);
+            }
+            for word in split_words(run) {
+                if (MIN_STRING_LEN..=MAX_TOKEN_LEN).contains(&word.len()) {
+                    self.push_token(word);
+                }
+            }
+        }
+    }
+
+    fn scan_text(&mut self, text: &[u8]) {
+        let mut pc = 0;
+        while pc + ebpf::INSN_SIZE <= text.len() {
+            let insn = &text[pc..pc + ebpf::INSN_SIZE];
+            let opc = insn[0];
+            let imm = i32::from_le_bytes(insn[4..8].try_into().unwrap());
+            pc += ebpf::INSN_SIZE;
+
+            if opc == ebpf::LD_DW_IMM {
+                // lddw chiếm 2 slot: imm cao nằm ở slot sau
+                let Some(next) = text.get(pc..pc + ebpf::INSN_SIZE) else {
+                    break;
+                };
+                pc += ebpf::INSN_SIZE;
+                let hi = u32::from_le_bytes(next[4..8].try_into().unwrap()) as u64;
+                let v = (hi << 32) | imm as u32 as u64;
+                // bỏ địa chỉ vùng nhớ VM (program/stack/heap/input: 0x1..0x4 << 32)
+                if hi > (ebpf::MM_INPUT_START >> 32) && looks_random(&v.to_le_bytes(), 5, 8) {
+                    self.push_token(&v.to_le_bytes());
+                }
+                continue;
+            }
+
+            // jump so với immediate (BPF_K): jeq/jgt/jge/jset/jne/jsgt/jsge/jlt/jle/jslt/jsle
+            let is_jmp = opc & 0x07 == ebpf::BPF_JMP && opc & ebpf::BPF_X == 0;
+            let is_cmp = !matches!(opc & 0xf0, ebpf::BPF_JA | ebpf::BPF_CALL | ebpf::BPF_EXIT);
+            if is_jmp && is_cmp && !(-0xff..=0xff).contains(&imm) {
+                self.push_token(&imm.to_le_bytes());
+                self.push_token(&(imm as i64).to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Tách một run ASCII thành từ: theo ký tự không phải chữ/số và theo chỗ chữ thường -> chữ hoa
+/// ("vaultInvalid seeds" -> "vault", "Invalid", "seeds")
+fn split_words(run: &[u8]) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for i in 0..=run.len() {
+        let boundary = match run.get(i) {
+            None => true,
+            Some(b) if !b.is_ascii_alphanumeric() => true,
+            Some(b) => i > start && b.is_ascii_uppercase() && run[i - 1].is_ascii_lowercase(),
+        };
+        if boundary {
+            if i > start {
+                out.push(&run[start..i]);
+            }
+            start = if run.get(i).is_some_and(|b| b.is_ascii_alphanumeric()) {
+                i
+            } else {
+                i + 1
+            };
+        }
+    }
+    out
+}
+
+/// Window trông như hash/key: ít nhất `min_distinct` byte khác nhau và tối đa `max_printable` byte in được
+fn looks_random(w: &[u8], min_distinct: usize, max_printable: usize) -> bool {
+    let distinct = w.iter().collect::<BTreeSet<_>>().len();
+    let printable = w.iter().filter(|b| (0x20..0x7f).contains(*b)).count();
+    distinct >= min_distinct && printable <= max_printable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHT_PROGBITS: u32 = 1;
+    const SHT_STRTAB: u32 = 3;
+
+    /// ELF64 tối thiểu (không program header) với các section `(name, type, data)`
+    fn elf(sections: &[(&str, u32, &[u8])]) -> Vec<u8> {
+        let mut shstrtab = vec![0u8];
+        let mut names = Vec::new();
+        for (name, _, _) in sections.iter().chain([&(".shstrtab", SHT_STRTAB, &[][..])]) {
+            names.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+        }
+
+        let mut body = Vec::new();
+        let mut headers = vec![[0u8; 64]];
+        let all = sections
+            .iter()
+            .map(|&(_, ty, data)| (ty, data))
+            .chain([(SHT_STRTAB, &shstrtab[..])]);
+        for (n, (ty, data)) in all.enumerate() {
+            let mut sh = [0u8; 64];
+            sh[0..4].copy_from_slice(&names[n].to_le_bytes());
+            sh[4..8].copy_from_slice(&ty.to_le_bytes());
+            sh[24..32].copy_from_slice(&(64 + body.len() as u64).to_le_bytes());
+            sh[32..40].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            sh[48..56].copy_from_slice(&1u64.to_le_bytes());
+            headers.push(sh);
+            body.extend_from_slice(data);
+        }
+        while body.len() % 8 != 0 {
+            body.push(0);
+        }
+
+        let mut out = vec![0u8; 64];
+        out[..8].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+        out[16..18].copy_from_slice(&3u16.to_le_bytes()); // ET_DYN
+        out[18..20].copy_from_slice(&247u16.to_le_bytes()); // EM_BPF
+        out[20..24].copy_from_slice(&1u32.to_le_bytes());
+        out[40..48].copy_from_slice(&(64 + body.len() as u64).to_le_bytes()); // e_shoff
+        out[52..54].copy_from_slice(&64u16.to_le_bytes());
+        out[54..56].copy_from_slice(&56u16.to_le_bytes());
+        out[58..60].copy_from_slice(&64u16.to_le_bytes());
+        out[60..62].copy_from_slice(&(headers.len() as u16).to_le_bytes());
+        out[62..64].copy_from_slice(&(headers.len() as u16 - 1).to_le_bytes());
+        out.extend_from_slice(&body);
+        for sh in headers {
+            out.extend_from_slice(&sh);
+        }
+        out
+    }
+
+    fn insn(opc: u8, imm: u32) -> [u8; 8] {
+        let mut i = [opc, 0, 0, 0, 0, 0, 0, 0];
+        i[4..].copy_from_slice(&imm.to_le_bytes());
+        i
+    }
+
+    #[test]
+    fn from_elf_extracts_strings_keys_and_immediates() {
+        let key: Vec<u8> = (0..32).map(|i| 0x80 + i * 3).collect();
+        let disc = [0x01, 0x92, 0xa3, 0xb4, 0xc5, 0xd6, 0xe7, 0xf8];
+        let mut rodata = b"vaultInvalid seeds".to_vec();
+        rodata.extend_from_slice(&key);
+        rodata.resize(56, 0);
+        rodata.extend_from_slice(&disc);
+
+        let mut text = Vec::new();
+        text.extend_from_slice(&insn(ebpf::JEQ_IMM, 0x1234_5678));
+        text.extend_from_slice(&insn(ebpf::LD_DW_IMM, 0x0bad_f00d));
+        text.extend_from_slice(&insn(0, 0xdead_beef));
+        text.extend_from_slice(&insn(ebpf::JEQ_IMM, 7)); // immediate nhỏ: bỏ
+        text.extend_from_slice(&insn(ebpf::EXIT, 0));
+
+        let dict = Dictionary::from_elf(&elf(&[
+            (".rodata", SHT_PROGBITS, &rodata),
+            (".text", SHT_PROGBITS, &text),
+        ]));
+        assert_eq!(dict.keys, vec![Pubkey::try_from(&key[..]).unwrap()]);
+        for t in [
+            &b"vaultInvalid seeds"[..],
+            b"vault",
+            b"Invalid",
+            b"seeds",
+            &key,
+            &disc,
+            &0x1234_5678u32.to_le_bytes(),
+            &0x1234_5678i64.to_le_bytes(),
+            &0xdead_beef_0bad_f00du64.to_le_bytes(),
+        ] {
+            assert!(dict.tokens.contains(&t.to_vec()), "missing {t:02x?}");
+        }
+        assert!(!dict.tokens.contains(&7u32.to_le_bytes().to_vec()));
+    }
+
+    #[test]
+    fn from_elf_rejects_non_elf() {
+        let dict = Dictionary::from_elf(b"not an elf at all");
+        assert!(dict.tokens.is_empty() && dict.keys.is_empty());
+    }
+
+    #[test]
+    fn split_words_on_separators_and_case() {
+        assert_eq!(
+            split_words(b"vaultInvalid seeds"),
+            vec![&b"vault"[..], b"Invalid", b"seeds"]
+        );
+        assert_eq!(
+            split_words(b"--max_amount::v2"),
+            vec![&b"max"[..], b"amount", b"v2"]
+        );
+        // chữ hoa liền nhau không tách
+        assert_eq!(split_words(b"PDA"), vec![&b"PDA"[..]]);
+        assert!(split_words(b"").is_empty());
+    }
+
+    #[test]
+    fn looks_random_thresholds() {
+        let hash: Vec<u8> = (0..32).map(|i| 0x80 + i * 3).collect();
+        assert!(looks_random(&hash, 24, 20));
+        // string ASCII: quá nhiều byte in được
+        assert!(!looks_random(b"this is definitely a string.....", 24, 20));
+        // lặp: ít byte khác nhau
+        assert!(!looks_random(&[0x90; 32], 24, 20));
+        assert!(looks_random(&[1, 2, 3, 4, 5, 0, 0, 0], 5, 8));
+        assert!(!looks_random(&[1, 2, 3, 0, 0, 0, 0, 0], 5, 8));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::layout::{LayoutField, LayoutModel};
+use crate::txfile::Roles;
+use crate::types::{hex, Account, LedgerSnapshot, Transaction};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Hex tối đa của một đoạn data trong bản text
+const MAX_HEX_BYTES: usize = 32;
+
+/// State một account tại một thời điểm (JSON report)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountState {
+    pub owner: String,
+    pub lamports: u64,
+    /// hex
+    pub data: String,
+    pub executable: bool,
+}
+
+impl AccountState {
+    fn new(a: &Account) -> Self {
+        Self {
+            owner: a.owner.to_string(),
+            lamports: a.lamports,
+            data: hex(&a.data),
+            executable: a.is_executable,
+        }
+    }
+}
+
+/// Một thay đổi: lamports, owner, field đã decode (`Vault.balance`) hoặc đoạn data raw (`data[40..48]`)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Account bị tx thay đổi: None = chưa tồn tại (before) / đã bị close (after)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountDiff {
+    pub pubkey: String,
+    /// attacker | user | program-owned | foreign-owned
+    pub role: String,
+    /// privilege trong tx (meta của instruction bất kỳ)
+    #[serde(default)]
+    pub signer: bool,
+    #[serde(default)]
+    pub writable: bool,
+    pub before: Option<AccountState>,
+    pub after: Option<AccountState>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Vai trò của account theo key (attacker/user) rồi theo owner trước tx
+pub(crate) fn role(key: &Pubkey, owner: &Pubkey, roles: Roles) -> &'static str {
+    if *key == roles.attacker {
+        "attacker"
+    } else if *key == roles.user {
+        "user"
+    } else if *owner == roles.program_id {
+        "program-owned"
+    } else {
+        "foreign-owned"
+    }
+}
+
+/// Giá trị field theo type IDL; không decode được -> hex
+fn decode(field: &LayoutField, bytes: &[u8]) -> String {
+    decode_value(&field.ty, bytes).unwrap_or_else(|| hex(bytes))
+}
+
+/// None khi type không biết hoặc số byte không khớp size của type (layout IDL sai)
+fn decode_value(ty: &str, b: &[u8]) -> Option<String> {
+    Some(match ty {
+        "bool" => (u8::from_le_bytes(b.try_into().ok()?) != 0).to_string(),
+        "u8" => u8::from_le_bytes(b.try_into().ok()?).to_string(),
+        "i8" => i8::from_le_bytes(b.try_into().ok()?).to_string(),
+        "u16" => u16::from_le_bytes(b.try_into().ok()?).to_string(),
+        "i16" => i16::from_le_bytes(b.try_into().ok()?).to_string(),
+        "u32" => u32::from_le_bytes(b.try_into().ok()?).to_string(),
+        "i32" => i32::from_le_bytes(b.try_into().ok()?).to_string(),
+        "f32" => f32::from_le_bytes(b.try_into().ok()?).to_string(),
+        "u64" => u64::from_le_bytes(b.try_into().ok()?).to_string(),
+        "i64" => i64::from_le_bytes(b.try_into().ok()?).to_string(),
+        "f64" => f64::from_le_bytes(b.try_into().ok()?).to_string(),
+        "u128" => u128::from_le_bytes(b.try_into().ok()?).to_string(),
+        "i128" => i128::from_le_bytes(b.try_into().ok()?).to_string(),
+        "publicKey" | "pubkey" => Pubkey::try_from(b).ok()?.to_string(),
+        _ => return None,
+    })
+}
+
+/// Các đoạn byte khác nhau (gộp đoạn cách nhau < 8 byte), trên phần data chung của hai bên
+fn changed_ranges(a: &[u8], b: &[u8], skip: &BTreeSet<usize>) -> Vec<(usize, usize)> {
+    let mut out: Vec<(usize, usize)> = Vec::new();
+    for i in (0..a.len().min(b.len())).filter(|i| a[*i] != b[*i] && !skip.contains(i)) {
+        match out.last_mut() {
+            Some((_, end)) if i < *end + 8 => *end = i + 1,
+            _ => out.push((i, i + 1)),
+        }
+    }
+    out
+}
+
+/// Diff data: type (discriminator) đổi, field theo layout của type, còn lại hex theo đoạn
+fn data_changes(pre: &[u8], post: &[u8], layouts: &LayoutModel, out: &mut Vec<FieldChange>) {
+    if pre == post {
+        return;
+    }
+    let (t_pre, t_post) = (layouts.classify(pre), layouts.classify(post));
+    // 8 byte đầu = discriminator -> tên type ("-" nếu account không có data)
+    let name = |d: &[u8]| match d.get(..8) {
+        Some(disc) => layouts.name_of(disc.try_into().unwrap()),
+        None => "-".to_string(),
+    };
+    if pre.get(..8) != post.get(..8) {
+        out.push(FieldChange {
+            field: "type".into(),
+            before: name(pre),
+            after: name(post),
+        });
+    }
+    let mut decoded = BTreeSet::from_iter(0..8);
+    // cùng type ở hai bên (hoặc một bên chưa init / không có) -> decode theo layout đó
+    let layout = match (t_pre, t_post) {
+        (Some(a), Some(b)) if a.name != b.name => None,
+        (a, b) => b.or(a),
+    };
+    for f in layout.map_or(&[][..], |l| &l.fields) {
+        let r = f.offset..f.offset + f.size;
+        let (a, b) = (pre.get(r.clone()), post.get(r.clone()));
+        if a.is_none() && b.is_none() {
+            continue;
+        }
+        decoded.extend(r);
+        if a != b {
+            out.push(FieldChange {
+                field: format!("{}.{}", layout.unwrap().name, f.name),
+                before: a.map_or("-".into(), |x| decode(f, x)),
+                after: b.map_or("-".into(), |x| decode(f, x)),
+            });
+        }
+    }
+    for (s, e) in changed_ranges(pre, post, &decoded) {
+        out.push(FieldChange {
+            field: format!("data[{s}..{e}]"),
+            before: hex(&pre[s..e]),
+            after: hex(&post[s..e]),
+        });
+    }
+    // phần data chỉ một bên có (realloc, create, close), trừ field đã decode
+    let (common, longer) = if pre.len() < post.len() {
+        (pre.len(), post)
+    } else {
+        (post.len(), pre)
+    };
+    let tail: Vec<usize> = (common..longer.len())
+        .filter(|i| !decoded.contains(i))
+        .collect();
+    if let (Some(&s), Some(&e)) = (tail.first(), tail.last()) {
+        let bytes = hex(&longer[s..=e]);
+        let (before, after) = if pre.len() < post.len() {
+            ("-".into(), bytes)
+        } else {
+            (bytes, "-".into())
+        };
+        out.push(FieldChange {
+            field: format!("data[{s}..{}]", e + 1),
+            before,
+            after,
+        });
+    }
+    if pre.len() != post.len() {
+        out.push(FieldChange {
+            field: "data_len".into(),
+            before: pre.len().to_string(),
+            after: post.len().to_string(),
+        });
+    }
+}
+
+/// Các account khác nhau giữa hai snapshot (theo thứ tự pubkey), kèm vai trò, privilege trong `tx`
+/// và thay đổi đã decode
+pub fn account_diffs(
+    pre: &LedgerSnapshot,
+    post: &LedgerSnapshot,
+    tx: &Transaction,
+    roles: Roles,
+    layouts: &LayoutModel,
+) -> Vec<AccountDiff> {
+    let ixs = tx.instructions();
+    let writable = |k: &Pubkey| {
+        ixs.iter()
+            .flat_map(|ix| &ix.accounts)
+            .any(|m| m.pubkey == *k && m.is_writable)
+    };
+    let keys: BTreeSet<&Pubkey> = pre.accounts.keys().chain(post.accounts.keys()).collect();
+    keys.into_iter()
+        .filter_map(|k| {
+            let (a, b) = (pre.accounts.get(k), post.accounts.get(k));
+            let mut changes = Vec::new();
+            let (lam_a, lam_b) = (a.map_or(0, |x| x.lamports), b.map_or(0, |x| x.lamports));
+            if lam_a != lam_b {
+                changes.push(FieldChange {
+                    field: "lamports".into(),
+                    before: lam_a.to_string(),
+                    after: lam_b.to_string(),
+                });
+            }
+            if let (Some(x), Some(y)) = (a, b) {
+                if x.owner != y.owner {
+                    changes.push(FieldChange {
+                        field: "owner".into(),
+                        before: x.owner.to_string(),
+                        after: y.owner.to_string(),
+                    });
+                }
+            }
+            let empty = Vec::new();
+            let (da, db) = (a.map_or(&empty, |x| &x.data), b.map_or(&empty, |x| &x.data));
+            data_changes(da, db, layouts, &mut changes);
+            if changes.is_empty() && a.is_some() == b.is_some() {
+                return None;
+            }
+            let owner = a.or(b).map(|x| x.owner).unwrap_or_default();
+            Some(AccountDiff {
+                pubkey: k.to_string(),
+                role: role(k, &owner, roles).to_string(),
+                signer: tx.signers.contains(k),
+                writable: writable(k),
+                before: a.map(AccountState::new),
+                after: b.map(AccountState::new),
+                changes,
+            })
+        })
+        .collect()
+}
+
+fn clip(hex: &str) -> String {
+    if hex.len() > MAX_HEX_BYTES * 2 {
+        format!("{}.. ({} bytes)", &hex[..MAX_HEX_BYTES * 2], hex.len() / 2)
+    } else {
+        hex.to_string()
+    }
+}
+
+/// Bản text: chỉ account đã đổi, lamports có dấu, owner, field/data diff
+pub fn render(diffs: &[AccountDiff]) -> String {
+    if diffs.is_empty() {
+        return "(no account changed)".into();
+    }
+    let mut s = String::new();
+    for d in diffs {
+        let status = match (&d.before, &d.after) {
+            (None, Some(_)) => " created",
+            (Some(_), None) => " closed",
+            _ => "",
+        };
+        let owner = d
+            .before
+            .as_ref()
+            .or(d.after.as_ref())
+            .map(|a| a.owner.as_str())
+            .unwrap_or("?");
+        let mut tags = vec![d.role.clone()];
+        if d.role == "foreign-owned" {
+            tags.push(format!("owner {owner}"));
+        }
+        if d.signer {
+            tags.push("signer".into());
+        }
+        if d.writable {
+            tags.push("writable".into());
+        }
+        let _ = writeln!(s, "{} [{}]{status}", d.pubkey, tags.join(", "));
+        for c in &d.changes {
+            match c.field.as_str() {
+                "lamports" => {
+                    let (a, b): (i128, i128) =
+                        (c.before.parse().unwrap(), c.after.parse().unwrap());
+                    let _ = writeln!(s, "  lamports: {} -> {} ({:+})", c.before, c.after, b - a);
+                }
+                f if f.starts_with("data[") => {
+                    let _ = writeln!(s, "  {f}: {} -> {}", clip(&c.before), clip(&c.after));
+                }
+                f => {
+                    let _ = writeln!(s, "  {f}: {} -> {}", c.before, c.after);
+                }
+            }
+        }
+    }
+    s.truncate(s.trim_end().len());
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(ty: &str, size: usize) -> LayoutField {
+        LayoutField {
+            name: "f".into(),
+            ty: ty.into(),
+            offset: 0,
+            size,
+        }
+    }
+
+    #[test]
+    fn decode_known_types() {
+        assert_eq!(decode(&field("u64", 8), &42u64.to_le_bytes()), "42");
+        assert_eq!(decode(&field("i16", 2), &(-2i16).to_le_bytes()), "-2");
+        assert_eq!(decode(&field("bool", 1), &[1]), "true");
+        let key = Pubkey::new_unique();
+        assert_eq!(
+            decode(&field("publicKey", 32), key.as_ref()),
+            key.to_string()
+        );
+    }
+
+    #[test]
+    fn decode_falls_back_to_hex() {
+        // size của layout không khớp type -> hex, không panic
+        assert_eq!(decode(&field("u64", 4), &[1, 2, 3, 4]), hex(&[1, 2, 3, 4]));
+        assert_eq!(decode(&field("bool", 0), &[]), hex(&[]));
+        assert_eq!(decode(&field("pubkey", 3), &[9, 9, 9]), hex(&[9, 9, 9]));
+        assert_eq!(
+            decode(&field("string", 2), &[0xab, 0xcd]),
+            hex(&[0xab, 0xcd])
+        );
+    }
+
+    #[test]
+    fn changed_ranges_merges_and_skips() {
+        let a = [0u8; 32];
+        let mut b = a;
+        b[1] = 1;
+        b[7] = 1; // cách < 8 byte -> cùng đoạn
+        b[20] = 1;
+        assert_eq!(
+            changed_ranges(&a, &b, &BTreeSet::new()),
+            vec![(1, 8), (20, 21)]
+        );
+        // byte đã decode bị bỏ qua
+        assert_eq!(changed_ranges(&a, &b, &BTreeSet::from([20])), vec![(1, 8)]);
+        // chỉ so phần chung của hai bên
+        assert_eq!(changed_ranges(&a[..4], &b, &BTreeSet::new()), vec![(1, 2)]);
+        assert!(changed_ranges(&a, &a, &BTreeSet::new()).is_empty());
+    }
+}
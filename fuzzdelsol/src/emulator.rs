@@ -1,12 +1,61 @@
-use crate::types::{Account, ExtractedSemantics, LedgerSnapshot};
-use solana_sdk::{pubkey::Pubkey, system_program};
+use crate::layout::LayoutModel;
+use crate::token::{Mint, TokenAccount, TOKEN_PROGRAM_ID};
+use crate::types::{Account, ExtractedSemantics, LedgerSnapshot, Transaction};
+use solana_sdk::sysvar::instructions::{self, BorrowedAccountMeta, BorrowedInstruction};
+use solana_sdk::{clock::Clock, pubkey::Pubkey, rent::Rent, system_program, sysvar};
 use std::collections::BTreeMap;
+use std::{fs, io};
+
+/// rent_epoch của account rent-exempt (runtime không thu rent nữa)
+pub const RENT_EXEMPT_RENT_EPOCH: u64 = u64::MAX;
+
+/// Clock của các tx liên tiếp khi input không tự mang clock
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockSchedule {
+    /// mọi tx thấy cùng một clock
+    #[default]
+    Fixed,
+    /// mỗi tx tăng slot / unix_timestamp một bước
+    Monotonic { slot_step: u64, secs_step: i64 },
+}
 
 /// Blockchain Emulator (paper-aligned, lite)
 pub struct BlockchainEmulator {
     pub attacker: Pubkey,
     pub user: Pubkey,
     pub selectable_accounts: Vec<Pubkey>,
+    // pubkey hard-code trong ELF: pool riêng (có trong ledger, nằm sau pool gốc) để index
+    // của pool gốc không đổi theo ELF; TxGen chỉ chọn khi `dictionary_in_pool`
+    pub dictionary_accounts: Vec<Pubkey>,
+    pub dictionary_in_pool: bool,
+
+    // SPL token fixtures: 1 mint (authority = user) + token account cho attacker/user
+    pub token_mint: Pubkey,
+    pub attacker_token: Pubkey,
+    pub user_token: Pubkey,
+
+    // extra programs (CPI target) đưa vào ledger dưới program id chọn trước
+    pub programs: BTreeMap<Pubkey, Vec<u8>>,
+
+    // account types của program (default hoặc từ Anchor IDL); program-owned account
+    // được gán type xoay vòng
+    pub layouts: LayoutModel,
+
+    // Rent sysvar; mọi account trong snapshot đều rent-exempt
+    pub rent: Rent,
+    // clock hiện tại (ghi vào sysvar account, syscall đọc cùng giá trị);
+    // set_clock tính lại từ clock_base theo schedule ở tick hiện tại
+    pub clock: Clock,
+    pub clock_base: Clock,
+    pub clock_schedule: ClockSchedule,
+    pub clock_ticks: u64,
+
+    // sysvar look-alike (owner = attacker, data cùng format nhưng giá trị giả)
+    pub spoof_clock: Pubkey,
+    pub spoof_rent: Pubkey,
+
+    // program "verify" do attacker deploy (sibling instruction giả ed25519)
+    pub fake_verifier: Pubkey,
 
     // semantic feedback (lite)
     pub semantic_seed_hint: Vec<u8>,
@@ -26,15 +75,114 @@ impl BlockchainEmulator {
             selectable.push(Pubkey::new_unique());
         }
 
+        let token_mint = Pubkey::new_unique();
+        let attacker_token = Pubkey::new_unique();
+        let user_token = Pubkey::new_unique();
+        selectable.push(token_mint);
+        selectable.push(attacker_token);
+        selectable.push(user_token);
+
+        let spoof_clock = Pubkey::new_unique();
+        let spoof_rent = Pubkey::new_unique();
+        let fake_verifier = Pubkey::new_unique();
+
+        let clock = Clock {
+            slot: 1_000,
+            epoch_start_timestamp: 1_700_000_000,
+            epoch: 0,
+            leader_schedule_epoch: 1,
+            unix_timestamp: 1_700_000_400,
+        };
+
         Self {
             attacker,
             user,
             selectable_accounts: selectable,
+            dictionary_accounts: Vec::new(),
+            dictionary_in_pool: true,
+            token_mint,
+            attacker_token,
+            user_token,
+            programs: BTreeMap::new(),
+            layouts: LayoutModel::default(),
+            rent: Rent::default(),
+            clock: clock.clone(),
+            clock_base: clock,
+            clock_schedule: ClockSchedule::Fixed,
+            clock_ticks: 0,
+            spoof_clock,
+            spoof_rent,
+            fake_verifier,
             semantic_seed_hint: vec![],
             semantic_layout_hint: vec![],
         }
     }
 
+    /// Đăng ký một ELF `.so` làm program executable dưới `program_id` (CPI target).
+    /// TraceVM không chạy image này: CPI tới `program_id` mở frame callee mô phỏng
+    pub fn load_program(&mut self, program_id: Pubkey, path: &str) -> io::Result<()> {
+        let elf = fs::read(path)?;
+        if !elf.starts_with(b"\x7fELF") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path}: not an ELF file"),
+            ));
+        }
+        self.programs.insert(program_id, elf);
+        Ok(())
+    }
+
+    /// Thêm pubkey (vd hard-code trong ELF) vào pool dictionary (không đụng pool gốc)
+    pub fn add_keys(&mut self, keys: &[Pubkey]) {
+        for &k in keys {
+            if k != Pubkey::default()
+                && !self.selectable_accounts.contains(&k)
+                && !self.dictionary_accounts.contains(&k)
+            {
+                self.dictionary_accounts.push(k);
+            }
+        }
+    }
+
+    /// Account TxGen được chọn: pool gốc rồi tới pool dictionary (nếu bật)
+    pub fn pool_accounts(&self) -> impl Iterator<Item = &Pubkey> {
+        let dictionary: &[Pubkey] = if self.dictionary_in_pool {
+            &self.dictionary_accounts
+        } else {
+            &[]
+        };
+        self.selectable_accounts.iter().chain(dictionary)
+    }
+
+    /// Clock cho tx kế tiếp: `clock_override` (slot, unix_timestamp) lấy từ input,
+    /// không có thì theo schedule ở tick hiện tại (không tự tăng tick)
+    pub fn set_clock(&mut self, clock_override: Option<(u64, i64)>) {
+        let mut clock = self.clock_base.clone();
+        if let ClockSchedule::Monotonic {
+            slot_step,
+            secs_step,
+        } = self.clock_schedule
+        {
+            clock.slot = clock
+                .slot
+                .saturating_add(slot_step.saturating_mul(self.clock_ticks));
+            clock.unix_timestamp = clock
+                .unix_timestamp
+                .saturating_add(secs_step.saturating_mul(self.clock_ticks as i64));
+        }
+        if let Some((slot, unix_timestamp)) = clock_override {
+            clock.slot = slot;
+            clock.unix_timestamp = unix_timestamp;
+        }
+        self.clock = clock;
+    }
+
+    /// Monotonic: sang tick kế tiếp. Chỉ harness gọi sau mỗi lần thực thi được ghi nhận;
+    /// tracer/replay/cmin/minimize chạy với clock đứng yên
+    pub fn tick_clock(&mut self) {
+        self.clock_ticks += 1;
+    }
+
     /// Nhận semantic feedback từ evaluator
     pub fn update_semantics(&mut self, sem: &ExtractedSemantics) {
         if let Some(x) = &sem.new_pda_seed_hint {
@@ -56,9 +204,8 @@ impl BlockchainEmulator {
                 owner: system_program::id(),
                 lamports: 1_000_000_000,
                 data: vec![],
-                is_signer: true,
-                is_writable: true,
                 is_executable: false,
+                rent_epoch: RENT_EXEMPT_RENT_EPOCH,
             },
         );
 
@@ -68,9 +215,8 @@ impl BlockchainEmulator {
                 owner: system_program::id(),
                 lamports: 1_000_000_000,
                 data: vec![],
-                is_signer: true,
-                is_writable: true,
                 is_executable: false,
+                rent_epoch: RENT_EXEMPT_RENT_EPOCH,
             },
         );
 
@@ -79,17 +225,118 @@ impl BlockchainEmulator {
             program_id,
             Account {
                 owner: system_program::id(), // simplified
-                lamports: 1,
+                lamports: self.rent.minimum_balance(program_elf_bytes.len()),
                 data: program_elf_bytes.to_vec(),
-                is_signer: false,
-                is_writable: false,
                 is_executable: true,
+                rent_epoch: RENT_EXEMPT_RENT_EPOCH,
             },
         );
 
-        // 3) Other accounts: mix of honest + attacker-controlled owners
-        for &k in &self.selectable_accounts {
-            if k == self.attacker || k == self.user || k == program_id {
+        // 2b) extra programs (CPI target)
+        for (k, elf) in &self.programs {
+            if *k == program_id {
+                continue;
+            }
+            accounts.insert(
+                *k,
+                Account {
+                    owner: system_program::id(), // simplified
+                    lamports: self.rent.minimum_balance(elf.len()),
+                    data: elf.clone(),
+                    is_executable: true,
+                    rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+                },
+            );
+        }
+
+        // 2c) sysvars (Clock, Rent) dưới id chuẩn + look-alike của attacker
+        let fake_clock = Clock {
+            slot: u64::MAX / 2,
+            unix_timestamp: i64::MAX / 2,
+            ..self.clock.clone()
+        };
+        let fake_rent = Rent {
+            lamports_per_byte_year: 0,
+            ..self.rent
+        };
+        let sysvars = [
+            (
+                sysvar::clock::id(),
+                sysvar::id(),
+                bincode::serialize(&self.clock).unwrap(),
+            ),
+            (
+                sysvar::rent::id(),
+                sysvar::id(),
+                bincode::serialize(&self.rent).unwrap(),
+            ),
+            (
+                self.spoof_clock,
+                self.attacker,
+                bincode::serialize(&fake_clock).unwrap(),
+            ),
+            (
+                self.spoof_rent,
+                self.attacker,
+                bincode::serialize(&fake_rent).unwrap(),
+            ),
+        ];
+        for (k, owner, data) in sysvars {
+            accounts.insert(
+                k,
+                Account {
+                    owner,
+                    lamports: self.rent.minimum_balance(data.len()),
+                    data,
+                    is_executable: false,
+                    rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+                },
+            );
+        }
+
+        // 3) SPL token: mint + token accounts (owner = token program)
+        let token_fixtures = [
+            (
+                self.token_mint,
+                Mint {
+                    mint_authority: Some(self.user),
+                    supply: 2_000_000,
+                    decimals: 6,
+                    is_initialized: true,
+                    freeze_authority: None,
+                }
+                .pack(),
+            ),
+            (
+                self.attacker_token,
+                self.token_account(self.attacker, 1_000_000).pack(),
+            ),
+            (
+                self.user_token,
+                self.token_account(self.user, 1_000_000).pack(),
+            ),
+        ];
+        for (k, data) in token_fixtures {
+            accounts.insert(
+                k,
+                Account {
+                    owner: TOKEN_PROGRAM_ID,
+                    lamports: self.rent.minimum_balance(data.len()),
+                    data,
+                    is_executable: false,
+                    rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+                },
+            );
+        }
+
+        // 4) Other accounts: mix of honest + attacker-controlled owners
+        let ledger_keys = self
+            .selectable_accounts
+            .iter()
+            .chain(&self.dictionary_accounts);
+        for (n, &k) in ledger_keys.enumerate() {
+            if k == self.attacker || k == self.user || k == program_id || accounts.contains_key(&k)
+            {
                 continue;
             }
 
@@ -101,7 +348,8 @@ impl BlockchainEmulator {
                 program_id
             };
 
-            let mut data = vec![0u8; 64];
+            let layout = &self.layouts.layouts[n % self.layouts.layouts.len()];
+            let mut data = vec![0u8; layout.size.unwrap_or(0).max(64)];
 
             // seed hints affect initial data shape (semantic feedback loop)
             if !self.semantic_seed_hint.is_empty() {
@@ -119,19 +367,78 @@ impl BlockchainEmulator {
                 }
             }
 
+            // typed account: discriminator + authority = user
+            if owner == program_id {
+                data[..8].copy_from_slice(&layout.discriminator);
+                if let Some(off) = layout.authority_offset {
+                    if off + 32 <= data.len() {
+                        data[off..off + 32].copy_from_slice(self.user.as_ref());
+                    }
+                }
+            }
+
             accounts.insert(
                 k,
                 Account {
                     owner,
-                    lamports: 100_000_000,
+                    lamports: self.rent.minimum_balance(data.len()).max(100_000_000),
                     data,
-                    is_signer: false,
-                    is_writable: true,
                     is_executable: false,
+                    rent_epoch: RENT_EXEMPT_RENT_EPOCH,
                 },
             );
         }
 
         LedgerSnapshot { program_id, accounts }
     }
+
+    /// Instructions sysvar của tx (mọi instruction + current index), giống runtime build trước khi chạy
+    pub fn add_instructions_sysvar(&self, snap: &mut LedgerSnapshot, tx: &Transaction) {
+        let ixs = tx.instructions();
+        let borrowed: Vec<BorrowedInstruction> = ixs
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|m| BorrowedAccountMeta {
+                        pubkey: &m.pubkey,
+                        is_signer: m.is_signer,
+                        is_writable: m.is_writable,
+                    })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = instructions::construct_instructions_data(&borrowed);
+        instructions::store_current_index(
+            &mut data,
+            tx.instruction_index.min(ixs.len() - 1) as u16,
+        );
+
+        snap.accounts.insert(
+            instructions::id(),
+            Account {
+                owner: sysvar::id(),
+                lamports: self.rent.minimum_balance(data.len()),
+                data,
+                is_executable: false,
+                rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+            },
+        );
+    }
+
+    fn token_account(&self, owner: Pubkey, amount: u64) -> TokenAccount {
+        TokenAccount {
+            mint: self.token_mint,
+            owner,
+            amount,
+            delegate: None,
+            state: 1, // Initialized
+            is_native: None,
+            delegated_amount: 0,
+            close_authority: None,
+        }
+    }
 }
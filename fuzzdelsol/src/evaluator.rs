@@ -1,31 +1,52 @@
-use crate::types::{hex, snapshot_to_string, ExecResult, ExtractedSemantics, VulnReport};
+use crate::types::{hex, trace_excerpt, ExecResult, ExtractedSemantics, VulnReport};
 
 pub struct TransactionEvaluator;
 
 pub struct EvalOutcome {
     #[allow(dead_code)]
     pub is_new_coverage: bool,
+    /// input có cost ước lượng cao nhất từ trước tới giờ (feedback tìm input đắt)
+    pub is_new_max_cu: bool,
     pub is_objective: bool,
     pub report: Option<VulnReport>,
     pub semantics: ExtractedSemantics,
 }
 
 impl TransactionEvaluator {
-    pub fn evaluate(exec: &ExecResult, best_cov_hash: &mut u64) -> EvalOutcome {
+    pub fn evaluate(exec: &ExecResult, best_cov_hash: &mut u64, max_cu: &mut u64) -> EvalOutcome {
         let cov_hash = exec.coverage.hash16();
         let is_new_coverage = cov_hash != *best_cov_hash;
         if is_new_coverage {
             *best_cov_hash = cov_hash;
         }
 
+        // chỉ tính run chạy hết (run vượt budget đã là finding DOS)
+        let is_new_max_cu = !exec.signals.dos && exec.compute_units > *max_cu;
+        if is_new_max_cu {
+            *max_cu = exec.compute_units;
+        }
+
         let is_objective = exec.signals.any();
         let report = if is_objective {
             Some(VulnReport {
                 vuln_class: exec.signals.class().to_string(),
+                bucket: None,
+                oracles: exec.signals.triggered().into_iter().map(String::from).collect(),
                 tx_payload_hex: hex(&exec.tx.instruction.data),
-                global_state_before: snapshot_to_string(&exec.pre_snapshot),
-                global_state_after: snapshot_to_string(&exec.post_snapshot),
+                details: exec.signals.details.clone(),
+                clock: format!(
+                    "slot={} unix_timestamp={} epoch={}",
+                    exec.clock.slot, exec.clock.unix_timestamp, exec.clock.epoch
+                ),
+                account_diffs: Vec::new(),
                 trace_summary: exec.trace_summary.clone(),
+                trace_excerpt: trace_excerpt(&exec.events),
+                input_hex: None,
+                seed: None,
+                transaction: None,
+                instruction: None,
+                location: None,
+                export: None,
             })
         } else {
             None
@@ -43,6 +64,12 @@ impl TransactionEvaluator {
             semantics.new_account_layout_hint = Some(exec.tx.instruction.data.iter().rev().take(8).copied().collect());
         }
 
-        EvalOutcome { is_new_coverage, is_objective, report, semantics }
+        EvalOutcome {
+            is_new_coverage,
+            is_new_max_cu,
+            is_objective,
+            report,
+            semantics,
+        }
     }
 }
@@ -0,0 +1,329 @@
+use crate::txfile::{Roles, TxFile};
+use crate::types::ExecResult;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::instruction::{AccountMeta, Instruction as SdkInstruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction as SdkTransaction;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+/// Key thật thay cho key placeholder của fuzzer, theo label ("attacker", "user", "program",
+/// "victim_1", ...) hoặc theo chính placeholder pubkey
+#[derive(Clone, Debug, Default)]
+pub struct KeyMap {
+    by_label: BTreeMap<String, Pubkey>,
+    by_key: BTreeMap<Pubkey, Pubkey>,
+}
+
+impl KeyMap {
+    /// JSON object: { "attacker": "<pubkey>", "victim_1": "<pubkey>", "<placeholder>": "<pubkey>" }
+    pub fn load(path: &str) -> io::Result<Self> {
+        let v: Value = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path}: {e}")))?;
+        let obj = v.as_object().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path}: expected an object"),
+            )
+        })?;
+
+        let mut map = Self::default();
+        for (name, real) in obj {
+            let real = real
+                .as_str()
+                .and_then(|s| Pubkey::from_str(s).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{path}: bad pubkey for `{name}`"),
+                    )
+                })?;
+            match Pubkey::from_str(name) {
+                Ok(placeholder) => map.by_key.insert(placeholder, real),
+                Err(_) => map.by_label.insert(name.clone(), real),
+            };
+        }
+        Ok(map)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyMapEntry {
+    pub label: String,
+    pub placeholder: String,
+    pub real: String,
+}
+
+/// Account pre-state theo format `solana account --output json`, nạp bằng
+/// `solana-test-validator --account <pubkey> <file.json>`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidatorAccount {
+    pub pubkey: String,
+    pub account: ValidatorAccountData,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorAccountData {
+    pub lamports: u64,
+    /// [data base64, "base64"]
+    pub data: (String, String),
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub space: u64,
+}
+
+/// Finding dưới dạng tx thật: message đã compile + tx chưa ký, base64 (bincode) như CLI in ra,
+/// kèm pre-state account (key đã remap) để dựng lại trên test validator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SolanaExport {
+    pub fee_payer: String,
+    pub message_base64: String,
+    pub transaction_base64: String,
+    pub key_map: Vec<KeyMapEntry>,
+    #[serde(default)]
+    pub accounts: Vec<ValidatorAccount>,
+}
+
+impl SolanaExport {
+    pub fn to_text(&self) -> String {
+        let keys: Vec<String> = self
+            .key_map
+            .iter()
+            .map(|e| format!("{:<10} {} -> {}", e.label, e.placeholder, e.real))
+            .collect();
+        let accounts: Vec<String> = self
+            .accounts
+            .iter()
+            .map(|a| format!("  --account {} {}.json", a.pubkey, a.pubkey))
+            .collect();
+        format!(
+            "fee payer: {}\nmessage (base64): {}\ntransaction (base64, unsigned): {}\nkey map:\n{}\npre-state (solana-test-validator, files in <finding>.accounts/):\n{}",
+            self.fee_payer,
+            self.message_base64,
+            self.transaction_base64,
+            keys.join("\n"),
+            accounts.join("\n")
+        )
+    }
+
+    /// Ghi mỗi account pre-state vào `<dir>/<pubkey>.json`
+    pub fn write_accounts(&self, dir: &str) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for a in &self.accounts {
+            fs::write(
+                format!("{dir}/{}.json", a.pubkey),
+                serde_json::to_string_pretty(a).unwrap(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Label cho mọi key tx tham chiếu: vai trò emulator, account program sở hữu là "victim_N",
+/// còn lại "account_N"
+pub(crate) fn labels(exec: &ExecResult, roles: Roles) -> Vec<(String, Pubkey)> {
+    let mut out = vec![
+        ("program".to_string(), roles.program_id),
+        ("attacker".to_string(), roles.attacker),
+        ("user".to_string(), roles.user),
+    ];
+    let (mut victims, mut others) = (0, 0);
+    for ix in exec.tx.instructions() {
+        for k in std::iter::once(ix.program_id).chain(ix.accounts.iter().map(|m| m.pubkey)) {
+            if out.iter().any(|(_, x)| *x == k) {
+                continue;
+            }
+            let owned =
+                exec.pre_snapshot.accounts.get(&k).map(|a| a.owner) == Some(roles.program_id);
+            let label = if owned {
+                victims += 1;
+                format!("victim_{victims}")
+            } else {
+                others += 1;
+                format!("account_{others}")
+            };
+            out.push((label, k));
+        }
+    }
+    out
+}
+
+/// Compile tx của finding thành `Message` + `Transaction` chưa ký; fee payer là attacker
+/// (người replay trên test validator). Key placeholder trong ix data (vd pubkey trong
+/// ed25519 verify) cũng được thay
+pub fn export(exec: &ExecResult, roles: Roles, key_map: &KeyMap) -> SolanaExport {
+    let entries: Vec<KeyMapEntry> = labels(exec, roles)
+        .into_iter()
+        .map(|(label, k)| {
+            let real = key_map
+                .by_key
+                .get(&k)
+                .or_else(|| key_map.by_label.get(&label))
+                .copied()
+                .unwrap_or(k);
+            KeyMapEntry {
+                label,
+                placeholder: k.to_string(),
+                real: real.to_string(),
+            }
+        })
+        .collect();
+    let remap: BTreeMap<Pubkey, Pubkey> = entries
+        .iter()
+        .map(|e| {
+            (
+                Pubkey::from_str(&e.placeholder).unwrap(),
+                Pubkey::from_str(&e.real).unwrap(),
+            )
+        })
+        .collect();
+    let key = |k: &Pubkey| remap.get(k).copied().unwrap_or(*k);
+    // key placeholder trong data (ix data, authority lưu trong account...)
+    let remap_data = |mut data: Vec<u8>| {
+        for (from, to) in remap.iter().filter(|(a, b)| a != b) {
+            let mut i = 0;
+            while i + 32 <= data.len() {
+                if data[i..i + 32] == from.to_bytes() {
+                    data[i..i + 32].copy_from_slice(to.as_ref());
+                    i += 32;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        data
+    };
+
+    let ixs: Vec<SdkInstruction> = exec
+        .tx
+        .instructions()
+        .into_iter()
+        .map(|ix| {
+            let data = remap_data(ix.data.clone());
+            SdkInstruction {
+                program_id: key(&ix.program_id),
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|m| AccountMeta {
+                        pubkey: key(&m.pubkey),
+                        is_signer: m.is_signer,
+                        is_writable: m.is_writable,
+                    })
+                    .collect(),
+                data,
+            }
+        })
+        .collect();
+
+    // pre-state giống account_overrides của typed file
+    let accounts = TxFile::from_exec(exec, roles, None)
+        .account_overrides
+        .into_iter()
+        .filter_map(|a| {
+            let data = remap_data(::hex::decode(&a.data).ok()?);
+            Some(ValidatorAccount {
+                pubkey: key(&Pubkey::from_str(&a.pubkey).ok()?).to_string(),
+                account: ValidatorAccountData {
+                    lamports: a.lamports,
+                    space: data.len() as u64,
+                    data: (BASE64.encode(&data), "base64".to_string()),
+                    owner: key(&Pubkey::from_str(&a.owner).ok()?).to_string(),
+                    executable: a.executable,
+                    rent_epoch: a.rent_epoch,
+                },
+            })
+        })
+        .collect();
+
+    let payer = key(&roles.attacker);
+    let message = Message::new(&ixs, Some(&payer));
+    let tx = SdkTransaction::new_unsigned(message.clone());
+    SolanaExport {
+        fee_payer: payer.to_string(),
+        message_base64: BASE64.encode(message.serialize()),
+        transaction_base64: BASE64.encode(bincode::serialize(&tx).unwrap()),
+        key_map: entries,
+        accounts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzer_libafl::{FuzzConfig, FuzzTarget};
+
+    fn contains(data: &[u8], k: &Pubkey) -> bool {
+        data.windows(32).any(|w| w == k.as_ref())
+    }
+
+    #[test]
+    fn key_map_remaps_keys_and_data() {
+        let mut t = FuzzTarget::setup(&FuzzConfig::default()).unwrap();
+        let roles = t.roles();
+        // attacker ký + sibling ed25519 verify: pubkey attacker nằm trong ix data
+        let exec = t.execute(&[3, 0x11, 0x22, 1, 2, 3, 0xaa]);
+        let (attacker, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut key_map = KeyMap::default();
+        key_map.by_label.insert("attacker".into(), attacker);
+        key_map.by_key.insert(roles.user, user);
+
+        let e = export(&exec, roles, &key_map);
+        assert_eq!(e.fee_payer, attacker.to_string());
+        let entry = |label: &str| e.key_map.iter().find(|x| x.label == label).unwrap();
+        assert_eq!(entry("attacker").real, attacker.to_string());
+        assert_eq!(entry("user").real, user.to_string());
+        assert_eq!(entry("program").real, roles.program_id.to_string());
+
+        let message: Message =
+            bincode::deserialize(&BASE64.decode(&e.message_base64).unwrap()).unwrap();
+        assert!(message.account_keys.contains(&attacker));
+        assert!(!message.account_keys.contains(&roles.attacker));
+        assert!(message
+            .instructions
+            .iter()
+            .any(|ix| contains(&ix.data, &attacker)));
+        assert!(message
+            .instructions
+            .iter()
+            .all(|ix| !contains(&ix.data, &roles.attacker)));
+
+        // có account lưu key của vai trò trong data (authority)
+        let overrides = TxFile::from_exec(&exec, roles, None).account_overrides;
+        assert!(overrides.iter().any(|a| {
+            let data = ::hex::decode(&a.data).unwrap();
+            contains(&data, &roles.attacker) || contains(&data, &roles.user)
+        }));
+        for a in &e.accounts {
+            let data = BASE64.decode(&a.account.data.0).unwrap();
+            assert!(!contains(&data, &roles.attacker) && !contains(&data, &roles.user));
+            assert_ne!(a.pubkey, roles.attacker.to_string());
+        }
+    }
+
+    #[test]
+    fn key_map_load() {
+        let (real, placeholder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let path = std::env::temp_dir().join(format!("fuzzdelsol-keymap-{placeholder}.json"));
+        fs::write(
+            &path,
+            format!("{{\"attacker\": \"{real}\", \"{placeholder}\": \"{real}\"}}"),
+        )
+        .unwrap();
+        let map = KeyMap::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(map.by_label.get("attacker"), Some(&real));
+        assert_eq!(map.by_key.get(&placeholder), Some(&real));
+
+        fs::write(&path, "{\"attacker\": \"not a key\"}").unwrap();
+        assert!(KeyMap::load(path.to_str().unwrap()).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,29 +1,77 @@
+use crate::cmin::collect_files;
+use crate::compute::{DEFAULT_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT};
+use crate::debuginfo::SourceMap;
+use crate::dictionary::Dictionary;
+use crate::diff::account_diffs;
+use crate::emulator::{BlockchainEmulator, ClockSchedule};
 use crate::evaluator::TransactionEvaluator;
-use crate::oracles::{OracleContext, Oracles};
-use crate::emulator::BlockchainEmulator;
+use crate::export::{export, KeyMap};
+use crate::layout::LayoutModel;
+use crate::mutators::{
+    AccountInsertMutator, AccountRemoveMutator, AccountSwapMutator, DataMutator, DataSpliceMutator,
+    SignerFlipMutator, WritableToggleMutator,
+};
+use crate::oracles::{OracleContext, Oracles, VmEvent};
+use crate::repro::repro_harness;
+use crate::sarif::sarif_log;
+use crate::triage::{bucket, stable_hash, Bucket};
+use crate::txfile::{Roles, TxFile};
 use crate::txgen::TxGenerator;
-use crate::types::{ExecResult, OracleSignals};
-use crate::vm_rbpf::TraceVm;
+use crate::types::{hex, Account, ExecResult, OracleSignals, Transaction, VulnReport};
+use crate::vm_rbpf::{TraceVm, VmConfig, OPS_VERSION};
+use libafl::corpus::{Corpus, InMemoryCorpus};
+use libafl::events::{
+    Event, EventConfig, EventFirer, EventManager, EventRestarter, Launcher,
+    LlmpRestartingEventManager, NopEventManager,
+};
+use libafl::executors::inprocess::OwnedInProcessExecutor;
+use libafl::executors::ExitKind;
+use libafl::feedbacks::Feedback;
+use libafl::fuzzer::{Evaluator, Fuzzer, StdFuzzer};
+use libafl::inputs::{BytesInput, HasTargetBytes, UsesInput};
+use libafl::monitors::SimpleMonitor;
+use libafl::mutators::{
+    havoc_mutations, tokens_mutations, I2SRandReplace, StdScheduledMutator, Tokens,
+};
+use libafl::observers::{CmpValues, CmpValuesMetadata, CmplogBytes, Observer, ObserversTuple};
+use libafl::schedulers::QueueScheduler;
+use libafl::stages::{StdMutationalStage, TracingStage};
+use libafl::state::{HasCorpus, HasExecutions, HasMaxSize, State, StdState};
+use libafl::HasMetadata;
+use libafl_bolts::core_affinity::{get_core_ids, CoreId, Cores};
+use libafl_bolts::rands::StdRand;
+use libafl_bolts::shmem::{ShMemProvider, StdShMemProvider};
+use libafl_bolts::tuples::{tuple_list, Merge};
+use libafl_bolts::{current_nanos, current_time, AsSlice, Named};
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
-fn mutate_bytes(seed: &[u8], iter: u64) -> Vec<u8> {
-    let mut out = seed.to_vec();
-    if out.is_empty() {
-        out = vec![2, 3, 0, 1, 2, 3, 4, 5];
-    }
-    let n = (iter as usize % 8) + 1;
-    for k in 0..n {
-        let i = (iter as usize + k * 13) % out.len();
-        out[i] ^= (iter as u8).wrapping_mul(31).wrapping_add(k as u8);
-    }
-    if (iter % 7) == 0 && out.len() < 256 {
-        out.push((iter as u8) ^ 0xAA);
-    }
-    out
-}
+/// Kích thước edge map (= CoverageMap của TraceVM)
+const EDGES_MAP_SIZE: usize = 64 * 1024;
+const MAX_INPUT_LEN: usize = 512;
+/// SARIF log của cả campaign (ghi lại sau mỗi bucket mới)
+const SARIF_PATH: &str = "findings.sarif";
+/// bucket root cause + số lần hit
+const TRIAGE_PATH: &str = "triage.json";
+/// input mở coverage mới (raw), đầu vào cho `cmin`
+const CORPUS_DIR: &str = "corpus";
+/// multi-core: claim bucket + stats của từng client (`clients/<seed>/`)
+const CLIENTS_DIR: &str = "clients";
+/// port LLMP broker của chế độ multi-core
+pub const BROKER_PORT: u16 = 1337;
+
+type FuzzState =
+    StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+type FuzzerType = StdFuzzer<QueueScheduler<FuzzState>, NoveltyFeedback, BucketFeedback>;
+type MainExecutor = OwnedInProcessExecutor<(), FuzzState>;
 
 fn write_artifact(dir: &str, name_hex: &str, bytes: &[u8]) -> io::Result<()> {
     fs::create_dir_all(dir)?;
@@ -31,30 +79,231 @@ fn write_artifact(dir: &str, name_hex: &str, bytes: &[u8]) -> io::Result<()> {
     Ok(())
 }
 
-pub fn run_fuzzdelsol(iters: u64, elf_path: &str) -> io::Result<()> {
-    println!("[*] Fuzzing for {iters} iterations...");
-    println!("[*] ELF = {elf_path}");
-    println!("[*] crashes_dir = crashes");
-    println!("[*] bugs_dir    = bugs");
+/// Coverage dừng tăng: không có edge (hoặc bucket hit count) mới trong N lần thực thi / một khoảng thời gian
+#[derive(Clone, Copy, Debug)]
+pub enum Plateau {
+    Execs(u64),
+    Time(Duration),
+}
 
-    let elf_bytes = fs::read(elf_path).unwrap_or_else(|_| vec![]);
-    let program_id = Pubkey::new_unique();
+/// Điều kiện dừng campaign ngoài `iters` (multi-core: mỗi client tự xét)
+#[derive(Clone, Debug, Default)]
+pub struct StopConditions {
+    /// thời gian chạy tối đa
+    pub duration: Option<Duration>,
+    /// (class, N): dừng khi class có N bucket unique; class None = class bất kỳ.
+    /// Multi-core: đếm bucket của mọi client (file claim trong run dir)
+    pub findings: Vec<(Option<String>, usize)>,
+    /// multi-core: tính theo coverage riêng của từng client
+    pub plateau: Option<Plateau>,
+}
 
-    let mut emu = BlockchainEmulator::new();
+/// 90s / 30m / 2h
+pub fn fmt_duration(d: Duration) -> String {
+    match d.as_secs() {
+        s if s > 0 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s > 0 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
 
-    let seed = vec![2, 3, 0, 1, 2, 3, 0x10, 0x22, 0x80, 0xFF, 0x7F, 0x01];
-    let mut best_cov_hash: u64 = 0;
+/// Tham số một campaign
+#[derive(Clone, Debug, Default)]
+pub struct FuzzConfig {
+    /// số lần thực thi (0 = không giới hạn, dừng theo `stop`)
+    pub iters: u64,
+    pub elf_path: String,
+    /// (program_id, path .so) đăng ký làm CPI target executable (TraceVM không chạy image)
+    pub extra_programs: Vec<(Pubkey, String)>,
+    /// Anchor IDL cho layout model (None = Vault/User mặc định)
+    pub idl_path: Option<String>,
+    /// clock khi input không mang clock trailer
+    pub clock_schedule: ClockSchedule,
+    /// CU budget của instruction (0 = mặc định 200k, tối đa 1.4M)
+    pub compute_budget: u64,
+    /// encoding trace op của input (None = `OPS_VERSION`)
+    pub ops_version: Option<u8>,
+    /// dictionary của user (AFL format), thêm vào token trích từ ELF
+    pub dict_path: Option<String>,
+    /// thư mục seed (raw legacy hoặc typed `.tx.json`, vd `corpus/` của run trước); rỗng = seed mặc định
+    pub seed_dirs: Vec<String>,
+    /// key thật cho tx export (label/placeholder -> pubkey)
+    pub keymap_path: Option<String>,
+    /// multi-core: danh sách core của Launcher (vd "0-31", "all"); None = một thread
+    pub cores: Option<String>,
+    /// multi-core: port TCP của LLMP broker (None = BROKER_PORT)
+    pub broker_port: Option<u16>,
+    /// seed StdRand (None = current_nanos), ghi vào report; multi-core: client = seed + core id
+    pub seed: Option<u64>,
+    pub stop: StopConditions,
+}
 
-    let mut executions: u64 = 0;
-    let mut new_crash_inputs: u64 = 0;
+/// Program + emulator đã setup; dùng chung cho fuzz loop và replay
+pub struct FuzzTarget {
+    pub program_id: Pubkey,
+    /// tên program (stem của file .so), dùng cho `ProgramTest::add_program` trong repro harness
+    pub program_name: String,
+    pub elf_path: String,
+    pub elf_bytes: Vec<u8>,
+    pub emu: BlockchainEmulator,
+    pub vm: VmConfig,
+    pub dict: Dictionary,
+    pub key_map: KeyMap,
+    /// handler -> file source (DWARF, mức function) cho report/SARIF
+    pub source_map: SourceMap,
+}
 
-    for i in 0..iters {
-        // ---------- build blockchain snapshot ----------
-        let pre_snapshot = emu.build_snapshot(program_id, &elf_bytes);
+impl FuzzTarget {
+    /// Thứ tự tạo key (program_id rồi emulator) cố định để replay ra cùng key
+    pub fn setup(cfg: &FuzzConfig) -> io::Result<Self> {
+        let elf_bytes = fs::read(&cfg.elf_path).unwrap_or_else(|_| vec![]);
+        let program_id = Pubkey::new_unique();
 
+        let mut emu = BlockchainEmulator::new();
+        // pubkey hard-code trong ELF (program id, admin...) -> account cho tx dùng
+        let dict = Dictionary::from_elf(&elf_bytes);
+        emu.add_keys(&dict.keys);
+        let source_map = SourceMap::from_elf(&elf_bytes);
+        for (id, path) in &cfg.extra_programs {
+            emu.load_program(*id, path)?;
+            println!("[*] CPI target {id} = {path} (modeled frame, image not executed)");
+        }
+        if let Some(idl) = &cfg.idl_path {
+            emu.layouts = LayoutModel::from_anchor_idl(idl)?;
+            println!(
+                "[*] IDL = {idl} ({} account types)",
+                emu.layouts.layouts.len()
+            );
+        }
+        emu.clock_schedule = cfg.clock_schedule;
+        let ops_version = cfg.ops_version.unwrap_or(OPS_VERSION);
+        emu.dictionary_in_pool = ops_version >= 1;
+
+        let compute_budget = match cfg.compute_budget {
+            0 => DEFAULT_COMPUTE_UNIT_LIMIT,
+            n => n.min(MAX_COMPUTE_UNIT_LIMIT),
+        };
+
+        let vm = VmConfig {
+            compute_budget,
+            sighashes: emu.layouts.instructions.iter().map(|(_, d)| *d).collect(),
+            layouts: emu.layouts.clone(),
+            rent: emu.rent,
+            ops_version,
+        };
+
+        let key_map = match &cfg.keymap_path {
+            Some(path) => KeyMap::load(path)?,
+            None => KeyMap::default(),
+        };
+
+        let program_name = Path::new(&cfg.elf_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "program".to_string());
+
+        Ok(Self {
+            program_id,
+            program_name,
+            elf_path: cfg.elf_path.clone(),
+            elf_bytes,
+            emu,
+            vm,
+            dict,
+            key_map,
+            source_map,
+        })
+    }
+
+    /// Một lần thực thi: input -> tx -> snapshot -> TraceVM -> oracles
+    pub fn execute(&mut self, input: &[u8]) -> ExecResult {
         // ---------- generate tx ----------
-        let input = mutate_bytes(&seed, i);
-        let tx = TxGenerator::from_bytes(&input, &emu, program_id);
+        let tx = TxGenerator::from_bytes(input, &self.emu, self.program_id);
+        self.execute_tx(tx, &[])
+    }
+
+    /// Input trên đĩa: typed file (`TxFile`) hoặc raw legacy
+    pub fn execute_file(&mut self, bytes: &[u8]) -> io::Result<ExecResult> {
+        match TxFile::from_json(bytes) {
+            Some(file) => {
+                let file = file?;
+                let (tx, overrides) = file.to_transaction(self.roles())?;
+                // typed file mang encoding trace op của nó
+                let saved = std::mem::replace(&mut self.vm.ops_version, file.ops_version);
+                let exec = self.execute_tx(tx, &overrides);
+                self.vm.ops_version = saved;
+                Ok(exec)
+            }
+            None => Ok(self.execute(bytes)),
+        }
+    }
+
+    /// Điền phần report evaluator không biết: diff account theo vai trò/layout, input, seed,
+    /// tx decode, source location, tx thật (Message/Transaction base64)
+    pub fn finish_report(
+        &self,
+        exec: &ExecResult,
+        mut report: VulnReport,
+        raw: Option<&[u8]>,
+        seed: Option<u64>,
+    ) -> VulnReport {
+        report.bucket = Some(self.bucket(exec));
+        report.account_diffs = account_diffs(
+            &exec.pre_snapshot,
+            &exec.post_snapshot,
+            &exec.tx,
+            self.roles(),
+            &self.emu.layouts,
+        );
+        report.input_hex = raw.map(hex);
+        report.seed = seed;
+        report.transaction = Some(TxFile::from_exec(exec, self.roles(), raw));
+        report.instruction = self.dispatched_instruction(exec);
+        report.location = report
+            .instruction
+            .iter()
+            .map(String::as_str)
+            .chain(["process_instruction", "entrypoint"])
+            .find_map(|f| self.source_map.function(f))
+            .cloned();
+        report.export = Some(export(exec, self.roles(), &self.key_map));
+        report
+    }
+
+    /// Bucket root cause (signature kèm instruction đã dispatch)
+    pub fn bucket(&self, exec: &ExecResult) -> Bucket {
+        bucket(
+            exec,
+            self.roles(),
+            self.dispatched_instruction(exec).as_deref(),
+        )
+    }
+
+    /// Instruction (IDL) mà Anchor dispatch đã chọn: sighash khớp trong sol_memcmp
+    fn dispatched_instruction(&self, exec: &ExecResult) -> Option<String> {
+        exec.events.iter().find_map(|ev| match &ev.event {
+            VmEvent::MemCmp { lhs, rhs } if ev.program_id == self.program_id && lhs == rhs => self
+                .emu
+                .layouts
+                .instructions
+                .iter()
+                .find(|(_, d)| d[..] == rhs[..])
+                .map(|(name, _)| name.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn roles(&self) -> Roles {
+        Roles {
+            program_id: self.program_id,
+            attacker: self.emu.attacker,
+            user: self.emu.user,
+        }
+    }
+
+    /// Chạy một tx cụ thể; `overrides` thay account emulator tạo trong snapshot
+    pub fn execute_tx(&mut self, tx: Transaction, overrides: &[(Pubkey, Account)]) -> ExecResult {
+        let program_id = self.program_id;
 
         // ✅ USE instruction.program_id (fix warning correctly)
         debug_assert_eq!(
@@ -63,27 +312,30 @@ pub fn run_fuzzdelsol(iters: u64, elf_path: &str) -> io::Result<()> {
             "Instruction targets wrong program_id"
         );
 
+        // ---------- build blockchain snapshot ----------
+        self.emu.set_clock(tx.clock_override);
+        let mut pre_snapshot = self.emu.build_snapshot(program_id, &self.elf_bytes);
+        for (k, a) in overrides {
+            pre_snapshot.accounts.insert(*k, a.clone());
+        }
+        self.emu.add_instructions_sysvar(&mut pre_snapshot, &tx);
+
         // ---------- run VM ----------
-        let vm_out = TraceVm::run(program_id, pre_snapshot.clone(), &tx);
-
-        let mut signals = OracleSignals {
-            msc: false,
-            moc: false,
-            acpi: false,
-            mkc: false,
-            ib: false,
-            lamports_theft: false,
-        };
+        let vm_out = TraceVm::run(program_id, pre_snapshot.clone(), &tx, &self.vm);
+
+        let mut signals = OracleSignals::default();
 
         let ctx = OracleContext {
             program_id,
-            attacker: emu.attacker,
-            user: emu.user,
+            attacker: self.emu.attacker,
+            user: self.emu.user,
+            layouts: self.emu.layouts.clone(),
+            rent: self.emu.rent,
         };
 
         let mut oracles = Oracles::new(ctx, &pre_snapshot);
 
-        for ev in vm_out.events.clone() {
+        for ev in vm_out.events.iter().cloned() {
             oracles.process_event(
                 &tx,
                 &vm_out.taint,
@@ -97,72 +349,1079 @@ pub fn run_fuzzdelsol(iters: u64, elf_path: &str) -> io::Result<()> {
         // finalize oracle decisions (paper-style)
         oracles.finalize(&tx, &pre_snapshot, &vm_out.post_snapshot, &mut signals);
 
-        let exec = ExecResult {
+        ExecResult {
             coverage: vm_out.coverage,
-            signals: signals.clone(),
+            signals,
             semantics: Default::default(),
-            tx: tx.clone(),
-            pre_snapshot: pre_snapshot.clone(),
-            post_snapshot: vm_out.post_snapshot.clone(),
-            trace_summary: vm_out.trace_summary.clone(),
+            tx,
+            pre_snapshot,
+            post_snapshot: vm_out.post_snapshot,
+            trace_summary: vm_out.trace_summary,
+            clock: self.emu.clock.clone(),
+            compute_units: vm_out.compute_units,
+            events: vm_out.events,
+            ops_version: self.vm.ops_version,
+        }
+    }
+}
+
+/// Thống kê + artifact của campaign, cập nhật trong harness
+struct Campaign {
+    /// seed StdRand của campaign (ghi vào report)
+    seed: u64,
+    executions: u64,
+    new_crash_inputs: u64,
+    best_cov_hash: u64,
+    max_cu: u64,
+    // bucket hit count lớn nhất từng thấy của mỗi edge (như MaxMapFeedback)
+    virgin: Vec<u8>,
+    // bucket root cause -> representative (finding đầu tiên) + số lần hit
+    buckets: BTreeMap<String, BucketEntry>,
+    /// multi-core: thư mục claim bucket / stats của client (None = single-thread)
+    run_dir: Option<String>,
+    /// multi-core: core của client (tên stats file)
+    core: usize,
+    /// lần chạy vừa xong mở bucket mới (client này ghi representative)
+    new_bucket: bool,
+    started: Instant,
+    /// (executions, thời điểm) của lần có coverage mới gần nhất
+    last_new_cov: (u64, Instant),
+}
+
+/// Một bucket trong triage
+#[derive(Clone, Serialize, Deserialize)]
+struct BucketEntry {
+    #[serde(flatten)]
+    bucket: Bucket,
+    hits: u64,
+    /// artifact của representative (dir/id, không đuôi)
+    representative: String,
+    /// seed của client đã ghi representative (multi-core: None nếu client khác ghi)
+    seed: Option<u64>,
+    #[serde(skip)]
+    report: Option<VulnReport>,
+}
+
+/// Kết quả một client multi-core (`clients/<seed>/<core>.json`), parent gộp khi kết thúc
+#[derive(Serialize, Deserialize)]
+struct ClientStats {
+    seed: u64,
+    executions: u64,
+    new_crash_inputs: u64,
+    max_cu: u64,
+    corpus: usize,
+    /// điều kiện đã dừng client ("running" khi client còn chạy / bị kill)
+    stopped: String,
+    /// thời gian client đã chạy (cộng dồn qua các lần respawn)
+    #[serde(default)]
+    elapsed_ms: u64,
+    buckets: Vec<BucketEntry>,
+}
+
+impl Campaign {
+    fn new(seed: u64, run_dir: Option<String>) -> Self {
+        Self {
+            seed,
+            executions: 0,
+            new_crash_inputs: 0,
+            best_cov_hash: 0,
+            max_cu: 0,
+            virgin: vec![0; EDGES_MAP_SIZE],
+            buckets: BTreeMap::new(),
+            run_dir,
+            core: 0,
+            new_bucket: false,
+            started: Instant::now(),
+            last_new_cov: (0, Instant::now()),
+        }
+    }
+
+    /// Điều kiện dừng đã thỏa (xét giữa các lượt fuzz_one) -> lý do cho summary
+    fn stop_reason(&self, iters: u64, stop: &StopConditions) -> Option<String> {
+        if iters > 0 && self.executions >= iters {
+            return Some(format!("iterations ({iters} executions)"));
+        }
+        if let Some(d) = stop.duration.filter(|d| self.started.elapsed() >= *d) {
+            return Some(format!("time budget ({})", fmt_duration(d)));
+        }
+        let counts = if stop.findings.is_empty() {
+            BTreeMap::new()
+        } else {
+            self.bucket_counts()
         };
+        for (class, n) in &stop.findings {
+            let hit = match class {
+                Some(c) => Some(c.as_str()).filter(|c| counts.get(*c).is_some_and(|k| k >= n)),
+                None => counts
+                    .iter()
+                    .find(|(_, k)| *k >= n)
+                    .map(|(c, _)| c.as_str()),
+            };
+            if let Some(c) = hit {
+                return Some(format!("findings ({n} {c} bucket(s))"));
+            }
+        }
+        let (exec, at) = self.last_new_cov;
+        match stop.plateau? {
+            Plateau::Execs(n) if self.executions - exec >= n => {
+                Some(format!("plateau (no new coverage for {n} executions)"))
+            }
+            Plateau::Time(d) if at.elapsed() >= d => {
+                Some(format!("plateau (no new coverage for {})", fmt_duration(d)))
+            }
+            _ => None,
+        }
+    }
 
-        let out = TransactionEvaluator::evaluate(&exec, &mut best_cov_hash);
+    /// Số bucket theo class; multi-core đếm file claim của mọi client
+    fn bucket_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        let claims = self.run_dir.as_ref().and_then(|dir| fs::read_dir(dir).ok());
+        match claims {
+            Some(entries) => {
+                for e in entries.flatten() {
+                    if e.path().extension().is_some_and(|x| x == "claim") {
+                        // file vừa tạo, class chưa ghi xong -> lượt sau đếm
+                        let class = fs::read_to_string(e.path()).unwrap_or_default();
+                        if !class.is_empty() {
+                            *counts.entry(class).or_default() += 1;
+                        }
+                    }
+                }
+            }
+            None => {
+                for e in self.buckets.values() {
+                    *counts.entry(e.bucket.class.clone()).or_default() += 1;
+                }
+            }
+        }
+        counts
+    }
 
-        if out.is_objective {
-            new_crash_inputs += 1;
-            let name = format!("{:016x}", exec.coverage.hash16());
-            let dir = if signals.lamports_theft { "crashes" } else { "bugs" };
-            write_artifact(dir, &name, &input)?;
-
-            if let Some(r) = out.report {
-                let rep_path = format!("{}/{}.report.txt", dir, name);
-                fs::write(
-                    rep_path,
-                    format!(
-                        "Vulnerability Class: {}\n\nTX Payload (hex): {}\n\n=== Global State BEFORE ===\n{}\n\n=== Global State AFTER ===\n{}\n\nTrace:\n{}\n",
-                        r.vuln_class,
-                        r.tx_payload_hex,
-                        r.global_state_before,
-                        r.global_state_after,
-                        r.trace_summary
-                    ),
-                )?;
+    /// Multi-core: giành quyền ghi representative của bucket (file claim tạo atomic giữa các client,
+    /// nội dung = class cho điều kiện dừng theo findings)
+    fn claim(&self, id: &str, class: &str) -> io::Result<bool> {
+        let Some(dir) = &self.run_dir else {
+            return Ok(true);
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(format!("{dir}/{id}.claim"))
+        {
+            Ok(mut f) => f.write_all(class.as_bytes()).map(|_| true),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Multi-core: stats + bucket của client cho parent gộp; ghi định kỳ để client respawn tiếp tục được
+    fn write_client_stats(&self, corpus: usize, stopped: &str) -> io::Result<()> {
+        let Some(dir) = &self.run_dir else {
+            return Ok(());
+        };
+        let stats = ClientStats {
+            seed: self.seed,
+            executions: self.executions,
+            new_crash_inputs: self.new_crash_inputs,
+            max_cu: self.max_cu,
+            corpus,
+            stopped: stopped.to_string(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            buckets: self.buckets.values().cloned().collect(),
+        };
+        // ghi file tạm rồi rename: parent / lần respawn sau không đọc phải file ghi dở
+        let path = format!("{dir}/{}.json", self.core);
+        fs::write(
+            format!("{path}.tmp"),
+            serde_json::to_string_pretty(&stats).unwrap(),
+        )?;
+        fs::rename(format!("{path}.tmp"), path)
+    }
+
+    /// Multi-core: client bị respawn (crash/timeout) lấy lại counter từ stats file của nó,
+    /// không có thì từ số lần thực thi trong state LibAFL đã restore
+    fn resume(&mut self, state_executions: Option<u64>) {
+        let Some(dir) = &self.run_dir else {
+            return;
+        };
+        let stats = fs::read(format!("{dir}/{}.json", self.core))
+            .ok()
+            .and_then(|b| serde_json::from_slice::<ClientStats>(&b).ok());
+        match stats {
+            Some(stats) => {
+                self.executions = stats.executions;
+                self.new_crash_inputs = stats.new_crash_inputs;
+                self.max_cu = stats.max_cu;
+                let elapsed = Duration::from_millis(stats.elapsed_ms);
+                self.started = Instant::now()
+                    .checked_sub(elapsed)
+                    .unwrap_or_else(Instant::now);
+                self.buckets = stats
+                    .buckets
+                    .into_iter()
+                    .map(|e| (e.bucket.id.clone(), e))
+                    .collect();
             }
+            None => self.executions = state_executions.unwrap_or(0),
+        }
+        self.last_new_cov = (self.executions, Instant::now());
+        if self.executions > 0 {
+            println!(
+                "[*] client {} resumed at {} executions, {} bucket(s)",
+                self.core,
+                self.executions,
+                self.buckets.len()
+            );
+        }
+    }
+
+    /// Ghi triage.json + SARIF (hit count hiện tại); multi-core thì parent ghi lúc kết thúc
+    fn write_triage(&self, elf_path: &str) -> io::Result<()> {
+        if self.run_dir.is_some() {
+            return Ok(());
+        }
+        let entries: Vec<&BucketEntry> = self.buckets.values().collect();
+        fs::write(TRIAGE_PATH, serde_json::to_string_pretty(&entries).unwrap())?;
+        let findings: Vec<(&str, u64, &VulnReport)> = entries
+            .iter()
+            .filter_map(|e| Some((e.representative.as_str(), e.hits, e.report.as_ref()?)))
+            .collect();
+        let sarif = sarif_log(&findings, elf_path);
+        fs::write(SARIF_PATH, serde_json::to_string_pretty(&sarif).unwrap())
+    }
+
+    /// Đánh giá một lần thực thi: ghi artifact/report nếu là objective.
+    /// Trả về true nếu input đáng giữ trong corpus (edge mới / hit count cao hơn / cost ước lượng cao nhất).
+    /// `input` = None: seed typed, không có dạng raw
+    fn record(
+        &mut self,
+        target: &mut FuzzTarget,
+        input: Option<&[u8]>,
+        exec: &ExecResult,
+    ) -> io::Result<bool> {
+        let signals = &exec.signals;
+        let out = TransactionEvaluator::evaluate(exec, &mut self.best_cov_hash, &mut self.max_cu);
+        self.executions += 1;
+        self.new_bucket = false;
 
+        let mut new_cov = false;
+        for (v, &h) in self.virgin.iter_mut().zip(&exec.coverage.hits) {
+            let b = hit_bucket(h);
+            if b > *v {
+                *v = b;
+                new_cov = true;
+            }
+        }
+        if new_cov {
+            self.last_new_cov = (self.executions, Instant::now());
+        }
+        let interesting = new_cov || out.is_new_max_cu;
+
+        if out.is_new_max_cu {
             println!(
-                "[Objective:new] {} cov_hash={:016x}",
-                signals.class(),
-                exec.coverage.hash16()
+                "[MaxCost] approximate CU {} (exec={})",
+                self.max_cu, self.executions
+            );
+        }
+
+        if out.is_objective {
+            self.new_crash_inputs += 1;
+        }
+        // input kèm clock đã dùng: artifact/corpus replay ra đúng lần thực thi này
+        let raw = input.map(|input| {
+            match exec.tx.clock_override {
+            Some(_) => input.to_vec(),
+            None => TxGenerator::embed_clock(input, exec.clock.slot, exec.clock.unix_timestamp),
+        }
+        });
+
+        // cùng root cause với bucket đã có -> chỉ tăng hit count
+        let b = out.is_objective.then(|| target.bucket(exec));
+        if let Some(e) = b.as_ref().and_then(|b| self.buckets.get_mut(&b.id)) {
+            e.hits += 1;
+        } else if let Some(b) = b {
+            let name = b.id.clone();
+            let dir = if signals.lamports_theft || signals.token_theft {
+                "crashes"
+            } else {
+                "bugs"
+            };
+
+            // multi-core: client khác đã ghi representative -> client này chỉ đếm hit
+            let owner = self.claim(&name, &b.class)?;
+            let mut report = None;
+            if owner {
+                // artifact typed: key/flag/data/clock/account state cụ thể -> replay không phụ thuộc TxGenerator
+                let file = TxFile::from_exec(exec, target.roles(), raw.as_deref());
+                write_artifact(dir, &format!("{name}.tx.json"), file.to_json().as_bytes())?;
+
+                if let Some(r) = out.report {
+                    let r = target.finish_report(exec, r, raw.as_deref(), Some(self.seed));
+                    let rep_path = format!("{}/{}.report.txt", dir, name);
+                    fs::write(rep_path, r.to_text())?;
+                    fs::write(format!("{}/{}.report.json", dir, name), r.to_json())?;
+                    if let Some(e) = &r.export {
+                        let json = serde_json::to_string_pretty(e).unwrap();
+                        fs::write(format!("{}/{}.solana.json", dir, name), json)?;
+                        e.write_accounts(&format!("{dir}/{name}.accounts"))?;
+                    }
+                    report = Some(r);
+                }
+                // repro harness (solana-program-test): chạy exploit trên program thật, in delta quan sát được
+                let harness = repro_harness(
+                    &name,
+                    exec,
+                    target.roles(),
+                    &target.program_name,
+                    target.vm.compute_budget,
+                );
+                fs::write(format!("{dir}/{name}.repro.rs"), harness)?;
+
+                println!(
+                    "[Objective:new] {} bucket={} {}",
+                    signals.class(),
+                    b.id,
+                    b.signature.join(" ")
+                );
+                self.new_bucket = true;
+            }
+            self.buckets.insert(
+                b.id.clone(),
+                BucketEntry {
+                    bucket: b,
+                    hits: 1,
+                    representative: format!("{dir}/{name}"),
+                    seed: owner.then_some(self.seed),
+                    report,
+                },
             );
+            self.write_triage(&target.elf_path)?;
+        }
+
+        // corpus cũng typed: cmin/replay thấy đúng edge campaign đã thấy
+        if interesting {
+            let json = TxFile::from_exec(exec, target.roles(), raw.as_deref()).to_json();
+            write_artifact(
+                CORPUS_DIR,
+                &format!(
+                    "{:016x}.tx.json",
+                    stable_hash(input.unwrap_or(json.as_bytes()))
+                ),
+                json.as_bytes(),
+            )?;
         }
 
         // semantic feedback loop (paper-lite)
-        emu.update_semantics(&out.semantics);
+        target.emu.update_semantics(&out.semantics);
 
-        executions += 1;
-        if (i + 1) % 1000 == 0 || out.is_objective {
+        if self.executions.is_multiple_of(1000) {
             println!(
-                "[*] iter={}/{} exec={} best_cov_hash={:016x} objective={}",
-                i + 1,
+                "[*] exec={} best_cov_hash={:016x} max_est_cost={} buckets={}",
+                self.executions,
+                self.best_cov_hash,
+                self.max_cu,
+                self.buckets.len()
+            );
+            if !self.buckets.is_empty() {
+                self.write_triage(&target.elf_path)?;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+/// Bucket hit count kiểu AFL: 1, 2, 3, 4-7, 8-15, 16-31, 32-127, 128+
+pub(crate) fn hit_bucket(h: u32) -> u8 {
+    match h {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4..=7 => 4,
+        8..=15 => 5,
+        16..=31 => 6,
+        32..=127 => 7,
+        _ => 8,
+    }
+}
+
+/// Feedback đọc kết luận của harness (`Campaign::record`) cho lần chạy vừa xong.
+/// Không dùng MapFeedback: nó lấy observer qua `match_name_type`, mà `type_eq` của libafl_bolts 0.13
+/// dựa vào specialization `<[T; 1]>::clone` -> Copy trong core. Core đã bỏ specialization đó,
+/// nên trên rustc hiện tại (1.95) `type_eq::<u8, u8>()` trả false và MapFeedback không bao giờ
+/// thấy map observer. Campaign::record đã tính edge mới / hit count / cost cao nhất, feedback chỉ đọc lại.
+struct NoveltyFeedback {
+    last: Rc<Cell<bool>>,
+}
+
+impl Named for NoveltyFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("novelty");
+        &NAME
+    }
+}
+
+impl<S> Feedback<S> for NoveltyFeedback
+where
+    S: State,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, libafl::Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(self.last.get())
+    }
+}
+
+/// Objective: bucket mới do client này ghi -> báo `Event::Objective` cho monitor (multi-core: đếm bucket unique).
+/// Luôn trả false: artifact đã ghi trong harness, input vẫn được xét vào corpus
+struct BucketFeedback {
+    new: Rc<Cell<bool>>,
+    count: usize,
+}
+
+impl Named for BucketFeedback {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("bucket");
+        &NAME
+    }
+}
+
+impl<S> Feedback<S> for BucketFeedback
+where
+    S: State + HasExecutions,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, libafl::Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        if self.new.get() {
+            self.count += 1;
+            manager.fire(
+                state,
+                Event::Objective {
+                    objective_size: self.count,
+                    executions: *state.executions(),
+                    time: current_time(),
+                },
+            )?;
+        }
+        Ok(false)
+    }
+}
+
+/// Toán hạng so sánh của một lần chạy (Cmp -> u64, sol_memcmp -> bytes, tối đa 32).
+/// Hai vế bytes phải cùng độ dài (I2SRandReplace copy theo độ dài của vế còn lại).
+///
+/// CmpLog chỉ ở mức model: toán hạng là của trace op tổng hợp trong TraceVM (Cmp so
+/// `read_u64(data, i + 1)` với `CMP_MAGIC` / stack slot, MemCmp là sighash của dispatch op 19),
+/// không phải cmp/memcmp mà BPF instruction của ELF thực sự chạy.
+fn cmp_values(exec: &ExecResult) -> Vec<CmpValues> {
+    let bytes = |v: &[u8], n: usize| {
+        let mut buf = [0u8; 32];
+        buf[..n].copy_from_slice(&v[..n]);
+        CmplogBytes::from_buf_and_len(buf, n as u8)
+    };
+    exec.events
+        .iter()
+        .filter_map(|ev| match &ev.event {
+            VmEvent::Cmp { lhs, rhs, .. } => Some(CmpValues::U64((*lhs, *rhs))),
+            VmEvent::MemCmp { lhs, rhs } => {
+                let n = lhs.len().min(rhs.len()).min(32);
+                (n > 0).then(|| CmpValues::Bytes((bytes(lhs, n), bytes(rhs, n))))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// CmpLog observer: toán hạng so sánh của lần chạy vừa xong -> `CmpValuesMetadata` (cho I2S)
+#[derive(Debug, Serialize, Deserialize)]
+struct CmpLogObserver {
+    #[serde(skip)]
+    log: Rc<RefCell<Vec<CmpValues>>>,
+}
+
+impl Named for CmpLogObserver {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("cmplog");
+        &NAME
+    }
+}
+
+impl<S> Observer<S> for CmpLogObserver
+where
+    S: UsesInput + HasMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), libafl::Error> {
+        self.log.borrow_mut().clear();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), libafl::Error> {
+        let meta = state.metadata_or_insert_with(CmpValuesMetadata::new);
+        meta.list = std::mem::take(&mut *self.log.borrow_mut());
+        Ok(())
+    }
+}
+
+pub fn run_fuzzdelsol(cfg: &FuzzConfig) -> io::Result<()> {
+    let iters = cfg.iters;
+    if iters > 0 {
+        println!("[*] Fuzzing for {iters} iterations...");
+    }
+    if let Some(d) = cfg.stop.duration {
+        println!("[*] time budget = {}", fmt_duration(d));
+    }
+    for (class, n) in &cfg.stop.findings {
+        println!(
+            "[*] stop at {n} bucket(s) of {}",
+            class.as_deref().unwrap_or("any class")
+        );
+    }
+    // multi-core: plateau xét coverage của từng client
+    let per_client = if cfg.cores.is_some() {
+        " (per client)"
+    } else {
+        ""
+    };
+    match cfg.stop.plateau {
+        Some(Plateau::Execs(n)) => {
+            println!("[*] stop after {n} executions without new coverage{per_client}")
+        }
+        Some(Plateau::Time(d)) => println!(
+            "[*] stop after {} without new coverage{per_client}",
+            fmt_duration(d)
+        ),
+        None => {}
+    }
+    if iters == 0
+        && cfg.stop.duration.is_none()
+        && cfg.stop.findings.is_empty()
+        && cfg.stop.plateau.is_none()
+    {
+        println!("[!] no stop condition: fuzzing until interrupted");
+    }
+    println!("[*] ELF = {}", cfg.elf_path);
+    println!("[*] crashes_dir = crashes");
+    println!("[*] bugs_dir    = bugs");
+
+    let target = FuzzTarget::setup(cfg)?;
+    let compute_budget = target.vm.compute_budget;
+    println!("[*] compute budget = {compute_budget} (checked against approximate compute units)");
+
+    let mut tokens = Tokens::new();
+    tokens.add_tokens(&target.dict.tokens);
+    println!(
+        "[*] ELF dictionary: {} tokens, {} keys",
+        target.dict.tokens.len(),
+        target.dict.keys.len()
+    );
+    if let Some(path) = &cfg.dict_path {
+        let before = tokens.len();
+        tokens.add_from_file(path).map_err(io::Error::other)?;
+        println!(
+            "[*] user dictionary {path}: {} tokens",
+            tokens.len() - before
+        );
+    }
+
+    let seed_rng = cfg.seed.unwrap_or_else(current_nanos);
+    println!("[*] seed = {seed_rng} (--seed {seed_rng} to repeat)");
+    if target.source_map.is_empty() {
+        println!("[*] no DWARF debug info: SARIF results point at the ELF");
+    }
+
+    match &cfg.cores {
+        Some(spec) => run_parallel(cfg, spec, target, tokens, seed_rng),
+        None => {
+            let campaign = Campaign::new(seed_rng, None);
+            let (c, corpus, stopped) = fuzz_client(
+                target,
+                tokens,
+                campaign,
                 iters,
-                executions,
-                best_cov_hash,
-                out.is_objective
+                cfg,
+                None,
+                &mut NopEventManager::new(),
+            )?;
+            print_summary(cfg, &c, corpus, compute_budget, 1, &stopped)
+        }
+    }
+}
+
+/// Fuzz loop của một client tới khi thỏa một điều kiện dừng -> (campaign, kích thước corpus, lý do dừng).
+/// Single-thread dùng NopEventManager; multi-core là LLMP client của Launcher: corpus mới được broadcast,
+/// client nhận chạy lại input để xét novelty trên coverage của chính nó
+fn fuzz_client<EM>(
+    target: FuzzTarget,
+    tokens: Tokens,
+    campaign: Campaign,
+    iters: u64,
+    cfg: &FuzzConfig,
+    state: Option<FuzzState>,
+    mgr: &mut EM,
+) -> io::Result<(Campaign, usize, String)>
+where
+    EM: EventManager<MainExecutor, FuzzerType, State = FuzzState>,
+{
+    let seed_rng = campaign.seed;
+    let target = Rc::new(RefCell::new(target));
+    let campaign = Rc::new(RefCell::new(campaign));
+    let seed = vec![2, 3, 0, 1, 2, 3, 0x10, 0x22, 0x80, 0xFF, 0x7F, 0x01];
+    let novel: Rc<Cell<bool>> = Rc::default();
+    let new_bucket: Rc<Cell<bool>> = Rc::default();
+    let cmp_log: Rc<RefCell<Vec<CmpValues>>> = Rc::default();
+
+    let harness = {
+        let (target, campaign, novel, new_bucket) = (
+            target.clone(),
+            campaign.clone(),
+            novel.clone(),
+            new_bucket.clone(),
+        );
+        move |input: &BytesInput| {
+            let bytes = input.target_bytes();
+            let mut target = target.borrow_mut();
+            let exec = target.execute(bytes.as_slice());
+            let mut campaign = campaign.borrow_mut();
+            let recorded = campaign.record(&mut target, Some(bytes.as_slice()), &exec);
+            target.emu.tick_clock();
+            match recorded {
+                Ok(n) => novel.set(n),
+                Err(e) => {
+                    eprintln!("[!] write artifact: {e}");
+                    novel.set(false);
+                }
+            }
+            new_bucket.set(campaign.new_bucket);
+            ExitKind::Ok
+        }
+    };
+
+    // tracer: chạy lại testcase hiện tại chỉ để lấy CmpLog (không ghi artifact, không tăng clock)
+    let tracing_harness = {
+        let (target, tracer_log) = (target.clone(), cmp_log.clone());
+        move |input: &BytesInput| {
+            let exec = target.borrow_mut().execute(input.target_bytes().as_slice());
+            *tracer_log.borrow_mut() = cmp_values(&exec);
+            ExitKind::Ok
+        }
+    };
+    let cmplog_observer = CmpLogObserver { log: cmp_log };
+
+    // corpus giữ input mở edge mới hoặc có cost ước lượng cao hơn
+    let mut feedback = NoveltyFeedback { last: novel };
+    // objective do oracle quyết định (ghi artifact trong harness); bucket mới chỉ được báo cho monitor
+    let mut objective = BucketFeedback {
+        new: new_bucket,
+        count: 0,
+    };
+
+    let mut state = match state {
+        Some(state) => state,
+        None => {
+            let mut state = StdState::new(
+                StdRand::with_seed(seed_rng),
+                InMemoryCorpus::<BytesInput>::new(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )
+            .map_err(io::Error::other)?;
+            state.set_max_size(MAX_INPUT_LEN);
+            state.add_metadata(tokens);
+            state
+        }
+    };
+
+    let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+
+    let mut executor = MainExecutor::generic(
+        tuple_list!(),
+        Box::new(harness),
+        (),
+        &mut fuzzer,
+        &mut state,
+        mgr,
+    )
+    .map_err(io::Error::other)?;
+    let tracer = OwnedInProcessExecutor::generic(
+        tuple_list!(),
+        Box::new(tracing_harness),
+        tuple_list!(cmplog_observer),
+        &mut fuzzer,
+        &mut state,
+        mgr,
+    )
+    .map_err(io::Error::other)?;
+
+    // RedQueen-lite: trace CmpLog của testcase -> thay toán hạng khớp trong input (I2S)
+    // -> mutate theo cấu trúc tx: header (signer/writable/account) + havoc, dictionary, splice trên ix data
+    let mut stages = tuple_list!(
+        TracingStage::new(tracer),
+        StdMutationalStage::new(StdScheduledMutator::new(tuple_list!(I2SRandReplace))),
+        StdMutationalStage::new(StdScheduledMutator::new(tuple_list!(
+            SignerFlipMutator,
+            WritableToggleMutator,
+            AccountSwapMutator,
+            AccountInsertMutator,
+            AccountRemoveMutator,
+            DataMutator::new(StdScheduledMutator::new(
+                havoc_mutations().merge(tokens_mutations())
+            )),
+            DataSpliceMutator,
+        ))),
+    );
+
+    if state.corpus().count() == 0 {
+        // seed raw vào corpus qua harness; typed chạy qua execute_file (coverage + finding vào
+        // campaign), corpus nhận input raw đã sinh ra nó nếu file có ghi
+        let (mut raw_seeds, mut typed_seeds, mut skipped) = (0, 0, 0);
+        for dir in &cfg.seed_dirs {
+            for path in collect_files(dir)? {
+                let bytes = fs::read(&path)?;
+                let file = match TxFile::from_json(&bytes) {
+                    Some(Ok(file)) => file,
+                    Some(Err(e)) => {
+                        eprintln!("[!] {}: {e}", path.display());
+                        skipped += 1;
+                        continue;
+                    }
+                    // JSON khác (triage, ...) không phải input
+                    None if serde_json::from_slice::<serde_json::Value>(&bytes).is_ok() => {
+                        skipped += 1;
+                        continue;
+                    }
+                    None => {
+                        fuzzer
+                            .add_input(&mut state, &mut executor, mgr, BytesInput::new(bytes))
+                            .map_err(io::Error::other)?;
+                        raw_seeds += 1;
+                        continue;
+                    }
+                };
+                let exec = match target.borrow_mut().execute_file(&bytes) {
+                    Ok(exec) => exec,
+                    Err(e) => {
+                        eprintln!("[!] {}: {e}", path.display());
+                        skipped += 1;
+                        continue;
+                    }
+                };
+                let mut t = target.borrow_mut();
+                campaign.borrow_mut().record(&mut t, None, &exec)?;
+                t.emu.tick_clock();
+                drop(t);
+                typed_seeds += 1;
+                if let Some(raw) = file.raw.and_then(|r| ::hex::decode(r).ok()) {
+                    fuzzer
+                        .add_input(&mut state, &mut executor, mgr, BytesInput::new(raw))
+                        .map_err(io::Error::other)?;
+                }
+            }
+        }
+        if !cfg.seed_dirs.is_empty() {
+            println!(
+                "[*] seeds: {raw_seeds} raw, {typed_seeds} typed, {skipped} skipped -> corpus {}",
+                state.corpus().count()
             );
         }
+        if state.corpus().count() == 0 {
+            fuzzer
+                .add_input(&mut state, &mut executor, mgr, BytesInput::new(seed))
+                .map_err(io::Error::other)?;
+        }
+    }
+    let mut saved = Instant::now();
+    let stopped = loop {
+        if let Some(reason) = campaign.borrow().stop_reason(iters, &cfg.stop) {
+            break reason;
+        }
+        fuzzer
+            .fuzz_one(&mut stages, &mut executor, &mut state, mgr)
+            .map_err(io::Error::other)?;
+        mgr.maybe_report_progress(&mut state, Duration::from_secs(1))
+            .map_err(io::Error::other)?;
+        if saved.elapsed() >= Duration::from_secs(1) {
+            campaign
+                .borrow()
+                .write_client_stats(state.corpus().count(), "running")?;
+            saved = Instant::now();
+        }
+    };
+
+    drop((stages, executor));
+    let campaign = Rc::try_unwrap(campaign)
+        .ok()
+        .expect("harness dropped")
+        .into_inner();
+    Ok((campaign, state.corpus().count(), stopped))
+}
+
+/// Multi-core: Launcher fork một client mỗi core (seed = seed gốc + core id), broker gộp stats vào một monitor.
+/// `iters` chia đều cho các client; kết thúc thì parent gộp bucket + hit count của mọi client
+fn run_parallel(
+    cfg: &FuzzConfig,
+    spec: &str,
+    target: FuzzTarget,
+    tokens: Tokens,
+    seed_rng: u64,
+) -> io::Result<()> {
+    let cores = Cores::from_cmdline(spec).map_err(io::Error::other)?;
+    // Launcher bỏ qua core không tồn tại nhưng broker vẫn chờ đủ số client
+    let available = get_core_ids().map_err(io::Error::other)?.len();
+    if let Some(c) = cores.ids.iter().find(|c| c.0 >= available) {
+        return Err(io::Error::other(format!(
+            "core {} not available ({available} cores)",
+            c.0
+        )));
+    }
+    let clients = cores.ids.len();
+    let per_client = cfg.iters.div_ceil(clients as u64);
+    let compute_budget = target.vm.compute_budget;
+    // --seed lặp lại: không dùng lại claim/stats của run trước
+    fs::create_dir_all(CLIENTS_DIR)?;
+    let mut run_dir = format!("{CLIENTS_DIR}/{seed_rng}");
+    let mut n = 0;
+    while let Err(e) = fs::create_dir(&run_dir) {
+        if e.kind() != io::ErrorKind::AlreadyExists {
+            return Err(e);
+        }
+        n += 1;
+        run_dir = format!("{CLIENTS_DIR}/{seed_rng}.{n}");
     }
+    let broker_port = cfg.broker_port.unwrap_or(BROKER_PORT);
+    println!("[*] multi-core: {clients} clients on cores {spec}, broker port {broker_port}");
+    if per_client > 0 {
+        println!("[*] {per_client} iterations per client");
+    }
+    let started = Instant::now();
+
+    let run_client = |state: Option<FuzzState>,
+                      mut mgr: LlmpRestartingEventManager<(), FuzzState, StdShMemProvider>,
+                      core: CoreId| {
+        let seed = seed_rng + core.0 as u64;
+        println!("[*] client {} seed = {seed}", core.0);
+        let mut campaign = Campaign::new(seed, Some(run_dir.clone()));
+        campaign.core = core.0;
+        campaign.resume(state.as_ref().map(|s| *s.executions()));
+        let client = fuzz_client(
+            target,
+            tokens,
+            campaign,
+            per_client,
+            cfg,
+            state,
+            &mut mgr,
+        )
+        .and_then(|(c, corpus, stopped)| c.write_client_stats(corpus, &stopped));
+        client.map_err(|e| libafl::Error::unknown(e.to_string()))?;
+        mgr.send_exiting()
+    };
 
+    // broker nhận event mỗi testcase mới -> in tối đa 1 dòng/giây
+    let mut last = Duration::ZERO;
+    let monitor = move |s: &str| {
+        let now = current_time();
+        if now - last >= Duration::from_secs(1) {
+            last = now;
+            println!("[Stats] {s}");
+        }
+    };
+
+    let parent = std::process::id();
+    let res = Launcher::builder()
+        .shmem_provider(StdShMemProvider::new().map_err(io::Error::other)?)
+        .configuration(EventConfig::AlwaysUnique)
+        .monitor(SimpleMonitor::new(monitor))
+        .run_client(run_client)
+        .cores(&cores)
+        .broker_port(broker_port)
+        .build()
+        .launch();
+    // client / respawner (đã fork) dừng ở đây, chỉ parent (broker) gộp kết quả
+    if std::process::id() != parent {
+        match &res {
+            Ok(()) | Err(libafl::Error::ShuttingDown) => {}
+            Err(e) => eprintln!("Error: {e}"),
+        }
+        std::process::exit(0);
+    }
+    match res {
+        Ok(()) | Err(libafl::Error::ShuttingDown) => {}
+        Err(e) => return Err(io::Error::other(e)),
+    }
+
+    let mut c = Campaign::new(seed_rng, None);
+    c.started = started;
+    let mut corpus = 0;
+    let mut stopped: BTreeMap<String, usize> = BTreeMap::new();
+    for entry in fs::read_dir(&run_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let stats: ClientStats =
+            serde_json::from_slice(&fs::read(&path)?).map_err(io::Error::other)?;
+        c.executions += stats.executions;
+        c.new_crash_inputs += stats.new_crash_inputs;
+        c.max_cu = c.max_cu.max(stats.max_cu);
+        corpus += stats.corpus;
+        *stopped.entry(stats.stopped).or_default() += 1;
+        for e in stats.buckets {
+            match c.buckets.get_mut(&e.bucket.id) {
+                Some(m) => {
+                    m.hits += e.hits;
+                    m.seed = m.seed.or(e.seed);
+                }
+                None => {
+                    c.buckets.insert(e.bucket.id.clone(), e);
+                }
+            }
+        }
+    }
+
+    // report cho SARIF: report.json client sở hữu bucket đã ghi (client bị kill giữa chừng có thể chưa ghi)
+    for e in c.buckets.values_mut() {
+        let path = format!("{}.report.json", e.representative);
+        match fs::read(&path).map(|b| serde_json::from_slice(&b)) {
+            Ok(Ok(r)) => e.report = Some(r),
+            Ok(Err(err)) => eprintln!("[!] {path}: {err}, skipped"),
+            Err(err) => eprintln!("[!] {path}: {err}, skipped"),
+        }
+    }
+    let stopped: Vec<String> = stopped
+        .into_iter()
+        .map(|(r, n)| format!("{r} x{n}"))
+        .collect();
+    print_summary(
+        cfg,
+        &c,
+        corpus,
+        compute_budget,
+        clients,
+        &stopped.join(", "),
+    )
+}
+
+fn print_summary(
+    cfg: &FuzzConfig,
+    c: &Campaign,
+    corpus: usize,
+    compute_budget: u64,
+    clients: usize,
+    stopped: &str,
+) -> io::Result<()> {
     println!("\n================ SUMMARY ================");
-    println!("iters requested  : {iters}");
-    println!("executions       : {executions}");
-    println!("new crash inputs : {new_crash_inputs}");
+    println!("stopped by       : {stopped}");
+    println!("iters requested  : {}", cfg.iters);
+    println!("elapsed          : {}", fmt_duration(c.started.elapsed()));
+    if clients > 1 {
+        println!("clients          : {clients}");
+    }
+    println!("executions       : {}", c.executions);
+    println!("corpus size      : {corpus}");
+    println!("new crash inputs : {}", c.new_crash_inputs);
+    println!("unique buckets   : {}", c.buckets.len());
+    for e in c.buckets.values() {
+        println!(
+            "  {} {:<22} hits={:<6} {}",
+            e.bucket.id, e.bucket.class, e.hits, e.representative
+        );
+    }
+    println!(
+        "max approx. CU   : {} / {} (approximate)",
+        c.max_cu, compute_budget
+    );
+    if clients > 1 {
+        println!("seed             : {} (+ core id per client)", c.seed);
+    } else {
+        println!("seed             : {}", c.seed);
+    }
     if Path::new("crashes").exists() {
         println!("crashes/ written");
     }
     if Path::new("bugs").exists() {
         println!("bugs/ written");
     }
+    if Path::new(CORPUS_DIR).exists() {
+        println!("{CORPUS_DIR}/ written");
+    }
+    if !c.buckets.is_empty() {
+        c.write_triage(&cfg.elf_path)?;
+        println!("{SARIF_PATH}, {TRIAGE_PATH} written");
+    }
     println!("========================================");
     Ok(())
 }
+
+/// Chạy lại một input đã lưu (clock lấy từ trailer của input nếu có)
+pub fn replay(cfg: &FuzzConfig, input_path: &str) -> io::Result<()> {
+    let input = fs::read(input_path)?;
+    let mut target = FuzzTarget::setup(cfg)?;
+    let exec = target.execute_file(&input)?;
+    let (mut best_cov_hash, mut max_cu) = (0, 0);
+    let out = TransactionEvaluator::evaluate(&exec, &mut best_cov_hash, &mut max_cu);
+
+    let raw = TxFile::from_json(&input)
+        .is_none()
+        .then_some(input.as_slice());
+    let kind = if raw.is_none() { "typed" } else { "raw" };
+    println!("[*] replay {input_path} ({} bytes, {kind})", input.len());
+    println!(
+        "[*] clock slot={} unix_timestamp={}",
+        exec.clock.slot, exec.clock.unix_timestamp
+    );
+    println!(
+        "[*] approximate CU {} / {} (approximation, not metered)",
+        exec.compute_units, target.vm.compute_budget
+    );
+    match out.report {
+        Some(r) => println!("{}", target.finish_report(&exec, r, raw, None).to_text()),
+        None => println!("[*] no objective\nTrace:\n{}", exec.trace_summary),
+    }
+    Ok(())
+}
+
+/// Chuyển input (raw legacy hoặc typed version cũ) sang typed format hiện tại.
+/// Raw được diễn giải bằng TxGenerator + emulator của `cfg` (giống lúc fuzz)
+pub fn convert(cfg: &FuzzConfig, input_path: &str, out_path: &str) -> io::Result<()> {
+    let input = fs::read(input_path)?;
+    let mut target = FuzzTarget::setup(cfg)?;
+    let raw = TxFile::from_json(&input)
+        .is_none()
+        .then_some(input.as_slice());
+    let exec = target.execute_file(&input)?;
+    let file = TxFile::from_exec(&exec, target.roles(), raw);
+    fs::write(out_path, file.to_json())?;
+    println!(
+        "[*] {input_path} -> {out_path} ({} instruction(s), {} account override(s), {})",
+        file.instructions.len(),
+        file.account_overrides.len(),
+        exec.signals.class()
+    );
+    Ok(())
+}
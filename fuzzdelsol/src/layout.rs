@@ -0,0 +1,232 @@
+use serde_json::Value;
+use solana_sdk::hash::hash;
+use std::fs;
+use std::io;
+
+/// Layout của một account type (Anchor-style: 8-byte discriminator + borsh fields)
+#[derive(Clone, Debug)]
+pub struct AccountLayout {
+    pub name: String,
+    pub discriminator: [u8; 8],
+    /// tổng size (gồm discriminator), None nếu có field không cố định size
+    pub size: Option<usize>,
+    /// offset field authority (pubkey), nếu biết
+    pub authority_offset: Option<usize>,
+    /// field có offset cố định (dừng ở field đầu tiên không cố định size)
+    pub fields: Vec<LayoutField>,
+}
+
+/// Field borsh cố định size: `ty` là tên type IDL (u64, publicKey, bool...)
+#[derive(Clone, Debug)]
+pub struct LayoutField {
+    pub name: String,
+    pub ty: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Layout model: danh sách account type mà program đọc/ghi + instruction (sighash) nó nhận
+#[derive(Clone, Debug)]
+pub struct LayoutModel {
+    pub layouts: Vec<AccountLayout>,
+    pub instructions: Vec<(String, [u8; 8])>,
+}
+
+/// Anchor: sha256("global:<name>")[..8]
+pub fn anchor_sighash(name: &str) -> [u8; 8] {
+    let h = hash(format!("global:{name}").as_bytes());
+    h.to_bytes()[..8].try_into().unwrap()
+}
+
+fn discriminator_of(v: &Value) -> Option<[u8; 8]> {
+    let d = v.as_array().filter(|d| d.len() == 8)?;
+    let mut out = [0u8; 8];
+    for (o, x) in out.iter_mut().zip(d) {
+        *o = x.as_u64().unwrap_or(0) as u8;
+    }
+    Some(out)
+}
+
+/// Anchor: sha256("account:<Name>")[..8]
+pub fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let h = hash(format!("account:{name}").as_bytes());
+    h.to_bytes()[..8].try_into().unwrap()
+}
+
+fn field_size(ty: &Value) -> Option<usize> {
+    match ty.as_str()? {
+        "bool" | "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "publicKey" | "pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+fn is_pubkey(ty: &Value) -> bool {
+    matches!(ty.as_str(), Some("publicKey") | Some("pubkey"))
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if !out.is_empty() {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl Default for LayoutModel {
+    /// Model mặc định khi không có IDL: Vault { authority, balance } và User { owner, points };
+    /// instruction initialize / deposit / withdraw
+    fn default() -> Self {
+        let field = |name: &str, ty: &str, offset, size| LayoutField {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            offset,
+            size,
+        };
+        let mk = |name: &str, authority: &str, amount: &str| AccountLayout {
+            name: name.to_string(),
+            discriminator: anchor_discriminator(name),
+            size: Some(8 + 32 + 8),
+            authority_offset: Some(8),
+            fields: vec![
+                field(authority, "publicKey", 8, 32),
+                field(amount, "u64", 40, 8),
+            ],
+        };
+        Self {
+            layouts: vec![
+                mk("Vault", "authority", "balance"),
+                mk("User", "owner", "points"),
+            ],
+            instructions: ["initialize", "deposit", "withdraw"]
+                .iter()
+                .map(|n| (n.to_string(), anchor_sighash(n)))
+                .collect(),
+        }
+    }
+}
+
+impl LayoutModel {
+    /// Đọc Anchor IDL (JSON, cả format cũ lẫn >= 0.30)
+    pub fn from_anchor_idl(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let idl: Value = serde_json::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let empty = vec![];
+        let types = idl["types"].as_array().unwrap_or(&empty);
+        let mut layouts = Vec::new();
+        for acc in idl["accounts"].as_array().unwrap_or(&empty) {
+            let Some(name) = acc["name"].as_str() else {
+                continue;
+            };
+            let discriminator = discriminator_of(&acc["discriminator"])
+                .unwrap_or_else(|| anchor_discriminator(name));
+
+            // IDL cũ: fields nằm trong account; >= 0.30: nằm trong `types`
+            let ty = if acc["type"].is_object() {
+                &acc["type"]
+            } else {
+                types
+                    .iter()
+                    .find(|t| t["name"].as_str() == Some(name))
+                    .map(|t| &t["type"])
+                    .unwrap_or(&Value::Null)
+            };
+
+            let mut offset = Some(8usize);
+            let mut authority_offset = None;
+            let mut first_pubkey = None;
+            let mut fields = Vec::new();
+            for f in ty["fields"].as_array().unwrap_or(&empty) {
+                let Some(off) = offset else { break };
+                if is_pubkey(&f["type"]) {
+                    first_pubkey.get_or_insert(off);
+                    if matches!(
+                        f["name"].as_str(),
+                        Some("authority") | Some("owner") | Some("admin")
+                    ) {
+                        authority_offset.get_or_insert(off);
+                    }
+                }
+                offset = field_size(&f["type"]).map(|n| off + n);
+                if let (Some(size), Some(name), Some(ty)) = (
+                    field_size(&f["type"]),
+                    f["name"].as_str(),
+                    f["type"].as_str(),
+                ) {
+                    fields.push(LayoutField {
+                        name: name.to_string(),
+                        ty: ty.to_string(),
+                        offset: off,
+                        size,
+                    });
+                }
+            }
+
+            layouts.push(AccountLayout {
+                name: name.to_string(),
+                discriminator,
+                size: offset,
+                authority_offset: authority_offset.or(first_pubkey),
+                fields,
+            });
+        }
+
+        if layouts.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path}: IDL has no accounts"),
+            ));
+        }
+        // IDL >= 0.30 ghi sẵn discriminator; IDL cũ dùng tên camelCase -> sighash theo snake_case
+        let instructions = idl["instructions"]
+            .as_array()
+            .unwrap_or(&empty)
+            .iter()
+            .filter_map(|ix| {
+                let name = ix["name"].as_str()?;
+                let d = discriminator_of(&ix["discriminator"])
+                    .unwrap_or_else(|| anchor_sighash(&snake_case(name)));
+                Some((name.to_string(), d))
+            })
+            .collect();
+
+        Ok(Self {
+            layouts,
+            instructions,
+        })
+    }
+
+    /// Layout khớp discriminator ở đầu data (account đã init)
+    pub fn classify(&self, data: &[u8]) -> Option<&AccountLayout> {
+        let d = data.get(..8)?;
+        self.layouts.iter().find(|l| l.discriminator == d)
+    }
+
+    /// `discriminator` là của một account type trong model
+    pub fn knows(&self, discriminator: &[u8; 8]) -> bool {
+        self.layouts
+            .iter()
+            .any(|l| l.discriminator == *discriminator)
+    }
+
+    pub fn name_of(&self, discriminator: &[u8; 8]) -> String {
+        self.layouts
+            .iter()
+            .find(|l| l.discriminator == *discriminator)
+            .map(|l| l.name.clone())
+            .unwrap_or_else(|| format!("unknown({})", crate::types::hex(discriminator)))
+    }
+}
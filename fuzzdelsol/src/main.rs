@@ -6,25 +6,500 @@ mod emulator;
 mod txgen;
 mod oracles;
 mod evaluator;
+mod token;
+mod layout;
+mod serialize;
+mod compute;
+mod debuginfo;
+mod diff;
+mod dictionary;
+mod mutators;
+mod txfile;
+mod export;
+mod repro;
+mod sarif;
+mod triage;
+mod minimize;
+mod cmin;
 
+use emulator::ClockSchedule;
+use fuzzer_libafl::Plateau;
+use libafl_bolts::core_affinity::Cores;
+use solana_sdk::pubkey::Pubkey;
 use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::Duration;
+use types::CLASSES;
 
 fn main() {
     println!("=== FuzzDelSol-Lite (paper-aligned, 6 oracles) ===");
 
+    // fuzzdelsol replay <elf> <input> [--program id=path.so]... [--idl path] [--clock spec] [--cu N] [--ops-version N] [--keymap path]
+    // fuzzdelsol convert <elf> <input> [out.tx.json] [cùng option như replay]
+    // fuzzdelsol minimize <elf> <input> [out.tx.json] [cùng option như replay]
+    // fuzzdelsol cmin <elf> <out_dir> <in_dir>... [cùng option như replay]
+    // fuzzdelsol [--seed N]: fuzz campaign, các tham số khác hỏi interactive
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let sub = match args.first().map(String::as_str) {
+        Some("replay") => Some(replay_cmd(&args[1..])),
+        Some("convert") => Some(convert_cmd(&args[1..])),
+        Some("minimize") => Some(minimize_cmd(&args[1..])),
+        Some("cmin") => Some(cmin_cmd(&args[1..])),
+        _ => None,
+    };
+    if let Some(res) = sub {
+        if let Err(e) = res {
+            eprintln!("Error: {e}");
+        }
+        return;
+    }
+    let seed = match args.as_slice() {
+        [] => None,
+        [flag, n] if flag == "--seed" => match n.parse::<u64>() {
+            Ok(n) => Some(n),
+            Err(e) => {
+                eprintln!("Error: --seed: {e}");
+                return;
+            }
+        },
+        _ => {
+            eprintln!("usage: fuzzdelsol [--seed N] | replay | convert | minimize | cmin ...");
+            return;
+        }
+    };
+
     print!("Nhập đường dẫn ELF .so (vd: /home/solana/fuzz_target/target/deploy/fuzz_target.so): ");
     io::stdout().flush().unwrap();
     let mut elf_path = String::new();
     io::stdin().read_line(&mut elf_path).unwrap();
     let elf_path = elf_path.trim().to_string();
 
-    print!("Nhập số lần fuzzing (iters, ví dụ 10000): ");
+    print!("Nhập số lần fuzzing (iters, ví dụ 10000; 0 = không giới hạn): ");
     io::stdout().flush().unwrap();
     let mut s = String::new();
     io::stdin().read_line(&mut s).unwrap();
     let iters: u64 = s.trim().parse().unwrap_or(10_000);
 
-    if let Err(e) = fuzzer_libafl::run_fuzzdelsol(iters, &elf_path) {
+    print!("Nhập program CPI target bổ sung (program_id=path.so, cách nhau bởi dấu phẩy; chỉ đăng ký account executable, CPI tới đó chạy frame mô phỏng, không chạy image; Enter để bỏ qua): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let extra_programs = match parse_extra_programs(s.trim()) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return;
+        }
+    };
+
+    print!("Nhập đường dẫn Anchor IDL (.json, Enter để dùng layout mặc định): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let idl_path = Some(s.trim().to_string()).filter(|x| !x.is_empty());
+
+    print!("Chế độ clock (fixed | monotonic[:slot_step:secs_step], Enter = fixed): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let clock_schedule = match parse_clock_schedule(s.trim()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return;
+        }
+    };
+
+    print!(
+        "Compute budget mỗi instruction (so với compute unit xấp xỉ, tối đa 1400000; Enter = 200000): "
+    );
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let compute_budget = s
+        .trim()
+        .parse()
+        .unwrap_or(compute::DEFAULT_COMPUTE_UNIT_LIMIT);
+
+    print!(
+        "Nhập file dictionary (AFL format: name=\"value\", Enter để chỉ dùng token trích từ ELF): "
+    );
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let dict_path = Some(s.trim().to_string()).filter(|x| !x.is_empty());
+
+    print!("Nhập thư mục seed (input raw legacy hoặc typed .tx.json, vd corpus/ của run trước; cách nhau bởi dấu phẩy; Enter = seed mặc định): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let seed_dirs: Vec<String> = s
+        .split(',')
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .collect();
+
+    print!("Nhập key map JSON cho tx export ({{\"attacker\": \"<pubkey>\", ...}}, Enter = giữ key của fuzzer): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let keymap_path = Some(s.trim().to_string()).filter(|x| !x.is_empty());
+
+    print!("Multi-core: danh sách core (vd 0-31, 0,2,4, all; Enter = 1 thread): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let cores = Some(s.trim().to_string()).filter(|x| !x.is_empty());
+    if let Some(spec) = &cores {
+        if let Err(e) = Cores::from_cmdline(spec) {
+            eprintln!("Error: cores {spec}: {e}");
+            return;
+        }
+    }
+    let mut broker_port = None;
+    if cores.is_some() {
+        print!("Port của broker (Enter = {}): ", fuzzer_libafl::BROKER_PORT);
+        io::stdout().flush().unwrap();
+        let mut s = String::new();
+        io::stdin().read_line(&mut s).unwrap();
+        broker_port = match Some(s.trim())
+            .filter(|x| !x.is_empty())
+            .map(str::parse::<u16>)
+            .transpose()
+        {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Error: broker port: {e}");
+                return;
+            }
+        };
+    }
+
+    print!("Thời gian chạy tối đa (vd 90s, 30m, 2h; Enter = không giới hạn): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let duration = match Some(s.trim())
+        .filter(|x| !x.is_empty())
+        .map(parse_duration)
+        .transpose()
+    {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return;
+        }
+    };
+
+    print!("Dừng khi đủ số bucket mỗi class (N hoặc CLASS=N,...; Enter = không): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let findings = match parse_findings_limit(s.trim()) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return;
+        }
+    };
+
+    print!("Dừng khi không có coverage mới trong (vd 10m, hoặc 50000 = số lần thực thi; multi-core: theo từng client; Enter = không): ");
+    io::stdout().flush().unwrap();
+    let mut s = String::new();
+    io::stdin().read_line(&mut s).unwrap();
+    let plateau = match Some(s.trim())
+        .filter(|x| !x.is_empty())
+        .map(parse_plateau)
+        .transpose()
+    {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return;
+        }
+    };
+
+    let cfg = fuzzer_libafl::FuzzConfig {
+        iters,
+        elf_path,
+        extra_programs,
+        idl_path,
+        clock_schedule,
+        compute_budget,
+        ops_version: None,
+        dict_path,
+        seed_dirs,
+        keymap_path,
+        cores,
+        broker_port,
+        seed,
+        stop: fuzzer_libafl::StopConditions {
+            duration,
+            findings,
+            plateau,
+        },
+    };
+    if let Err(e) = fuzzer_libafl::run_fuzzdelsol(&cfg) {
         eprintln!("Error: {e:?}");
     }
 }
+
+/// Option chung của replay/convert/minimize/cmin -> (config, tham số vị trí)
+fn parse_target_args(args: &[String]) -> Result<(fuzzer_libafl::FuzzConfig, Vec<String>), String> {
+    let mut cfg = fuzzer_libafl::FuzzConfig::default();
+    let mut positional = Vec::new();
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        let mut value = || {
+            it.next()
+                .cloned()
+                .ok_or_else(|| format!("{a}: missing value"))
+        };
+        match a.as_str() {
+            "--program" => cfg.extra_programs.extend(parse_extra_programs(&value()?)?),
+            "--idl" => cfg.idl_path = Some(value()?),
+            "--clock" => cfg.clock_schedule = parse_clock_schedule(&value()?)?,
+            "--cu" => cfg.compute_budget = value()?.parse().map_err(|e| format!("--cu: {e}"))?,
+            "--keymap" => cfg.keymap_path = Some(value()?),
+            "--ops-version" => cfg.ops_version = Some(parse_ops_version(&value()?)?),
+            _ => positional.push(a.clone()),
+        }
+    }
+    Ok((cfg, positional))
+}
+
+fn replay_cmd(args: &[String]) -> Result<(), String> {
+    let (mut cfg, positional) = parse_target_args(args)?;
+    let [elf_path, input_path] = positional.as_slice() else {
+        return Err("usage: replay <elf> <input> [--program id=path.so]... [--idl path] [--clock spec] [--cu N] [--ops-version N] [--keymap path]".into());
+    };
+    cfg.elf_path = elf_path.clone();
+    fuzzer_libafl::replay(&cfg, input_path).map_err(|e| e.to_string())
+}
+
+/// Input raw legacy (hoặc typed version cũ) -> typed format hiện tại
+fn convert_cmd(args: &[String]) -> Result<(), String> {
+    let (mut cfg, positional) = parse_target_args(args)?;
+    let (elf_path, input_path, out_path) = match positional.as_slice() {
+        [elf, input] => (elf, input, format!("{input}.tx.json")),
+        [elf, input, out] => (elf, input, out.clone()),
+        _ => {
+            return Err(
+                "usage: convert <elf> <input> [out.tx.json] [--program id=path.so]... [--idl path] [--clock spec] [--cu N] [--ops-version N]"
+                    .into(),
+            )
+        }
+    };
+    cfg.elf_path = elf_path.clone();
+    fuzzer_libafl::convert(&cfg, input_path, &out_path).map_err(|e| e.to_string())
+}
+
+/// Thu nhỏ finding, giữ class + bucket -> reproducer nhỏ nhất + report
+fn minimize_cmd(args: &[String]) -> Result<(), String> {
+    let (mut cfg, positional) = parse_target_args(args)?;
+    let (elf_path, input_path, out_path) = match positional.as_slice() {
+        [elf, input] => {
+            let stem = input.strip_suffix(".tx.json").unwrap_or(input);
+            (elf, input, format!("{stem}.min.tx.json"))
+        }
+        [elf, input, out] => (elf, input, out.clone()),
+        _ => {
+            return Err(
+                "usage: minimize <elf> <input> [out.tx.json] [--program id=path.so]... [--idl path] [--clock spec] [--cu N] [--ops-version N] [--keymap path]"
+                    .into(),
+            )
+        }
+    };
+    cfg.elf_path = elf_path.clone();
+    minimize::minimize(&cfg, input_path, &out_path).map_err(|e| e.to_string())
+}
+
+/// Distill (và merge) corpus của một hay nhiều campaign vào `out_dir`
+fn cmin_cmd(args: &[String]) -> Result<(), String> {
+    let (mut cfg, positional) = parse_target_args(args)?;
+    let (elf_path, out_dir, in_dirs) = match positional.as_slice() {
+        [elf, out, in_dirs @ ..] if !in_dirs.is_empty() => (elf, out, in_dirs),
+        _ => {
+            return Err(
+                "usage: cmin <elf> <out_dir> <in_dir>... [--program id=path.so]... [--idl path] [--clock spec] [--cu N] [--ops-version N] [--keymap path]"
+                    .into(),
+            )
+        }
+    };
+    cfg.elf_path = elf_path.clone();
+    cmin::cmin(&cfg, in_dirs, out_dir).map_err(|e| e.to_string())
+}
+
+/// Version encoding input raw, tối đa `OPS_VERSION` (0 = corpus ghi trước khi có extended op / key dictionary)
+fn parse_ops_version(s: &str) -> Result<u8, String> {
+    let v: u8 = s.parse().map_err(|e| format!("--ops-version: {e}"))?;
+    if v > vm_rbpf::OPS_VERSION {
+        return Err(format!(
+            "--ops-version: {v} is newer than this build ({})",
+            vm_rbpf::OPS_VERSION
+        ));
+    }
+    Ok(v)
+}
+
+/// "fixed" | "monotonic" | "monotonic:<slot_step>:<secs_step>"
+/// (mặc định mỗi tx ~1 phút: 150 slot, 60 giây)
+fn parse_clock_schedule(s: &str) -> Result<ClockSchedule, String> {
+    let mut parts = s.split(':');
+    match parts.next().unwrap_or("") {
+        "" | "fixed" => Ok(ClockSchedule::Fixed),
+        "monotonic" => {
+            let slot_step = parts
+                .next()
+                .map(str::parse)
+                .transpose()
+                .map_err(|e| format!("bad slot step: {e}"))?;
+            let secs_step = parts
+                .next()
+                .map(str::parse)
+                .transpose()
+                .map_err(|e| format!("bad secs step: {e}"))?;
+            Ok(ClockSchedule::Monotonic {
+                slot_step: slot_step.unwrap_or(150),
+                secs_step: secs_step.unwrap_or(60),
+            })
+        }
+        other => Err(format!("unknown clock mode `{other}`")),
+    }
+}
+
+/// "90s" | "30m" | "2h" (số trần = giây)
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (n, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let n: u64 = n.parse().map_err(|e| format!("bad duration `{s}`: {e}"))?;
+    match unit.trim() {
+        "s" => Some(Duration::from_secs(n)),
+        "m" => n.checked_mul(60).map(Duration::from_secs),
+        "h" => n.checked_mul(3600).map(Duration::from_secs),
+        other => return Err(format!("bad duration unit `{other}` (s, m, h)")),
+    }
+    .ok_or_else(|| format!("duration `{s}` is too large"))
+}
+
+/// "3" (class bất kỳ) | "LAMPORTS_THEFT=1,MOC=5"
+fn parse_findings_limit(s: &str) -> Result<Vec<(Option<String>, usize)>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|item| {
+            let (class, n) = match item.split_once('=') {
+                Some((c, n)) => (Some(c.trim().to_uppercase()), n),
+                None => (None, item),
+            };
+            if let Some(c) = class.as_deref().filter(|c| !CLASSES.contains(c)) {
+                return Err(format!(
+                    "unknown finding class `{c}` (one of {})",
+                    CLASSES.join(", ")
+                ));
+            }
+            let n: usize = n
+                .trim()
+                .parse()
+                .map_err(|e| format!("bad findings limit `{item}`: {e}"))?;
+            Ok((class, n.max(1)))
+        })
+        .collect()
+}
+
+/// "10m" (thời gian) | "50000" (số lần thực thi)
+fn parse_plateau(s: &str) -> Result<Plateau, String> {
+    match s.parse() {
+        Ok(n) => Ok(Plateau::Execs(n)),
+        Err(_) => parse_duration(s).map(Plateau::Time),
+    }
+}
+
+/// "id1=a.so,id2=b.so" -> [(id1, a.so), (id2, b.so)]
+fn parse_extra_programs(s: &str) -> Result<Vec<(Pubkey, String)>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|item| {
+            let (id, path) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected program_id=path, got `{item}`"))?;
+            let id =
+                Pubkey::from_str(id.trim()).map_err(|e| format!("bad program id `{id}`: {e}"))?;
+            Ok((id, path.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_schedule() {
+        assert_eq!(parse_clock_schedule(""), Ok(ClockSchedule::Fixed));
+        assert_eq!(parse_clock_schedule("fixed"), Ok(ClockSchedule::Fixed));
+        assert_eq!(
+            parse_clock_schedule("monotonic"),
+            Ok(ClockSchedule::Monotonic {
+                slot_step: 150,
+                secs_step: 60
+            })
+        );
+        assert_eq!(
+            parse_clock_schedule("monotonic:2:-5"),
+            Ok(ClockSchedule::Monotonic {
+                slot_step: 2,
+                secs_step: -5
+            })
+        );
+        assert!(parse_clock_schedule("monotonic:x").is_err());
+        assert!(parse_clock_schedule("daily").is_err());
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3d").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        // n * 3600 tràn u64
+        assert!(parse_duration(&format!("{}h", u64::MAX / 60)).is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn findings_limit() {
+        assert_eq!(parse_findings_limit("3"), Ok(vec![(None, 3)]));
+        assert_eq!(
+            parse_findings_limit("lamports_theft=1, MOC=5,"),
+            Ok(vec![
+                (Some("LAMPORTS_THEFT".into()), 1),
+                (Some("MOC".into()), 5)
+            ])
+        );
+        // 0 -> 1: dừng ngay ở finding đầu tiên
+        assert_eq!(
+            parse_findings_limit("MOC=0"),
+            Ok(vec![(Some("MOC".into()), 1)])
+        );
+        assert!(parse_findings_limit("MOC=x").is_err());
+        // class gõ sai -> lỗi thay vì giới hạn không bao giờ khớp
+        assert!(parse_findings_limit("LAMPORT_THEFT=1").is_err());
+    }
+
+    #[test]
+    fn plateau() {
+        assert!(matches!(parse_plateau("50000"), Ok(Plateau::Execs(50000))));
+        assert!(
+            matches!(parse_plateau("10m"), Ok(Plateau::Time(d)) if d == Duration::from_secs(600))
+        );
+        assert!(parse_plateau("soon").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::evaluator::TransactionEvaluator;
+use crate::fuzzer_libafl::{FuzzConfig, FuzzTarget};
+use crate::txfile::{TxFile, TxFileInstruction};
+use crate::types::{hex, ExecResult};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+
+/// Giới hạn số lần chạy của một lần minimize
+const MAX_EXECS: u64 = 20_000;
+
+/// Shrink một finding trên typed format: giữ nguyên class + bucket (signature root cause)
+struct Shrinker {
+    target: FuzzTarget,
+    class: String,
+    bucket_id: String,
+    execs: u64,
+}
+
+fn data(ix: &TxFileInstruction) -> Vec<u8> {
+    ::hex::decode(&ix.data).unwrap_or_default()
+}
+
+/// Bỏ account override của account không còn được tham chiếu
+fn prune(mut file: TxFile) -> TxFile {
+    let referenced: BTreeSet<&str> = file
+        .instructions
+        .iter()
+        .flat_map(|ix| ix.accounts.iter().map(|m| m.pubkey.as_str()))
+        .collect();
+    let keep: Vec<bool> = file
+        .account_overrides
+        .iter()
+        .map(|a| referenced.contains(a.pubkey.as_str()))
+        .collect();
+    let mut keep = keep.into_iter();
+    file.account_overrides.retain(|_| keep.next().unwrap());
+    file
+}
+
+/// (instruction, meta, signer, byte data, byte data khác 0)
+fn size(file: &TxFile) -> (usize, usize, usize, usize, usize) {
+    let ixs = &file.instructions;
+    let bytes: Vec<u8> = ixs.iter().flat_map(data).collect();
+    (
+        ixs.len(),
+        ixs.iter().map(|ix| ix.accounts.len()).sum(),
+        ixs.iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|m| m.is_signer)
+            .count(),
+        bytes.len(),
+        bytes.iter().filter(|&&b| b != 0).count(),
+    )
+}
+
+impl Shrinker {
+    fn run(&mut self, file: &TxFile) -> Option<ExecResult> {
+        let (tx, overrides) = file.to_transaction(self.target.roles()).ok()?;
+        self.execs += 1;
+        Some(self.target.execute_tx(tx, &overrides))
+    }
+
+    fn reproduces(&mut self, file: &TxFile) -> bool {
+        if self.execs >= MAX_EXECS {
+            return false;
+        }
+        self.run(file).is_some_and(|exec| {
+            exec.signals.any()
+                && exec.signals.class() == self.class
+                && self.target.bucket(&exec).id == self.bucket_id
+        })
+    }
+
+    /// Giữ candidate nếu vẫn tái hiện finding
+    fn accept(&mut self, cur: &mut TxFile, cand: TxFile) -> bool {
+        let cand = prune(cand);
+        if self.reproduces(&cand) {
+            *cur = cand;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Bỏ nguyên instruction khác (sibling) trong tx
+    fn drop_instructions(&mut self, cur: &mut TxFile) -> bool {
+        let mut changed = false;
+        for i in (0..cur.instructions.len()).rev() {
+            if i == cur.instruction_index || i >= cur.instructions.len() {
+                continue;
+            }
+            let mut cand = cur.clone();
+            cand.instructions.remove(i);
+            if i < cand.instruction_index {
+                cand.instruction_index -= 1;
+            }
+            changed |= self.accept(cur, cand);
+        }
+        changed
+    }
+
+    fn drop_metas(&mut self, cur: &mut TxFile) -> bool {
+        let mut changed = false;
+        for i in 0..cur.instructions.len() {
+            for j in (0..cur.instructions[i].accounts.len()).rev() {
+                let mut cand = cur.clone();
+                cand.instructions[i].accounts.remove(j);
+                changed |= self.accept(cur, cand);
+            }
+        }
+        changed
+    }
+
+    fn drop_signers(&mut self, cur: &mut TxFile) -> bool {
+        let mut changed = false;
+        for i in 0..cur.instructions.len() {
+            for j in 0..cur.instructions[i].accounts.len() {
+                if !cur.instructions[i].accounts[j].is_signer {
+                    continue;
+                }
+                let mut cand = cur.clone();
+                cand.instructions[i].accounts[j].is_signer = false;
+                changed |= self.accept(cur, cand);
+            }
+        }
+        changed
+    }
+
+    /// Cắt data theo khối (len/2, len/4, ..., 1 byte), từ cuối về đầu
+    fn truncate_data(&mut self, cur: &mut TxFile) -> bool {
+        let mut changed = false;
+        for i in 0..cur.instructions.len() {
+            let mut chunk = data(&cur.instructions[i]).len().div_ceil(2);
+            while chunk > 0 {
+                let mut end = data(&cur.instructions[i]).len();
+                while end > 0 {
+                    let start = end.saturating_sub(chunk);
+                    let mut bytes = data(&cur.instructions[i]);
+                    bytes.drain(start..end);
+                    let mut cand = cur.clone();
+                    cand.instructions[i].data = hex(&bytes);
+                    changed |= self.accept(cur, cand);
+                    end = start;
+                }
+                chunk /= 2;
+            }
+        }
+        changed
+    }
+
+    fn zero_data(&mut self, cur: &mut TxFile) -> bool {
+        let mut changed = false;
+        for i in 0..cur.instructions.len() {
+            for k in 0..data(&cur.instructions[i]).len() {
+                let mut bytes = data(&cur.instructions[i]);
+                if bytes[k] == 0 {
+                    continue;
+                }
+                bytes[k] = 0;
+                let mut cand = cur.clone();
+                cand.instructions[i].data = hex(&bytes);
+                changed |= self.accept(cur, cand);
+            }
+        }
+        changed
+    }
+
+    /// Chạy các pass tới fixpoint: bỏ metas có thể mở đường cắt data và ngược lại
+    fn shrink(&mut self, cur: &mut TxFile) {
+        loop {
+            let mut changed = self.drop_instructions(cur);
+            changed |= self.drop_metas(cur);
+            changed |= self.drop_signers(cur);
+            changed |= self.truncate_data(cur);
+            changed |= self.zero_data(cur);
+            if !changed || self.execs >= MAX_EXECS {
+                break;
+            }
+        }
+    }
+}
+
+/// Thu nhỏ một finding (typed hoặc raw) tới khi không bỏ thêm được gì mà vẫn giữ class + bucket.
+/// Ghi `out_path` (typed) và report bên cạnh (`<out>.report.txt` / `.report.json`)
+pub fn minimize(cfg: &FuzzConfig, input_path: &str, out_path: &str) -> io::Result<()> {
+    let input = fs::read(input_path)?;
+    let mut target = FuzzTarget::setup(cfg)?;
+    let raw = TxFile::from_json(&input)
+        .is_none()
+        .then_some(input.as_slice());
+    let exec = target.execute_file(&input)?;
+    if !exec.signals.any() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{input_path}: no finding to minimize"),
+        ));
+    }
+    let b = target.bucket(&exec);
+    println!(
+        "[*] minimize {input_path}: {} bucket={} {}",
+        b.class,
+        b.id,
+        b.signature.join(" ")
+    );
+
+    let mut cur = TxFile::from_exec(&exec, target.roles(), raw);
+    // input raw chỉ còn để tham khảo, không khớp với tx đã thu nhỏ
+    cur.raw = None;
+    let before = size(&cur);
+    let mut s = Shrinker {
+        target,
+        class: b.class,
+        bucket_id: b.id,
+        execs: 0,
+    };
+    if !s.reproduces(&cur) {
+        return Err(io::Error::other(format!(
+            "{input_path}: finding does not reproduce from the typed tx"
+        )));
+    }
+
+    s.shrink(&mut cur);
+    if s.execs >= MAX_EXECS {
+        println!("[!] stopped after {MAX_EXECS} executions, result may not be minimal");
+    }
+
+    let after = size(&cur);
+    println!("[*] {} executions", s.execs);
+    println!("    instructions {} -> {}", before.0, after.0);
+    println!("    account metas {} -> {}", before.1, after.1);
+    println!("    signers {} -> {}", before.2, after.2);
+    println!(
+        "    data bytes {} -> {} ({} -> {} non-zero)",
+        before.3, after.3, before.4, after.4
+    );
+
+    let exec = s.run(&cur).expect("minimized tx parses");
+    let (mut best_cov_hash, mut max_cu) = (0, 0);
+    let report = TransactionEvaluator::evaluate(&exec, &mut best_cov_hash, &mut max_cu)
+        .report
+        .map(|r| s.target.finish_report(&exec, r, None, None))
+        .expect("minimized tx still reproduces");
+
+    fs::write(out_path, cur.to_json())?;
+    let stem = out_path.strip_suffix(".tx.json").unwrap_or(out_path);
+    fs::write(format!("{stem}.report.txt"), report.to_text())?;
+    fs::write(format!("{stem}.report.json"), report.to_json())?;
+    if let Some(e) = &report.export {
+        e.write_accounts(&format!("{stem}.accounts"))?;
+    }
+    println!("[*] {out_path} + {stem}.report.{{txt,json}} + {stem}.accounts/");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrinker_keeps_class_and_bucket() {
+        let mut target = FuzzTarget::setup(&FuzzConfig::default()).unwrap();
+        let mut s: u64 = 7;
+        let (mut checked, mut shrunk) = (0, false);
+        while checked < 5 {
+            let input: Vec<u8> = (0..96)
+                .map(|_| {
+                    s = s
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (s >> 56) as u8
+                })
+                .collect();
+            let exec = target.execute(&input);
+            if !exec.signals.any() {
+                continue;
+            }
+            let b = target.bucket(&exec);
+            let mut cur = TxFile::from_exec(&exec, target.roles(), None);
+            let before = size(&cur);
+            let mut sh = Shrinker {
+                target,
+                class: b.class.clone(),
+                bucket_id: b.id.clone(),
+                execs: 0,
+            };
+            assert!(sh.reproduces(&cur));
+            sh.shrink(&mut cur);
+
+            let exec = sh.run(&cur).unwrap();
+            assert_eq!(exec.signals.class(), b.class);
+            assert_eq!(sh.target.bucket(&exec).id, b.id);
+            let after = size(&cur);
+            assert!(after.0 <= before.0 && after.1 <= before.1 && after.2 <= before.2);
+            assert!(after.3 <= before.3 && after.4 <= before.4);
+            shrunk |= after != before;
+            target = sh.target;
+            checked += 1;
+        }
+        assert!(shrunk);
+    }
+}
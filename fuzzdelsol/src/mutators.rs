@@ -0,0 +1,376 @@
+//! Mutator hiểu layout input của TxGenerator: header (n_accounts, signer mask, mode, indices,
+//! extension) được sửa có chủ đích, havoc/splice chỉ đụng tới ix data.
+
+use crate::txgen::{mal_mod, TxGenerator};
+use libafl::corpus::Corpus;
+use libafl::inputs::{BytesInput, HasMutatorBytes, UsesInput};
+use libafl::mutators::{MutationResult, Mutator};
+use libafl::random_corpus_id_with_disabled;
+use libafl::state::{HasCorpus, HasRand};
+use libafl::Error;
+use libafl_bolts::rands::Rand;
+use libafl_bolts::Named;
+use std::borrow::Cow;
+
+const MAX_ACCOUNTS: u8 = 7;
+/// bit 0..6 của signer mask; bit7 (extension) đổi độ dài header nên không lật
+const N_SIGNER_BITS: usize = 7;
+
+/// Thay toàn bộ input bằng header đã sửa + ix data
+fn rebuild(input: &mut BytesInput, header: &[u8], data: &[u8]) {
+    let mut out = header.to_vec();
+    out.extend_from_slice(data);
+    *input = BytesInput::new(out);
+}
+
+macro_rules! named {
+    ($ty:ident) => {
+        impl Named for $ty {
+            fn name(&self) -> &Cow<'static, str> {
+                static NAME: Cow<'static, str> = Cow::Borrowed(stringify!($ty));
+                &NAME
+            }
+        }
+    };
+}
+
+/// Lật một bit của signer mask (attacker/user ký, sysvar, spoof, sibling ed25519...)
+#[derive(Debug, Default)]
+pub struct SignerFlipMutator;
+named!(SignerFlipMutator);
+
+impl<S: HasRand> Mutator<BytesInput, S> for SignerFlipMutator {
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (mut header, data) = TxGenerator::split_input(input.bytes());
+        header[1] ^= 1 << state.rand_mut().below(N_SIGNER_BITS);
+        let data = data.to_vec();
+        rebuild(input, &header, &data);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+/// Lật writable của một account meta (mask trong extension của header), meta khác giữ nguyên
+#[derive(Debug, Default)]
+pub struct WritableToggleMutator;
+named!(WritableToggleMutator);
+
+impl<S: HasRand> Mutator<BytesInput, S> for WritableToggleMutator {
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (mut header, data) = TxGenerator::split_input(input.bytes());
+        // meta của tx: các slot (sau dedup) + user + attacker
+        let n = (header[0] % 8).max(1) as usize;
+        TxGenerator::flip_writable(&mut header, state.rand_mut().below((n + 2).min(8)));
+        let data = data.to_vec();
+        rebuild(input, &header, &data);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+/// Đổi account ở một slot: chuyển sang pool còn lại (benign <-> malicious) hoặc
+/// lấy index của slot khác (tx ít account khác nhau hơn)
+#[derive(Debug, Default)]
+pub struct AccountSwapMutator;
+named!(AccountSwapMutator);
+
+impl<S: HasRand> Mutator<BytesInput, S> for AccountSwapMutator {
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (mut header, data) = TxGenerator::split_input(input.bytes());
+        let n = (header[0] % 8).max(1) as usize;
+        let a = 3 + state.rand_mut().below(n);
+        let b = 3 + state.rand_mut().below(n);
+        if a != b && state.rand_mut().coinflip(0.5) {
+            header[a] = header[b];
+        } else {
+            // index % mal_mod == 0 -> malicious pool: cộng mal_mod giữ pool (đổi account),
+            // còn lại nhảy tới index kế tiếp ở pool kia
+            let m = mal_mod(header[2]);
+            let rem = header[a] as usize % m;
+            let step = if state.rand_mut().coinflip(0.5) {
+                m
+            } else if rem == 0 {
+                1
+            } else {
+                m - rem
+            };
+            // u8 wrap (256 không chia hết cho 3) có thể đổi pool: bỏ qua
+            let next = header[a] as usize + step;
+            if next > u8::MAX as usize {
+                return Ok(MutationResult::Skipped);
+            }
+            header[a] = next as u8;
+        }
+        let data = data.to_vec();
+        rebuild(input, &header, &data);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+/// Thêm một account slot (n_accounts + 1, index mới chèn vào vị trí ngẫu nhiên)
+#[derive(Debug, Default)]
+pub struct AccountInsertMutator;
+named!(AccountInsertMutator);
+
+impl<S: HasRand> Mutator<BytesInput, S> for AccountInsertMutator {
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (mut header, data) = TxGenerator::split_input(input.bytes());
+        let n = (header[0] % 8).max(1);
+        if n >= MAX_ACCOUNTS {
+            return Ok(MutationResult::Skipped);
+        }
+        // giữ bit cao của byte 0 (chọn meta bị duplicate)
+        header[0] = (header[0] & !7) | (n + 1);
+        let at = 3 + state.rand_mut().below(n as usize + 1);
+        header.insert(at, state.rand_mut().below(256) as u8);
+        let data = data.to_vec();
+        rebuild(input, &header, &data);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+/// Bỏ một account slot (n_accounts - 1)
+#[derive(Debug, Default)]
+pub struct AccountRemoveMutator;
+named!(AccountRemoveMutator);
+
+impl<S: HasRand> Mutator<BytesInput, S> for AccountRemoveMutator {
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (mut header, data) = TxGenerator::split_input(input.bytes());
+        let n = (header[0] % 8).max(1);
+        if n <= 1 {
+            return Ok(MutationResult::Skipped);
+        }
+        header[0] = (header[0] & !7) | (n - 1);
+        header.remove(3 + state.rand_mut().below(n as usize));
+        let data = data.to_vec();
+        rebuild(input, &header, &data);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+/// Chạy mutator `M` (vd havoc + token) chỉ trên ix data, header giữ nguyên
+#[derive(Debug)]
+pub struct DataMutator<M> {
+    inner: M,
+}
+
+impl<M> DataMutator<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<M> Named for DataMutator<M> {
+    fn name(&self) -> &Cow<'static, str> {
+        static NAME: Cow<'static, str> = Cow::Borrowed("DataMutator");
+        &NAME
+    }
+}
+
+impl<M, S> Mutator<BytesInput, S> for DataMutator<M>
+where
+    M: Mutator<BytesInput, S>,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let (header, data) = TxGenerator::split_input(input.bytes());
+        let mut data = BytesInput::new(data.to_vec());
+        if data.bytes().is_empty() {
+            data.resize(1, 0);
+        }
+        let res = self.inner.mutate(state, &mut data)?;
+        if res == MutationResult::Mutated {
+            rebuild(input, &header, data.bytes());
+        }
+        Ok(res)
+    }
+}
+
+/// Splice ix data của hai corpus entry: header của input hiện tại + data[..cut] + data của entry kia từ cut
+#[derive(Debug, Default)]
+pub struct DataSpliceMutator;
+named!(DataSpliceMutator);
+
+impl<S> Mutator<BytesInput, S> for DataSpliceMutator
+where
+    S: HasCorpus + UsesInput<Input = BytesInput> + HasRand,
+{
+    fn mutate(&mut self, state: &mut S, input: &mut BytesInput) -> Result<MutationResult, Error> {
+        let id = random_corpus_id_with_disabled!(state.corpus(), state.rand_mut());
+        if state.corpus().current().as_ref() == Some(&id) {
+            return Ok(MutationResult::Skipped);
+        }
+        let other_data = {
+            let mut other = state.corpus().get_from_all(id)?.borrow_mut();
+            let other = other.load_input(state.corpus())?;
+            TxGenerator::split_input(other.bytes()).1.to_vec()
+        };
+
+        let (header, data) = TxGenerator::split_input(input.bytes());
+        let cut = state.rand_mut().below(data.len().min(other_data.len()) + 1);
+        let mut spliced = data[..cut].to_vec();
+        spliced.extend_from_slice(&other_data[cut..]);
+        if spliced == data {
+            return Ok(MutationResult::Skipped);
+        }
+        rebuild(input, &header, &spliced);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::BlockchainEmulator;
+    use libafl::corpus::{InMemoryCorpus, Testcase};
+    use libafl::feedbacks::ConstFeedback;
+    use libafl::mutators::ByteFlipMutator;
+    use libafl::state::StdState;
+    use libafl_bolts::rands::StdRand;
+    use solana_sdk::pubkey::Pubkey;
+
+    type State =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// header đủ / thiếu / có extension (clock + mask) / có duplicate meta
+    const SEEDS: [&[u8]; 5] = [
+        &[],
+        &[2, 0x01, 0x22],
+        &[3, 0x13, 0x62, 1, 2, 3, 0xde, 0xad],
+        &[
+            7, 0x82, 0xe6, 1, 2, 3, 4, 5, 6, 7, 0x03, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13,
+            14, 15, 16, 0x05, 0xaa,
+        ],
+        &[1, 0x80, 0x04, 9, 0x02, 0x01, 0x10, 0x20, 0x30],
+    ];
+
+    fn state() -> State {
+        let mut corpus = InMemoryCorpus::new();
+        for seed in SEEDS {
+            corpus
+                .add(Testcase::new(BytesInput::new(seed.to_vec())))
+                .unwrap();
+        }
+        StdState::new(
+            StdRand::with_seed(7),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut ConstFeedback::new(false),
+            &mut ConstFeedback::new(false),
+        )
+        .unwrap()
+    }
+
+    /// Chạy `m` nhiều lần trên mỗi seed; `check(before, after)` cho mỗi lần Mutated
+    fn run<M: Mutator<BytesInput, State>>(mut m: M, check: impl Fn(&[u8], &[u8])) {
+        let mut state = state();
+        let emu = BlockchainEmulator::new();
+        let program_id = Pubkey::new_unique();
+        for seed in SEEDS {
+            let mut input = BytesInput::new(seed.to_vec());
+            for _ in 0..200 {
+                let before = input.bytes().to_vec();
+                if m.mutate(&mut state, &mut input).unwrap() == MutationResult::Mutated {
+                    let after = input.bytes();
+                    // header parse được: đủ byte, tách ra rồi ghép lại không đổi
+                    assert!(
+                        TxGenerator::header_len(after) <= after.len(),
+                        "{after:02x?}"
+                    );
+                    let (header, data) = TxGenerator::split_input(after);
+                    assert_eq!([&header[..], data].concat(), after);
+                    TxGenerator::from_bytes(after, &emu, program_id);
+                    check(&before, after);
+                }
+            }
+        }
+    }
+
+    fn header(input: &[u8]) -> Vec<u8> {
+        TxGenerator::split_input(input).0
+    }
+
+    fn data(input: &[u8]) -> Vec<u8> {
+        TxGenerator::split_input(input).1.to_vec()
+    }
+
+    #[test]
+    fn signer_flip_changes_one_low_bit() {
+        run(SignerFlipMutator, |before, after| {
+            let diff = header(before)[1] ^ after[1];
+            assert!(diff.is_power_of_two() && diff < 0x80);
+            assert_eq!(data(before), data(after));
+        });
+    }
+
+    #[test]
+    fn writable_toggle_keeps_mode_and_data() {
+        run(WritableToggleMutator, |before, after| {
+            let b = header(before);
+            // chỉ thêm extension (signer bit7), n_accounts / mode / indices giữ nguyên
+            assert_eq!((b[0], b[1] | 0x80, b[2]), (after[0], after[1], after[2]));
+            assert_eq!(
+                b[3..3 + (b[0] % 8).max(1) as usize],
+                after[3..3 + (b[0] % 8).max(1) as usize]
+            );
+            assert_eq!(data(before), data(after));
+        });
+    }
+
+    #[test]
+    fn account_swap_keeps_pool_sizes() {
+        run(AccountSwapMutator, |before, after| {
+            let (b, a) = (header(before), header(after));
+            assert_eq!(b.len(), a.len());
+            assert_eq!(b[..3], a[..3]);
+            assert_eq!(data(before), data(after));
+        });
+    }
+
+    #[test]
+    fn account_swap_step_stays_in_pool() {
+        let mut state = state();
+        for mode in [0x22u8, 0x62, 0xa2, 0xe2] {
+            let m = mal_mod(mode);
+            let mut same = 0;
+            for _ in 0..200 {
+                let mut input = BytesInput::new(vec![1, 0x01, mode, 0x40]);
+                if AccountSwapMutator.mutate(&mut state, &mut input).unwrap()
+                    == MutationResult::Mutated
+                {
+                    let after = input.bytes()[3] as usize;
+                    if after == 0x40 + m {
+                        same += 1;
+                        assert_eq!(after % m, 0x40 % m);
+                    } else {
+                        // đổi pool: malicious <-> benign
+                        assert_ne!(after.is_multiple_of(m), 0x40 % m == 0);
+                    }
+                }
+            }
+            assert!(same > 0);
+        }
+    }
+
+    #[test]
+    fn account_insert_and_remove_adjust_count() {
+        run(AccountInsertMutator, |before, after| {
+            let n = |h: &[u8]| (h[0] % 8).max(1);
+            assert_eq!(n(&header(after)), n(&header(before)) + 1);
+            assert_eq!(data(before), data(after));
+        });
+        run(AccountRemoveMutator, |before, after| {
+            let n = |h: &[u8]| (h[0] % 8).max(1);
+            assert_eq!(n(&header(after)) + 1, n(&header(before)));
+            assert_eq!(data(before), data(after));
+        });
+    }
+
+    #[test]
+    fn data_mutations_leave_header_untouched() {
+        run(DataMutator::new(ByteFlipMutator::new()), |before, after| {
+            assert_eq!(header(before), header(after));
+        });
+        run(DataSpliceMutator, |before, after| {
+            assert_eq!(header(before), header(after));
+        });
+    }
+}
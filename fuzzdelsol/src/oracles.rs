@@ -1,21 +1,50 @@
+use crate::layout::LayoutModel;
+use crate::token::{mints, token_accounts, Mint, TokenAccount};
 use crate::types::{LedgerSnapshot, OracleSignals, TaintEngine, Transaction};
+use solana_sdk::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use solana_sdk::instruction::TRANSACTION_LEVEL_STACK_HEIGHT;
 use solana_sdk::pubkey::Pubkey;
-use std::collections::BTreeSet;
+use solana_sdk::rent::Rent;
+use solana_sdk::sysvar;
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Clone, Debug)]
 pub struct OracleContext {
     pub program_id: Pubkey,
     pub attacker: Pubkey,
     pub user: Pubkey,
+    pub layouts: LayoutModel,
+    pub rent: Rent,
+}
+
+/// VM event kèm frame phát ra nó (program + invoke depth, top-level = 1)
+#[derive(Clone, Debug)]
+pub struct FrameEvent {
+    pub program_id: Pubkey,
+    #[allow(dead_code)]
+    pub depth: usize,
+    /// site trong TraceVM: pc handler phát event
+    pub pc: u64,
+    pub event: VmEvent,
 }
 
 /// VM events
 #[derive(Clone, Debug)]
 pub enum VmEvent {
+    // offsets: byte input region (top-level) chảy vào toán hạng
     Cmp {
         lhs_tainted: bool,
         rhs_tainted: bool,
         used_for_auth: bool,
+        offsets: Vec<usize>,
+        // giá trị toán hạng (CmpLog)
+        lhs: u64,
+        rhs: u64,
+    },
+    // sol_memcmp (sighash / key equality)
+    MemCmp {
+        lhs: Vec<u8>,
+        rhs: Vec<u8>,
     },
     ReadAccountData {
         acct: Pubkey,
@@ -27,29 +56,142 @@ pub enum VmEvent {
     },
     WriteData {
         acct: Pubkey,
+        offset: usize,
         nbytes: usize,
     },
     Cpi {
         invoked_program: Pubkey,
         provided: Vec<Pubkey>,
+        // index account meta (trong instruction của frame gọi) mà input chọn làm program id
+        // của CPI, nếu key chưa được check; None = program id hard-code hoặc self
+        program_source: Option<usize>,
+        // account được forward với signer/writable
+        privileged: Vec<Pubkey>,
+    },
+    // CPI bị runtime từ chối (depth limit / privilege escalation), không mở frame callee
+    CpiRejected {
+        #[allow(dead_code)]
+        invoked_program: Pubkey,
+        #[allow(dead_code)]
+        reason: &'static str,
     },
     KeyAccess {
         required_key: Pubkey,
         provided_keys: Vec<Pubkey>,
         used_for_auth: bool,
+        offsets: Vec<usize>,
     },
     IntegerOp {
         tainted: bool,
         overflowed: bool,
+        offsets: Vec<usize>,
+    },
+    // deserialize account data thành một account type
+    ReadAccountAs {
+        acct: Pubkey,
+        expected: [u8; 8],
+        actual: [u8; 8],
+        checked: bool,
+    },
+    // lamport transfer giữa 2 writable meta (index trong instruction accounts)
+    MetaTransfer {
+        from_index: usize,
+        to_index: usize,
+        from: Pubkey,
+        to: Pubkey,
+        #[allow(dead_code)]
+        amount: u64,
+    },
+    // program tạo account mới, trả rent từ `payer`
+    CreateAccount {
+        payer: Pubkey,
+        // index meta đã cung cấp payer nếu payer do attacker chọn
+        payer_source: Option<usize>,
+        new_account: Pubkey,
+        lamports: u64,
+        space: usize,
+    },
+    // đọc sysvar qua AccountInfo
+    ReadSysvar {
+        acct: Pubkey,
+        owner: Pubkey,
+        sysvar: Pubkey,
+        key_checked: bool,
+    },
+    // đọc Clock (acct = None: syscall, Some: AccountInfo không check key)
+    ClockRead {
+        acct: Option<Pubkey>,
+        slot: u64,
+        unix_timestamp: i64,
+    },
+    // đọc sibling instruction qua Instructions sysvar
+    Introspect {
+        sibling_program: Option<Pubkey>,
+        verify_signer: Option<Pubkey>,
+        program_checked: bool,
+        data_checked: bool,
+        stack_checked: bool,
+        stack_height: usize,
+        passed: bool,
+    },
+    // cost ước lượng vượt compute budget -> instruction fail (state đã rollback)
+    ComputeExhausted {
+        consumed: u64,
+        limit: u64,
+    },
+    Realloc {
+        acct: Pubkey,
+        old_len: usize,
+        new_len: usize,
+    },
+    TokenTransfer {
+        source: Pubkey,
+        destination: Pubkey,
+        #[allow(dead_code)]
+        amount: u64,
+    },
+    // mint_to (delta > 0) / burn (delta < 0)
+    TokenSupplyOp {
+        mint: Pubkey,
+        account: Pubkey,
+        delta: i64,
+        authority: Pubkey,
+    },
+    TokenSetAuthority {
+        account: Pubkey,
+        #[allow(dead_code)]
+        close_authority: bool,
+        #[allow(dead_code)]
+        new_authority: Pubkey,
     },
 }
 
+/// "[a..b, c]" — offset liên tiếp gộp thành range
+fn fmt_offsets(offsets: &[usize]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut iter = offsets.iter().copied().peekable();
+    while let Some(start) = iter.next() {
+        let mut end = start;
+        while iter.peek() == Some(&(end + 1)) {
+            end = iter.next().unwrap();
+        }
+        parts.push(if end == start {
+            start.to_string()
+        } else {
+            format!("{start}..={end}")
+        });
+    }
+    format!("[{}]", parts.join(", "))
+}
+
 pub struct Oracles {
     ctx: OracleContext,
 
     // auth gating
     saw_auth_cmp: bool,
     saw_auth_cmp_with_taint: bool,
+    // input byte offsets chảy vào auth compare
+    auth_cmp_offsets: BTreeSet<usize>,
 
     // 핵심 fix: auth decision depends on malicious-owner data
     auth_depends_on_malicious: bool,
@@ -69,6 +211,24 @@ pub struct Oracles {
 
     // integer bug
     saw_tainted_overflow: bool,
+    overflow_offsets: BTreeSet<usize>,
+
+    // token supply: net mint_to/burn đã được authority hợp lệ ký, theo mint
+    authorized_supply_delta: BTreeMap<Pubkey, i128>,
+
+    // type cosplay: (program, type mong đợi) -> discriminator thực tế đã chấp nhận (không check)
+    accepted_types: BTreeMap<(Pubkey, [u8; 8]), BTreeSet<[u8; 8]>>,
+    // (account, expected, actual) đọc sai type mà không check
+    confused_reads: Vec<(Pubkey, [u8; 8], [u8; 8])>,
+
+    // duplicate mutable: (account, meta i, meta j) khi 2 writable meta alias cùng account
+    aliased_writes: Vec<(Pubkey, usize, usize)>,
+
+    // (account, offset, nbytes) của mọi WriteData
+    data_writes: Vec<(Pubkey, usize, usize)>,
+
+    // account mới tạo trong instruction
+    created_accounts: BTreeSet<Pubkey>,
 }
 
 impl Oracles {
@@ -84,6 +244,7 @@ impl Oracles {
             ctx,
             saw_auth_cmp: false,
             saw_auth_cmp_with_taint: false,
+            auth_cmp_offsets: BTreeSet::new(),
             auth_depends_on_malicious: false,
             msc_candidates: BTreeSet::new(),
             moc_malicious_reads: BTreeSet::new(),
@@ -91,6 +252,13 @@ impl Oracles {
             pre_user_lamports,
             pre_attacker_lamports,
             saw_tainted_overflow: false,
+            overflow_offsets: BTreeSet::new(),
+            authorized_supply_delta: BTreeMap::new(),
+            accepted_types: BTreeMap::new(),
+            confused_reads: Vec::new(),
+            aliased_writes: Vec::new(),
+            data_writes: Vec::new(),
+            created_accounts: BTreeSet::new(),
         }
     }
 
@@ -98,23 +266,33 @@ impl Oracles {
         &mut self,
         tx: &Transaction,
         taint: &TaintEngine,
-        _pre: &LedgerSnapshot,
+        pre: &LedgerSnapshot,
         _post: &LedgerSnapshot,
-        ev: VmEvent,
+        ev: FrameEvent,
         signals: &mut OracleSignals,
     ) {
-        match ev {
+        // emitter: program của frame phát event (top-level hoặc frame callee của CPI)
+        let FrameEvent {
+            program_id: emitter,
+            event,
+            ..
+        } = ev;
+
+        match event {
             VmEvent::Cmp {
                 lhs_tainted,
                 rhs_tainted,
                 used_for_auth,
+                offsets,
+                ..
             } => {
                 if used_for_auth {
                     self.saw_auth_cmp = true;
 
-                    // "tainted auth compare" (input/data taint)
-                    if lhs_tainted || rhs_tainted || taint.input_taint || taint.data_acc_taint {
+                    // "tainted auth compare": toán hạng mang taint thật (byte-level)
+                    if lhs_tainted || rhs_tainted {
                         self.saw_auth_cmp_with_taint = true;
+                        self.auth_cmp_offsets.extend(offsets);
                     }
 
                     // 핵심 FIX (2): auth depends on malicious-owner data
@@ -134,8 +312,10 @@ impl Oracles {
                 }
             }
 
+            VmEvent::MemCmp { .. } => {}
+
             VmEvent::ReadAccountData { acct, owner } => {
-                if owner != self.ctx.program_id {
+                if owner != emitter {
                     self.moc_malicious_reads.insert(acct);
                 }
             }
@@ -144,42 +324,251 @@ impl Oracles {
                 self.modified_accounts.insert(acct);
             }
 
-            VmEvent::WriteData { acct, nbytes: _nbytes } => {
-                // _nbytes to silence warning
+            VmEvent::WriteData { acct, offset, nbytes } => {
                 self.modified_accounts.insert(acct);
+                self.data_writes.push((acct, offset, nbytes));
             }
 
-            // ACPI: only signal if auth tainted exists and attacker involved
+            // ACPI: CPI target lấy từ account attacker truyền vào (không check key)
+            // và có account privileged được forward sang target đó
             VmEvent::Cpi {
                 invoked_program,
                 provided,
+                program_source,
+                privileged,
             } => {
-                let allowed = invoked_program == self.ctx.program_id;
-                if !allowed && self.saw_auth_cmp_with_taint && provided.contains(&self.ctx.attacker) {
-                    signals.acpi = true;
+                if let Some(idx) = program_source {
+                    let self_call = invoked_program == emitter || invoked_program == self.ctx.program_id;
+                    if !self_call && !privileged.is_empty() {
+                        signals.acpi = true;
+                        signals.flag("ACPI", invoked_program);
+                        signals.details.push(format!(
+                            "acpi: program {} invoked {} supplied by account index {} (unchecked), forwarded {} accounts, privileged {:?}",
+                            emitter,
+                            invoked_program,
+                            idx,
+                            provided.len(),
+                            privileged
+                        ));
+                    }
                 }
             }
 
+            VmEvent::CpiRejected { .. } => {}
+
             // MKC: only when used_for_auth
             VmEvent::KeyAccess {
                 required_key,
                 provided_keys,
                 used_for_auth,
+                offsets,
             } => {
                 if used_for_auth {
                     let ok = provided_keys.contains(&required_key);
                     let required_is_signer = tx.signers.contains(&required_key);
                     if !ok && !required_is_signer {
                         signals.mkc = true;
+                        signals.flag("MKC", required_key);
+                        signals.details.push(format!(
+                            "mkc: key {} (selected by input region bytes {}) used for auth without check",
+                            required_key,
+                            fmt_offsets(&offsets)
+                        ));
                     }
                 }
             }
 
-            VmEvent::IntegerOp { tainted, overflowed } => {
+            VmEvent::IntegerOp {
+                tainted,
+                overflowed,
+                offsets,
+            } => {
                 if tainted && overflowed {
                     self.saw_tainted_overflow = true;
+                    self.overflow_offsets.extend(offsets);
+                }
+            }
+
+            VmEvent::ReadAccountAs {
+                acct,
+                expected,
+                actual,
+                checked,
+            } => {
+                // chỉ type layout model biết: discriminator rác không phải một account type
+                let known = self.ctx.layouts.knows(&expected) && self.ctx.layouts.knows(&actual);
+                if !checked && known {
+                    self.accepted_types
+                        .entry((emitter, expected))
+                        .or_default()
+                        .insert(actual);
+                    if actual != expected {
+                        self.confused_reads.push((acct, expected, actual));
+                    }
+                }
+            }
+
+            VmEvent::MetaTransfer {
+                from_index,
+                to_index,
+                from,
+                to,
+                ..
+            } => {
+                self.modified_accounts.insert(from);
+                self.modified_accounts.insert(to);
+                if from == to {
+                    self.aliased_writes.push((from, from_index, to_index));
+                }
+            }
+
+            // rent trả từ account attacker chọn mà account đó không ký
+            VmEvent::CreateAccount {
+                payer,
+                payer_source,
+                new_account,
+                lamports,
+                space,
+            } => {
+                self.modified_accounts.insert(payer);
+                self.created_accounts.insert(new_account);
+                if let Some(idx) = payer_source {
+                    if !tx.signers.contains(&payer) {
+                        signals.rent = true;
+                        signals.flag("RENT", payer);
+                        signals.details.push(format!(
+                            "rent: {} lamports for {} bytes paid from non-signer {} chosen via account index {}",
+                            lamports, space, payer, idx
+                        ));
+                    }
                 }
             }
+
+            // sysvar spoofing: dùng data từ account không phải sysvar id chuẩn mà không check key
+            VmEvent::ReadSysvar {
+                acct,
+                owner,
+                sysvar,
+                key_checked,
+            } => {
+                if acct != sysvar && !key_checked {
+                    signals.sysvar_spoof = true;
+                    signals.flag("SYSVAR_SPOOF", acct);
+                    signals.details.push(format!(
+                        "sysvar_spoof: program {} consumed {} data from look-alike {} (owner {})",
+                        emitter, sysvar, acct, owner
+                    ));
+                }
+            }
+
+            // logic theo thời gian lấy Clock từ account giả -> attacker chọn được thời điểm
+            VmEvent::ClockRead {
+                acct: Some(acct),
+                slot,
+                unix_timestamp,
+            } if acct != sysvar::clock::id() => {
+                signals.sysvar_spoof = true;
+                signals.flag("SYSVAR_SPOOF", acct);
+                signals.details.push(format!(
+                    "sysvar_spoof: program {} took clock slot={} unix_timestamp={} from look-alike {}",
+                    emitter, slot, unix_timestamp, acct
+                ));
+            }
+            VmEvent::ClockRead { .. } => {}
+
+            // check sibling instruction mà attacker tự dựng được vẫn pass
+            VmEvent::Introspect {
+                sibling_program: Some(sibling),
+                verify_signer,
+                program_checked,
+                data_checked,
+                stack_checked,
+                stack_height,
+                passed: true,
+            } => {
+                let why = if !program_checked {
+                    Some(format!("accepted sibling instruction from arbitrary program {sibling}"))
+                } else if !data_checked {
+                    Some(format!(
+                        "accepted ed25519 verify signed by {} without binding the signer to the stored authority",
+                        verify_signer.map(|k| k.to_string()).unwrap_or_else(|| "?".into())
+                    ))
+                } else if !stack_checked && stack_height > TRANSACTION_LEVEL_STACK_HEIGHT {
+                    Some(format!(
+                        "sibling check evaluated inside CPI (stack height {stack_height}) against the outer transaction"
+                    ))
+                } else {
+                    None
+                };
+                if let Some(why) = why {
+                    signals.introspection = true;
+                    signals.flag("INTROSPECTION", sibling);
+                    signals.details.push(format!("introspection: program {emitter} {why}"));
+                }
+            }
+            VmEvent::Introspect { .. } => {}
+
+            VmEvent::ComputeExhausted { consumed, limit } => {
+                signals.dos = true;
+                signals.flag("DOS", emitter);
+                signals.details.push(format!(
+                    "dos: program {emitter} reached approximately {consumed} compute units (approximation, not metered), exceeding the {limit} compute budget"
+                ));
+            }
+
+            VmEvent::Realloc {
+                acct,
+                old_len,
+                new_len,
+            } => {
+                self.modified_accounts.insert(acct);
+                let original = pre.accounts.get(&acct).map(|a| a.data.len()).unwrap_or(old_len);
+                if new_len > original + MAX_PERMITTED_DATA_INCREASE {
+                    signals.rent = true;
+                    signals.flag("RENT", acct);
+                    signals.details.push(format!(
+                        "rent: realloc {} from {} to {} bytes exceeds +{} per instruction",
+                        acct, original, new_len, MAX_PERMITTED_DATA_INCREASE
+                    ));
+                }
+            }
+
+            VmEvent::TokenTransfer {
+                source, destination, ..
+            } => {
+                self.modified_accounts.insert(source);
+                self.modified_accounts.insert(destination);
+            }
+
+            // mint_to hợp lệ khi authority = mint_authority; burn khi authority = owner/delegate
+            // của token account nguồn (như SPL Token); cả hai đều phải ký
+            VmEvent::TokenSupplyOp {
+                mint,
+                account,
+                delta,
+                authority,
+            } => {
+                self.modified_accounts.insert(account);
+                let allowed = if delta >= 0 {
+                    pre.accounts
+                        .get(&mint)
+                        .and_then(|a| Mint::unpack(&a.data))
+                        .and_then(|m| m.mint_authority)
+                        == Some(authority)
+                } else {
+                    pre.accounts
+                        .get(&account)
+                        .and_then(|a| TokenAccount::unpack(&a.data))
+                        .is_some_and(|t| t.owner == authority || t.delegate == Some(authority))
+                };
+                if allowed && tx.signers.contains(&authority) {
+                    *self.authorized_supply_delta.entry(mint).or_insert(0) += delta as i128;
+                }
+            }
+
+            VmEvent::TokenSetAuthority { account, .. } => {
+                self.modified_accounts.insert(account);
+            }
         }
     }
 
@@ -199,6 +588,12 @@ impl Oracles {
                     let post_l = post.accounts.get(acct).map(|a| a.lamports).unwrap_or(pre_l);
                     if post_l < pre_l {
                         signals.msc = true;
+                        signals.flag("MSC", *acct);
+                        signals.details.push(format!(
+                            "msc: {} drained after auth compare on input region bytes {}",
+                            acct,
+                            fmt_offsets(&self.auth_cmp_offsets.iter().copied().collect::<Vec<_>>())
+                        ));
                         break;
                     }
                 }
@@ -253,7 +648,999 @@ impl Oracles {
             let post_att = post.accounts.get(&self.ctx.attacker).map(|a| a.lamports).unwrap_or(0);
             if post_att > self.pre_attacker_lamports.saturating_add(50_000_000) {
                 signals.ib = true;
+                signals.details.push(format!(
+                    "ib: overflowing arithmetic on input region bytes {}",
+                    fmt_offsets(&self.overflow_offsets.iter().copied().collect::<Vec<_>>())
+                ));
+            }
+        }
+
+        // ---------- Type cosplay ----------
+        // (1) cùng program dùng lẫn account có discriminator khác nhau cho cùng một type
+        for ((program, expected), actual) in &self.accepted_types {
+            if actual.len() > 1 {
+                signals.type_cosplay = true;
+                signals.flag("TYPE_COSPLAY", *program);
+                signals.details.push(format!(
+                    "type_cosplay: program {} accepts {:?} interchangeably as {}",
+                    program,
+                    actual
+                        .iter()
+                        .map(|d| self.ctx.layouts.name_of(d))
+                        .collect::<Vec<_>>(),
+                    self.ctx.layouts.name_of(expected)
+                ));
+            }
+        }
+        // (2) discriminator sai được chấp nhận và chính account đó bị ghi sau đó
+        for (acct, expected, actual) in &self.confused_reads {
+            if !self.modified_accounts.contains(acct) {
+                continue;
+            }
+            signals.type_cosplay = true;
+            signals.flag("TYPE_COSPLAY", *acct);
+            signals.details.push(format!(
+                "type_cosplay: account {} of type {} accepted as {}, then modified",
+                acct,
+                self.ctx.layouts.name_of(actual),
+                self.ctx.layouts.name_of(expected)
+            ));
+        }
+
+        // ---------- Duplicate mutable accounts ----------
+        // kết quả phụ thuộc aliasing: transfer from == to (đúng ra không đổi gì) mà balance tăng
+        for (acct, i, j) in &self.aliased_writes {
+            let pre_l = pre.accounts.get(acct).map(|a| a.lamports).unwrap_or(0);
+            let post_l = post.accounts.get(acct).map(|a| a.lamports).unwrap_or(pre_l);
+            if post_l > pre_l {
+                signals.dup_mut = true;
+                signals.flag("DUP_MUT", *acct);
+                signals.details.push(format!(
+                    "dup_mut: writable metas #{} and #{} alias {}; lamports {} -> {}",
+                    i, j, acct, pre_l, post_l
+                ));
+                break;
+            }
+        }
+
+        // ---------- Reinitialization ----------
+        // ghi đè trọn field authority của account đã init (discriminator hợp lệ trong pre)
+        // và account vẫn live sau khi ghi (không phải close)
+        for (acct, offset, nbytes) in &self.data_writes {
+            let (Some(a_pre), Some(a_post)) = (pre.accounts.get(acct), post.accounts.get(acct))
+            else {
+                continue;
+            };
+            let Some(layout) = self.ctx.layouts.classify(&a_pre.data) else {
+                continue;
+            };
+            let Some(auth) = layout.authority_offset else {
+                continue;
+            };
+            let covers = *offset <= auth && offset + nbytes >= auth + 32;
+            let (Some(old), Some(new)) = (
+                a_pre.data.get(auth..auth + 32),
+                a_post.data.get(auth..auth + 32),
+            ) else {
+                continue;
+            };
+            let still_live = self.ctx.layouts.classify(&a_post.data).is_some();
+            if covers && still_live && old != new && old.iter().any(|&x| x != 0) {
+                signals.reinit = true;
+                signals.flag("REINIT", *acct);
+                signals.details.push(format!(
+                    "reinit: live {} account {} authority overwritten {} -> {}",
+                    layout.name,
+                    acct,
+                    Pubkey::try_from(old).unwrap(),
+                    Pubkey::try_from(new).unwrap()
+                ));
+                break;
+            }
+        }
+
+        // ---------- Account revival ----------
+        // program-owned account bị rút hết lamports nhưng data chưa zero hoặc program chưa trả owner
+        // -> revive được trong cùng tx (wallet/system account bị rút thì không phải close)
+        for (k, a_pre) in &pre.accounts {
+            let Some(a_post) = post.accounts.get(k) else {
+                continue;
+            };
+            if a_pre.owner != self.ctx.program_id || a_pre.lamports == 0 || a_post.lamports != 0 {
+                continue;
+            }
+            let dirty_data = a_post.data.iter().any(|&x| x != 0);
+            let same_owner = a_post.owner == a_pre.owner;
+            if dirty_data || same_owner {
+                signals.revival = true;
+                signals.flag("REVIVAL", *k);
+                signals.details.push(format!(
+                    "revival: account {} drained ({} lamports) with data_zeroed={} owner_unchanged={}",
+                    k, a_pre.lamports, !dirty_data, same_owner
+                ));
+            }
+        }
+
+        // ---------- Rent exemption ----------
+        // program-owned account (mới tạo hoặc bị sửa) còn lamports nhưng dưới rent-exempt minimum
+        for (k, a) in &post.accounts {
+            if a.owner != self.ctx.program_id || a.lamports == 0 {
+                continue;
+            }
+            if !self.modified_accounts.contains(k) && !self.created_accounts.contains(k) {
+                continue;
+            }
+            let min = self.ctx.rent.minimum_balance(a.data.len());
+            if a.lamports < min {
+                signals.rent = true;
+                signals.flag("RENT", *k);
+                signals.details.push(format!(
+                    "rent: account {} has {} lamports for {} bytes, rent-exempt minimum is {}",
+                    k,
+                    a.lamports,
+                    a.data.len(),
+                    min
+                ));
+            }
+        }
+
+        self.finalize_tokens(tx, pre, post, signals);
+    }
+
+    fn finalize_tokens(
+        &self,
+        tx: &Transaction,
+        pre: &LedgerSnapshot,
+        post: &LedgerSnapshot,
+        signals: &mut OracleSignals,
+    ) {
+        let pre_tok = token_accounts(pre);
+        let post_tok: BTreeMap<Pubkey, _> = token_accounts(post).into_iter().collect();
+        let amount_delta = |k: &Pubkey, pre_amount: u64| -> i128 {
+            post_tok.get(k).map(|t| t.amount).unwrap_or(pre_amount) as i128 - pre_amount as i128
+        };
+
+        // ---------- Token theft ----------
+        // attacker balance (mint X) tăng trong khi victim (mint X) giảm mà victim không ký
+        let mut attacker_gain: BTreeMap<Pubkey, i128> = BTreeMap::new();
+        for (k, t) in &pre_tok {
+            if t.owner == self.ctx.attacker {
+                *attacker_gain.entry(t.mint).or_insert(0) += amount_delta(k, t.amount);
+            }
+        }
+        for (k, t) in &pre_tok {
+            let gain = attacker_gain.get(&t.mint).copied().unwrap_or(0);
+            if gain > 0
+                && t.owner != self.ctx.attacker
+                && !tx.signers.contains(&t.owner)
+                && amount_delta(k, t.amount) < 0
+            {
+                signals.token_theft = true;
+                signals.flag("TOKEN_THEFT", *k);
+                signals.details.push(format!(
+                    "token_theft: mint={} victim_account={} (owner {}, not signer) lost {}, attacker gained {}",
+                    t.mint,
+                    k,
+                    t.owner,
+                    -amount_delta(k, t.amount),
+                    gain
+                ));
+            }
+        }
+
+        // ---------- Supply conservation ----------
+        // supply và tổng balance của mint chỉ được đổi đúng bằng net mint_to/burn hợp lệ
+        let post_mints: BTreeMap<Pubkey, _> = mints(post).into_iter().collect();
+        for (mint, m) in mints(pre) {
+            let post_supply = post_mints.get(&mint).map(|x| x.supply).unwrap_or(m.supply);
+            let supply_delta = post_supply as i128 - m.supply as i128;
+            let balance_delta: i128 = pre_tok
+                .iter()
+                .filter(|(_, t)| t.mint == mint)
+                .map(|(k, t)| amount_delta(k, t.amount))
+                .sum();
+            let authorized = self
+                .authorized_supply_delta
+                .get(&mint)
+                .copied()
+                .unwrap_or(0);
+            if supply_delta != authorized || balance_delta != authorized {
+                signals.supply_violation = true;
+                signals.flag("SUPPLY_VIOLATION", mint);
+                signals.details.push(format!(
+                    "supply_violation: mint={} supply_delta={} balance_delta={} authorized_delta={}",
+                    mint, supply_delta, balance_delta, authorized
+                ));
+            }
+        }
+
+        // ---------- Delegate / close authority hijack ----------
+        for (k, t) in &pre_tok {
+            let Some(after) = post_tok.get(k) else {
+                continue;
+            };
+            if t.owner == self.ctx.attacker || tx.signers.contains(&t.owner) {
+                continue;
+            }
+            for (field, before, now) in [
+                ("delegate", t.delegate, after.delegate),
+                ("close_authority", t.close_authority, after.close_authority),
+            ] {
+                if now != before && now == Some(self.ctx.attacker) {
+                    signals.token_authority_hijack = true;
+                    signals.flag("TOKEN_AUTHORITY_HIJACK", *k);
+                    signals.details.push(format!(
+                        "token_authority_hijack: account={} (owner {}) {} -> attacker {}",
+                        k, t.owner, field, self.ctx.attacker
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute;
+    use crate::token::TOKEN_PROGRAM_ID;
+    use crate::types::{Account, InstrAccountMeta, Instruction};
+    use crate::vm_rbpf::{TraceVm, VmConfig, OPS_VERSION};
+    use solana_sdk::system_program;
+
+    struct Fixture {
+        ctx: OracleContext,
+        pre: LedgerSnapshot,
+    }
+
+    fn account(owner: Pubkey, lamports: u64, data: Vec<u8>) -> Account {
+        Account {
+            owner,
+            lamports,
+            data,
+            is_executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    /// program + attacker/user (wallet 1 SOL)
+    fn fixture() -> Fixture {
+        let ctx = OracleContext {
+            program_id: Pubkey::new_unique(),
+            attacker: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            layouts: LayoutModel::default(),
+            rent: Rent::default(),
+        };
+        let mut pre = LedgerSnapshot {
+            program_id: ctx.program_id,
+            accounts: BTreeMap::new(),
+        };
+        for k in [ctx.attacker, ctx.user] {
+            pre.accounts
+                .insert(k, account(system_program::id(), 1_000_000_000, vec![]));
+        }
+        Fixture { ctx, pre }
+    }
+
+    impl Fixture {
+        fn add(&mut self, owner: Pubkey, lamports: u64, data: Vec<u8>) -> Pubkey {
+            let k = Pubkey::new_unique();
+            self.pre.accounts.insert(k, account(owner, lamports, data));
+            k
+        }
+
+        /// tx của program: metas (key, signer, writable)
+        fn tx(&self, metas: &[(Pubkey, bool, bool)]) -> Transaction {
+            let accounts: Vec<InstrAccountMeta> = metas
+                .iter()
+                .map(|&(pubkey, is_signer, is_writable)| InstrAccountMeta {
+                    pubkey,
+                    is_signer,
+                    is_writable,
+                })
+                .collect();
+            let mut all_accounts_sorted: Vec<Pubkey> = accounts.iter().map(|m| m.pubkey).collect();
+            all_accounts_sorted.sort();
+            all_accounts_sorted.dedup();
+            Transaction {
+                signers: accounts
+                    .iter()
+                    .filter(|m| m.is_signer)
+                    .map(|m| m.pubkey)
+                    .collect(),
+                all_accounts_sorted,
+                instruction: Instruction {
+                    program_id: self.ctx.program_id,
+                    accounts,
+                    data: vec![],
+                },
+                siblings: vec![],
+                instruction_index: 0,
+                clock_override: None,
+            }
+        }
+
+        /// Chạy oracle trên event của frame top-level rồi finalize với `post`
+        fn run(
+            &self,
+            tx: &Transaction,
+            events: Vec<VmEvent>,
+            post: &LedgerSnapshot,
+        ) -> OracleSignals {
+            let mut signals = OracleSignals::default();
+            let mut oracles = Oracles::new(self.ctx.clone(), &self.pre);
+            let taint = TaintEngine::default();
+            for event in events {
+                let ev = FrameEvent {
+                    program_id: self.ctx.program_id,
+                    depth: 1,
+                    pc: 0,
+                    event,
+                };
+                oracles.process_event(tx, &taint, &self.pre, post, ev, &mut signals);
             }
+            oracles.finalize(tx, &self.pre, post, &mut signals);
+            signals
+        }
+    }
+
+    impl Fixture {
+        fn vm_config(&self) -> VmConfig {
+            VmConfig {
+                compute_budget: compute::DEFAULT_COMPUTE_UNIT_LIMIT,
+                sighashes: vec![],
+                layouts: self.ctx.layouts.clone(),
+                rent: self.ctx.rent,
+                ops_version: OPS_VERSION,
+            }
+        }
+
+        /// Chạy TraceVM trên `data` rồi oracle như FuzzTarget::execute_tx
+        fn run_vm(&self, mut tx: Transaction, data: &[u8]) -> OracleSignals {
+            tx.instruction.data = data.to_vec();
+            let out = TraceVm::run(
+                self.ctx.program_id,
+                self.pre.clone(),
+                &tx,
+                &self.vm_config(),
+            );
+            let mut signals = OracleSignals::default();
+            let mut oracles = Oracles::new(self.ctx.clone(), &self.pre);
+            for ev in out.events {
+                oracles.process_event(
+                    &tx,
+                    &out.taint,
+                    &self.pre,
+                    &out.post_snapshot,
+                    ev,
+                    &mut signals,
+                );
+            }
+            oracles.finalize(&tx, &self.pre, &out.post_snapshot, &mut signals);
+            signals
+        }
+    }
+
+    /// program executable khác trong ledger
+    fn add_program(f: &mut Fixture) -> Pubkey {
+        let k = f.add(solana_sdk::bpf_loader_upgradeable::id(), 1, vec![]);
+        f.pre.accounts.get_mut(&k).unwrap().is_executable = true;
+        k
+    }
+
+    #[test]
+    fn acpi_hard_coded_target_is_not_attacker_chosen() {
+        let mut f = fixture();
+        let helper = add_program(&mut f);
+        // helper cũng có mặt trong metas (vd program account truyền cho CPI)
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (helper, false, false)]);
+        // 0x89: CPI tới program hard-code (bit3), không phải meta do input chọn
+        let s = f.run_vm(tx, &[0x89, 0x00]);
+        assert!(!s.acpi, "{:?}", s.details);
+    }
+
+    #[test]
+    fn acpi_attacker_chosen_target_fires() {
+        let mut f = fixture();
+        let evil = add_program(&mut f);
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (evil, false, false)]);
+        // 0x91: program id lấy từ meta #(arg % 2) = #1
+        let s = f.run_vm(tx.clone(), &[0x91, 0x01]);
+        assert!(s.acpi, "{:?}", s.details);
+        assert_eq!(s.flagged["ACPI"], BTreeSet::from([evil]));
+        assert!(
+            s.details.iter().any(|d| d.contains("account index 1")),
+            "{:?}",
+            s.details
+        );
+
+        // target do input chọn nhưng là chính program -> self-CPI
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (f.ctx.program_id, false, false),
+        ]);
+        let s = f.run_vm(tx, &[0x91, 0x01]);
+        assert!(!s.acpi, "{:?}", s.details);
+    }
+
+    fn typed(f: &Fixture, name: &str) -> Vec<u8> {
+        let l = f
+            .ctx
+            .layouts
+            .layouts
+            .iter()
+            .find(|l| l.name == name)
+            .unwrap();
+        let mut data = vec![0u8; l.size.unwrap()];
+        data[..8].copy_from_slice(&l.discriminator);
+        data
+    }
+
+    fn read_as(acct: Pubkey, expected: &[u8], actual: &[u8]) -> VmEvent {
+        VmEvent::ReadAccountAs {
+            acct,
+            expected: expected[..8].try_into().unwrap(),
+            actual: actual[..8].try_into().unwrap(),
+            checked: false,
+        }
+    }
+
+    #[test]
+    fn type_cosplay_interchangeable_known_types() {
+        let mut f = fixture();
+        let (vault, user) = (typed(&f, "Vault"), typed(&f, "User"));
+        let program_id = f.ctx.program_id;
+        let a = f.add(program_id, 1_000_000_000, vault.clone());
+        let b = f.add(program_id, 1_000_000_000, user.clone());
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (a, false, false),
+            (b, false, false),
+        ]);
+        let post = f.pre.clone();
+
+        // (1) Vault và User cùng được đọc như Vault
+        let s = f.run(
+            &tx,
+            vec![read_as(a, &vault, &vault), read_as(b, &vault, &user)],
+            &post,
+        );
+        assert!(s.type_cosplay, "{:?}", s.details);
+        assert_eq!(s.flagged["TYPE_COSPLAY"], BTreeSet::from([program_id]));
+
+        // discriminator rác (data bị ghi hỏng) không phải một type
+        let junk = [0x5a; 8];
+        let s = f.run(
+            &tx,
+            vec![read_as(a, &vault, &vault), read_as(b, &vault, &junk)],
+            &post,
+        );
+        assert!(!s.type_cosplay, "{:?}", s.details);
+
+        // có check discriminator
+        let mut ev = read_as(b, &vault, &user);
+        if let VmEvent::ReadAccountAs { checked, .. } = &mut ev {
+            *checked = true;
         }
+        let s = f.run(&tx, vec![read_as(a, &vault, &vault), ev], &post);
+        assert!(!s.type_cosplay, "{:?}", s.details);
+    }
+
+    #[test]
+    fn type_cosplay_wrong_type_then_modified() {
+        let mut f = fixture();
+        let (vault, user) = (typed(&f, "Vault"), typed(&f, "User"));
+        let program_id = f.ctx.program_id;
+        let b = f.add(program_id, 1_000_000_000, user.clone());
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (b, false, true)]);
+        let post = f.pre.clone();
+        let write = VmEvent::WriteData {
+            acct: b,
+            offset: 40,
+            nbytes: 8,
+        };
+
+        let s = f.run(&tx, vec![read_as(b, &vault, &user), write.clone()], &post);
+        assert!(s.type_cosplay, "{:?}", s.details);
+        assert_eq!(s.flagged["TYPE_COSPLAY"], BTreeSet::from([b]));
+
+        // chỉ đọc, không ghi
+        let s = f.run(&tx, vec![read_as(b, &vault, &user)], &post);
+        assert!(!s.type_cosplay, "{:?}", s.details);
+        // type khớp
+        let s = f.run(&tx, vec![read_as(b, &user, &user), write], &post);
+        assert!(!s.type_cosplay, "{:?}", s.details);
+    }
+
+    #[test]
+    fn typed_read_skips_corrupted_discriminators() {
+        let mut f = fixture();
+        let program_id = f.ctx.program_id;
+        let vault = typed(&f, "Vault");
+        let a = f.add(program_id, 1_000_000_000, vault.clone());
+        let mut junk = vault.clone();
+        junk[..8].copy_from_slice(&[0x5a; 8]);
+        let b = f.add(program_id, 1_000_000_000, junk);
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (a, false, false),
+            (b, false, false),
+        ]);
+        // 0xe9: đọc typed không check, mọi lựa chọn account/type
+        let data: Vec<u8> = (0..=255u8).flat_map(|arg| [0xe9, arg]).collect();
+        let s = f.run_vm(tx, &data);
+        assert!(!s.type_cosplay, "{:?}", s.details);
+    }
+
+    #[test]
+    fn dup_mut_fires_when_aliased_transfer_mints_lamports() {
+        let mut f = fixture();
+        let program_id = f.ctx.program_id;
+        let x = f.add(program_id, 1_000_000_000, vec![0; 48]);
+        // x ở 2 meta writable
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (x, false, true),
+            (x, false, true),
+        ]);
+        // 0xea: transfer meta #(arg % 3) -> #((arg >> 4) % 3) = #1 -> #2
+        let s = f.run_vm(tx, &[0xea, 0x22]);
+        assert!(s.dup_mut, "{:?}", s.details);
+        assert_eq!(s.flagged["DUP_MUT"], BTreeSet::from([x]));
+    }
+
+    #[test]
+    fn dup_mut_ignores_aliased_transfer_without_gain() {
+        let mut f = fixture();
+        let program_id = f.ctx.program_id;
+        let x = f.add(program_id, 1_000_000_000, vec![0; 48]);
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (x, false, true),
+            (x, false, true),
+        ]);
+        let aliased = VmEvent::MetaTransfer {
+            from_index: 1,
+            to_index: 2,
+            from: x,
+            to: x,
+            amount: 10_000,
+        };
+        // balance không đổi, hoặc giảm vì op khác (không phụ thuộc aliasing)
+        for lamports in [1_000_000_000, 999_990_000] {
+            let mut post = f.pre.clone();
+            post.accounts.get_mut(&x).unwrap().lamports = lamports;
+            let s = f.run(&tx, vec![aliased.clone()], &post);
+            assert!(!s.dup_mut, "{:?}", s.details);
+        }
+        // balance tăng nhưng không có transfer alias
+        let mut post = f.pre.clone();
+        post.accounts.get_mut(&x).unwrap().lamports += 10_000;
+        let s = f.run(&tx, vec![], &post);
+        assert!(!s.dup_mut, "{:?}", s.details);
+    }
+
+    /// Vault của program với authority = user
+    fn user_vault(f: &mut Fixture) -> Pubkey {
+        let mut data = typed(f, "Vault");
+        data[8..40].copy_from_slice(f.ctx.user.as_ref());
+        let program_id = f.ctx.program_id;
+        f.add(program_id, 1_000_000_000, data)
+    }
+
+    #[test]
+    fn reinit_overwrites_live_authority() {
+        let mut f = fixture();
+        let vault = user_vault(&mut f);
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (vault, false, true)]);
+        // 0xeb: initialize, arg bit0 = 0 -> không check đã init
+        let s = f.run_vm(tx.clone(), &[0xeb, 0x00]);
+        assert!(s.reinit, "{:?}", s.details);
+        assert_eq!(s.flagged["REINIT"], BTreeSet::from([vault]));
+
+        // có check đã init
+        let s = f.run_vm(tx, &[0xeb, 0x01]);
+        assert!(!s.reinit, "{:?}", s.details);
+    }
+
+    #[test]
+    fn initialize_fresh_account_is_not_reinit() {
+        let mut f = fixture();
+        let program_id = f.ctx.program_id;
+        let fresh = f.add(program_id, 1_000_000_000, vec![0; 48]);
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (fresh, false, true)]);
+        let s = f.run_vm(tx.clone(), &[0xeb, 0x00]);
+        assert!(!s.reinit, "{:?}", s.details);
+
+        // ghi discriminator + authority
+        let mut tx = tx;
+        tx.instruction.data = vec![0xeb, 0x00];
+        let out = TraceVm::run(program_id, f.pre.clone(), &tx, &f.vm_config());
+        let data = &out.post_snapshot.accounts[&fresh].data;
+        assert_eq!(
+            f.ctx.layouts.classify(data).map(|l| l.name.as_str()),
+            Some("Vault")
+        );
+        assert_eq!(&data[8..40], f.ctx.attacker.as_ref());
+    }
+
+    #[test]
+    fn revival_when_close_leaves_data_or_owner() {
+        let mut f = fixture();
+        let vault = user_vault(&mut f);
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (vault, false, true)]);
+        // 0xec: close, arg bit0 = 0 -> chỉ rút lamports
+        let s = f.run_vm(tx.clone(), &[0xec, 0x00]);
+        assert!(s.revival, "{:?}", s.details);
+        assert_eq!(s.flagged["REVIVAL"], BTreeSet::from([vault]));
+
+        // close đúng: zero data + trả owner
+        let s = f.run_vm(tx, &[0xec, 0x01]);
+        assert!(!s.revival, "{:?}", s.details);
+
+        // data đã zero nhưng owner vẫn là program
+        let mut post = f.pre.clone();
+        let a = post.accounts.get_mut(&vault).unwrap();
+        a.lamports = 0;
+        a.data.fill(0);
+        let s = f.run(
+            &f.tx(&[(f.ctx.attacker, true, true), (vault, false, true)]),
+            vec![],
+            &post,
+        );
+        assert!(s.revival);
+        assert!(
+            s.details[0].contains("data_zeroed=true owner_unchanged=true"),
+            "{:?}",
+            s.details
+        );
+
+        // rút lamports của wallet không phải close
+        let mut post = f.pre.clone();
+        post.accounts.get_mut(&f.ctx.user).unwrap().lamports = 0;
+        let s = f.run(&f.tx(&[(f.ctx.attacker, true, true)]), vec![], &post);
+        assert!(!s.revival, "{:?}", s.details);
+    }
+
+    #[test]
+    fn rent_paid_by_attacker_chosen_non_signer() {
+        let f = fixture();
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (f.ctx.user, false, true)]);
+        // 0xed: create account; arg 0x30 -> payer meta #1 (user), nạp đủ rent
+        let s = f.run_vm(tx.clone(), &[0xed, 0x30]);
+        assert!(s.rent, "{:?}", s.details);
+        assert_eq!(s.flagged["RENT"], BTreeSet::from([f.ctx.user]));
+        assert!(s.details[0].contains("account index 1"), "{:?}", s.details);
+
+        // payer là signer (attacker tự trả)
+        let s = f.run_vm(tx, &[0xed, 0x10]);
+        assert!(!s.rent, "{:?}", s.details);
+    }
+
+    #[test]
+    fn rent_below_exempt_minimum_and_deterministic_key() {
+        let f = fixture();
+        let tx = f.tx(&[(f.ctx.attacker, true, true)]);
+        let run = |data: &[u8]| {
+            let mut tx = tx.clone();
+            tx.instruction.data = data.to_vec();
+            TraceVm::run(f.ctx.program_id, f.pre.clone(), &tx, &f.vm_config())
+        };
+        // arg 0x00: chỉ nạp nửa rent-exempt minimum
+        let created = |snap: &LedgerSnapshot| -> Vec<Pubkey> {
+            snap.accounts
+                .keys()
+                .filter(|k| !f.pre.accounts.contains_key(k))
+                .copied()
+                .collect()
+        };
+        let a = created(&run(&[0xed, 0x00]).post_snapshot);
+        let b = created(&run(&[0xed, 0x00]).post_snapshot);
+        // replay cùng input ra cùng key
+        assert_eq!(a.len(), 1);
+        assert_eq!(a, b);
+
+        let s = f.run_vm(tx.clone(), &[0xed, 0x00]);
+        assert!(s.rent, "{:?}", s.details);
+        assert_eq!(s.flagged["RENT"], BTreeSet::from([a[0]]));
+        let s = f.run_vm(tx, &[0xed, 0x10]);
+        assert!(!s.rent, "{:?}", s.details);
+    }
+
+    #[test]
+    fn rent_realloc_beyond_permitted_increase() {
+        let mut f = fixture();
+        let program_id = f.ctx.program_id;
+        let x = f.add(program_id, 1_000_000_000, vec![0; 48]);
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (x, false, true)]);
+        // 0xee: realloc +arg * 64; 0xa0 * 64 = đúng MAX_PERMITTED_DATA_INCREASE
+        assert_eq!(0xa0 * 64, MAX_PERMITTED_DATA_INCREASE);
+        let s = f.run_vm(tx.clone(), &[0xee, 0xa0]);
+        assert!(!s.rent, "{:?}", s.details);
+        let s = f.run_vm(tx.clone(), &[0xee, 0xa1]);
+        assert!(s.rent, "{:?}", s.details);
+        assert_eq!(s.flagged["RENT"], BTreeSet::from([x]));
+        // cộng dồn qua nhiều lần realloc trong cùng instruction
+        let s = f.run_vm(tx, &[0xee, 0x60, 0xee, 0x60]);
+        assert!(s.rent, "{:?}", s.details);
+    }
+
+    fn set_token(snap: &mut LedgerSnapshot, k: &Pubkey, f: impl FnOnce(&mut TokenAccount)) {
+        let a = snap.accounts.get_mut(k).unwrap();
+        let mut t = TokenAccount::unpack(&a.data).unwrap();
+        f(&mut t);
+        a.data = t.pack();
+    }
+
+    fn set_supply(snap: &mut LedgerSnapshot, mint: &Pubkey, supply: u64) {
+        let a = snap.accounts.get_mut(mint).unwrap();
+        let mut m = Mint::unpack(&a.data).unwrap();
+        m.supply = supply;
+        a.data = m.pack();
+    }
+
+    /// mint (supply 1000) + token account của user (1000) và attacker (0)
+    struct Tokens {
+        mint: Pubkey,
+        mint_authority: Pubkey,
+        victim: Pubkey,
+        loot: Pubkey,
+    }
+
+    fn tokens(f: &mut Fixture) -> Tokens {
+        let mint_authority = Pubkey::new_unique();
+        let mint = f.add(
+            TOKEN_PROGRAM_ID,
+            1,
+            Mint {
+                mint_authority: Some(mint_authority),
+                supply: 1_000,
+                decimals: 0,
+                is_initialized: true,
+                freeze_authority: None,
+            }
+            .pack(),
+        );
+        let token = |owner: Pubkey, amount: u64| TokenAccount {
+            mint,
+            owner,
+            amount,
+            delegate: None,
+            state: 1,
+            is_native: None,
+            delegated_amount: 0,
+            close_authority: None,
+        };
+        let victim = f.add(TOKEN_PROGRAM_ID, 1, token(f.ctx.user, 1_000).pack());
+        let loot = f.add(TOKEN_PROGRAM_ID, 1, token(f.ctx.attacker, 0).pack());
+        Tokens {
+            mint,
+            mint_authority,
+            victim,
+            loot,
+        }
+    }
+
+    fn transfer(post: &mut LedgerSnapshot, t: &Tokens, amount: u64) -> VmEvent {
+        set_token(post, &t.victim, |x| x.amount -= amount);
+        set_token(post, &t.loot, |x| x.amount += amount);
+        VmEvent::TokenTransfer {
+            source: t.victim,
+            destination: t.loot,
+            amount,
+        }
+    }
+
+    #[test]
+    fn token_theft_needs_victim_not_signing() {
+        let mut f = fixture();
+        let t = tokens(&mut f);
+        let mut post = f.pre.clone();
+        let ev = transfer(&mut post, &t, 400);
+
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (t.victim, false, true),
+            (t.loot, false, true),
+        ]);
+        let s = f.run(&tx, vec![ev.clone()], &post);
+        assert!(s.token_theft, "{:?}", s.details);
+        assert_eq!(s.flagged["TOKEN_THEFT"], BTreeSet::from([t.victim]));
+        // transfer không đổi supply
+        assert!(!s.supply_violation, "{:?}", s.details);
+
+        // user ký: transfer hợp lệ
+        let tx = f.tx(&[
+            (f.ctx.user, true, true),
+            (t.victim, false, true),
+            (t.loot, false, true),
+        ]);
+        let s = f.run(&tx, vec![ev], &post);
+        assert!(!s.token_theft && !s.any(), "{:?}", s.details);
+    }
+
+    #[test]
+    fn supply_change_needs_authorized_mint_or_burn() {
+        let mut f = fixture();
+        let t = tokens(&mut f);
+        let mut post = f.pre.clone();
+        set_token(&mut post, &t.loot, |x| x.amount += 500);
+        set_supply(&mut post, &t.mint, 1_500);
+        let mint_to = |authority| VmEvent::TokenSupplyOp {
+            mint: t.mint,
+            account: t.loot,
+            delta: 500,
+            authority,
+        };
+
+        // mint_to do mint authority ký
+        let tx = f.tx(&[
+            (t.mint_authority, true, false),
+            (t.mint, false, true),
+            (t.loot, false, true),
+        ]);
+        let s = f.run(&tx, vec![mint_to(t.mint_authority)], &post);
+        assert!(!s.supply_violation, "{:?}", s.details);
+
+        // attacker tự mint
+        let tx = f.tx(&[
+            (f.ctx.attacker, true, true),
+            (t.mint, false, true),
+            (t.loot, false, true),
+        ]);
+        let s = f.run(&tx, vec![mint_to(f.ctx.attacker)], &post);
+        assert!(s.supply_violation);
+        assert_eq!(s.flagged["SUPPLY_VIOLATION"], BTreeSet::from([t.mint]));
+
+        // balance tăng mà supply không đổi (không có mint_to)
+        let mut post = f.pre.clone();
+        set_token(&mut post, &t.loot, |x| x.amount += 500);
+        let s = f.run(&tx, vec![], &post);
+        assert!(s.supply_violation);
+        assert!(
+            s.details[0].contains("supply_delta=0 balance_delta=500"),
+            "{:?}",
+            s.details
+        );
+    }
+
+    #[test]
+    fn authority_hijack_needs_owner_not_signing() {
+        let mut f = fixture();
+        let t = tokens(&mut f);
+        let mut post = f.pre.clone();
+        set_token(&mut post, &t.victim, |x| x.delegate = Some(f.ctx.attacker));
+        let ev = VmEvent::TokenSetAuthority {
+            account: t.victim,
+            close_authority: false,
+            new_authority: f.ctx.attacker,
+        };
+
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (t.victim, false, true)]);
+        let s = f.run(&tx, vec![ev.clone()], &post);
+        assert!(s.token_authority_hijack);
+        assert_eq!(
+            s.flagged["TOKEN_AUTHORITY_HIJACK"],
+            BTreeSet::from([t.victim])
+        );
+
+        // owner ký approve
+        let tx = f.tx(&[(f.ctx.user, true, true), (t.victim, false, true)]);
+        let s = f.run(&tx, vec![ev], &post);
+        assert!(!s.token_authority_hijack);
+
+        // delegate sang key khác attacker
+        let mut post = f.pre.clone();
+        set_token(&mut post, &t.victim, |x| {
+            x.close_authority = Some(Pubkey::new_unique())
+        });
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (t.victim, false, true)]);
+        let s = f.run(&tx, vec![], &post);
+        assert!(!s.token_authority_hijack);
+    }
+
+    /// Đặt sibling instruction vào tx (instruction chính ở `index`) và dựng Instructions sysvar
+    fn with_siblings(
+        f: &mut Fixture,
+        mut tx: Transaction,
+        siblings: Vec<Instruction>,
+        index: usize,
+    ) -> Transaction {
+        use solana_sdk::sysvar::instructions::{self, BorrowedAccountMeta, BorrowedInstruction};
+
+        tx.instruction.accounts.push(InstrAccountMeta {
+            pubkey: instructions::id(),
+            is_signer: false,
+            is_writable: false,
+        });
+        tx.all_accounts_sorted.push(instructions::id());
+        tx.all_accounts_sorted.sort();
+        tx.siblings = siblings;
+        tx.instruction_index = index;
+
+        let ixs = tx.instructions();
+        let borrowed: Vec<BorrowedInstruction> = ixs
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|m| BorrowedAccountMeta {
+                        pubkey: &m.pubkey,
+                        is_signer: m.is_signer,
+                        is_writable: m.is_writable,
+                    })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = instructions::construct_instructions_data(&borrowed);
+        instructions::store_current_index(&mut data, index as u16);
+        f.pre.accounts.insert(
+            instructions::id(),
+            account(solana_sdk::sysvar::id(), 1_000_000, data),
+        );
+        tx
+    }
+
+    fn verify(signer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: solana_sdk::ed25519_program::id(),
+            accounts: vec![],
+            data: crate::txgen::ed25519_verify_data(signer, b"fuzzdelsol"),
+        }
+    }
+
+    #[test]
+    fn introspection_accepts_attacker_crafted_sibling() {
+        let mut f = fixture();
+        let vault = user_vault(&mut f);
+        let attacker = f.ctx.attacker;
+        let base = f.tx(&[(attacker, true, true), (vault, false, true)]);
+
+        // attacker tự ký ed25519 verify bằng key của mình, đứng trước instruction chính;
+        // 0xf1: introspect, arg 0x03 = sibling liền trước + check program id, không bind signer
+        let tx = with_siblings(&mut f, base.clone(), vec![verify(&attacker)], 1);
+        let s = f.run_vm(tx.clone(), &[0xf1, 0x03]);
+        assert!(s.introspection, "{:?}", s.details);
+        assert_eq!(
+            s.flagged["INTROSPECTION"],
+            BTreeSet::from([solana_sdk::ed25519_program::id()])
+        );
+        assert!(s.details[0].contains("without binding"), "{:?}", s.details);
+
+        // check đủ: chữ ký attacker không khớp authority -> không pass, không flag
+        let s = f.run_vm(tx, &[0xf1, 0x0f]);
+        assert!(!s.introspection, "{:?}", s.details);
+
+        // sibling từ program bất kỳ, program không check program id
+        let mut fake = verify(&attacker);
+        fake.program_id = Pubkey::new_unique();
+        let fake_id = fake.program_id;
+        let tx = with_siblings(&mut f, base, vec![fake], 1);
+        let s = f.run_vm(tx, &[0xf1, 0x01]);
+        assert!(s.introspection, "{:?}", s.details);
+        assert_eq!(s.flagged["INTROSPECTION"], BTreeSet::from([fake_id]));
+    }
+
+    #[test]
+    fn introspection_real_verify_before_instruction_is_clean() {
+        let mut f = fixture();
+        let vault = user_vault(&mut f);
+        let user = f.ctx.user;
+        let tx = f.tx(&[(f.ctx.attacker, true, true), (vault, false, true)]);
+        // ed25519 verify thật của authority (user) đứng ngay trước, program check đủ
+        let mut tx = with_siblings(&mut f, tx, vec![verify(&user)], 1);
+        tx.instruction.data = vec![0xf1, 0x0f];
+        let out = TraceVm::run(f.ctx.program_id, f.pre.clone(), &tx, &f.vm_config());
+        assert!(out
+            .events
+            .iter()
+            .any(|e| matches!(e.event, VmEvent::Introspect { passed: true, .. })));
+
+        let s = f.run_vm(tx, &[0xf1, 0x0f]);
+        assert!(!s.introspection, "{:?}", s.details);
     }
 }
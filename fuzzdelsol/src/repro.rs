@@ -0,0 +1,378 @@
+use crate::export::labels;
+use crate::txfile::Roles;
+use crate::types::{hex, Account, ExecResult};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Tên hàm test của harness (`fuzzdelsol_<bucket id>`; id có thể bắt đầu bằng chữ số)
+pub fn harness_name(finding: &str) -> String {
+    format!("fuzzdelsol_{finding}")
+}
+
+/// Account đưa vào ProgramTest: account tx tham chiếu, trừ program (add_program) và sysvar thật
+fn fixture_accounts(exec: &ExecResult) -> Vec<(Pubkey, &Account)> {
+    let referenced: BTreeSet<Pubkey> = exec
+        .tx
+        .instructions()
+        .iter()
+        .flat_map(|ix| ix.accounts.iter().map(|m| m.pubkey))
+        .collect();
+    referenced
+        .into_iter()
+        .filter_map(|k| exec.pre_snapshot.accounts.get(&k).map(|a| (k, a)))
+        .filter(|(_, a)| !a.is_executable && a.owner != sysvar::id())
+        .collect()
+}
+
+/// Repro harness `#[tokio::test]` (solana-program-test) cho một finding: pre-state bằng `add_account`,
+/// load ELF `program_name`.so, gửi exploit tx, in delta post-state quan sát được trên program thật
+/// rồi assert post-state mà TraceVM dự đoán (lamports/owner/data từng account đổi, hoặc tx lỗi
+/// khi vượt compute budget). Signer được thay bằng `Keypair` mới; pubkey placeholder trong data
+/// account / ix data được thay theo (authority lưu trong vault vẫn khớp với người ký)
+pub fn repro_harness(
+    finding: &str,
+    exec: &ExecResult,
+    roles: Roles,
+    program_name: &str,
+    compute_budget: u64,
+) -> String {
+    let labels = labels(exec, roles);
+    let label_of: BTreeMap<Pubkey, &str> = labels.iter().map(|(l, k)| (*k, l.as_str())).collect();
+    let signers: Vec<(Pubkey, String)> = exec
+        .tx
+        .signers
+        .iter()
+        .map(|k| {
+            (
+                *k,
+                format!("kp_{}", label_of.get(k).copied().unwrap_or("signer")),
+            )
+        })
+        .collect();
+    let key_expr = |k: &Pubkey| -> String {
+        match signers.iter().find(|(s, _)| s == k) {
+            Some((_, var)) => format!("{var}.pubkey()"),
+            None if *k == roles.program_id => "program_id".to_string(),
+            None => format!("pk(\"{k}\")"),
+        }
+    };
+
+    let mut s = String::new();
+    let w = &mut s;
+    let _ = writeln!(
+        w,
+        "// Generated by fuzzdelsol: {} finding {finding}",
+        exec.signals.class()
+    );
+    for d in &exec.signals.details {
+        let _ = writeln!(w, "// {d}");
+    }
+    let _ = writeln!(w, "//");
+    let _ = writeln!(w, "// Dựng pre-state của finding, gửi exploit tx tới program thật, in delta post-state quan sát được");
+    let _ = writeln!(w, "// rồi assert post-state mà TraceVM (mô hình diễn giải instruction data, không chạy program) dự đoán.");
+    let _ = writeln!(
+        w,
+        "// Assert fail = program thật khác mô hình: so với delta đã in."
+    );
+    let _ = writeln!(
+        w,
+        "// Chạy: cargo test {} -- --nocapture",
+        harness_name(finding)
+    );
+    let _ = writeln!(w, "//");
+    let _ = writeln!(
+        w,
+        "// Cần dev-dependencies: solana-program-test, solana-sdk, tokio (macros, rt)."
+    );
+    let _ = writeln!(
+        w,
+        "// ProgramTest tìm {program_name}.so trong BPF_OUT_DIR / tests/fixtures / target/deploy."
+    );
+    let _ = writeln!(w, "use solana_program_test::ProgramTest;");
+    let _ = writeln!(w, "use solana_sdk::account::Account;");
+    let _ = writeln!(w, "use solana_sdk::clock::Clock;");
+    let _ = writeln!(
+        w,
+        "use solana_sdk::instruction::{{AccountMeta, Instruction}};"
+    );
+    let _ = writeln!(w, "use solana_sdk::pubkey::Pubkey;");
+    let _ = writeln!(w, "use solana_sdk::signature::{{Keypair, Signer}};");
+    let _ = writeln!(w, "use solana_sdk::transaction::Transaction;");
+    let _ = writeln!(w, "use std::str::FromStr;");
+    let _ = writeln!(w);
+    let _ = writeln!(
+        w,
+        "fn pk(s: &str) -> Pubkey {{\n    Pubkey::from_str(s).unwrap()\n}}"
+    );
+    let _ = writeln!(w);
+    let _ = writeln!(w, "fn bytes(h: &str) -> Vec<u8> {{");
+    let _ = writeln!(w, "    (0..h.len()).step_by(2).map(|i| u8::from_str_radix(&h[i..i + 2], 16).unwrap()).collect()");
+    let _ = writeln!(w, "}}");
+    let _ = writeln!(w);
+    let _ = writeln!(
+        w,
+        "/// Thay pubkey placeholder của fuzzer (32 byte) trong data bằng key của test"
+    );
+    let _ = writeln!(
+        w,
+        "fn remap(mut data: Vec<u8>, keys: &[(Pubkey, Pubkey)]) -> Vec<u8> {{"
+    );
+    let _ = writeln!(w, "    for (from, to) in keys {{");
+    let _ = writeln!(w, "        let mut i = 0;");
+    let _ = writeln!(w, "        while i + 32 <= data.len() {{");
+    let _ = writeln!(w, "            if data[i..i + 32] == from.to_bytes() {{");
+    let _ = writeln!(
+        w,
+        "                data[i..i + 32].copy_from_slice(to.as_ref());"
+    );
+    let _ = writeln!(w, "                i += 32;");
+    let _ = writeln!(w, "            }} else {{");
+    let _ = writeln!(w, "                i += 1;");
+    let _ = writeln!(w, "            }}");
+    let _ = writeln!(w, "        }}");
+    let _ = writeln!(w, "    }}");
+    let _ = writeln!(w, "    data");
+    let _ = writeln!(w, "}}");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "#[tokio::test]");
+    let _ = writeln!(w, "async fn {}() {{", harness_name(finding));
+    let _ = writeln!(w, "    let program_id = pk(\"{}\");", roles.program_id);
+    for (k, var) in &signers {
+        let _ = writeln!(w, "    let {var} = Keypair::new(); // placeholder {k}");
+    }
+    let _ = writeln!(w, "    let keys = [");
+    for (k, var) in &signers {
+        let _ = writeln!(w, "        (pk(\"{k}\"), {var}.pubkey()),");
+    }
+    let _ = writeln!(w, "    ];");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    let mut pt = ProgramTest::default();");
+    let _ = writeln!(w, "    pt.prefer_bpf(true);");
+    let _ = writeln!(
+        w,
+        "    pt.add_program(\"{program_name}\", program_id, None);"
+    );
+    let _ = writeln!(w, "    pt.set_compute_max_units({compute_budget});");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    // pre-state (LedgerSnapshot của TraceVM)");
+    let _ = writeln!(w, "    let accounts = vec![");
+    for (k, a) in fixture_accounts(exec) {
+        let label = label_of.get(&k).copied().unwrap_or("account");
+        let _ = writeln!(w, "        (");
+        let _ = writeln!(w, "            \"{label}\",");
+        let _ = writeln!(w, "            {},", key_expr(&k));
+        let _ = writeln!(w, "            Account {{");
+        let _ = writeln!(w, "                lamports: {},", a.lamports);
+        let _ = writeln!(
+            w,
+            "                data: remap(bytes(\"{}\"), &keys),",
+            hex(&a.data)
+        );
+        let _ = writeln!(w, "                owner: {},", key_expr(&a.owner));
+        let _ = writeln!(w, "                executable: false,");
+        let _ = writeln!(w, "                rent_epoch: {},", a.rent_epoch);
+        let _ = writeln!(w, "            }},");
+        let _ = writeln!(w, "        ),");
+    }
+    let _ = writeln!(w, "    ];");
+    let _ = writeln!(w, "    for (_, key, account) in &accounts {{");
+    let _ = writeln!(w, "        pt.add_account(*key, account.clone());");
+    let _ = writeln!(w, "    }}");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    let mut ctx = pt.start_with_context().await;");
+    let _ = writeln!(w, "    ctx.set_sysvar(&Clock {{");
+    let _ = writeln!(w, "        slot: {},", exec.clock.slot);
+    let _ = writeln!(
+        w,
+        "        epoch_start_timestamp: {},",
+        exec.clock.epoch_start_timestamp
+    );
+    let _ = writeln!(w, "        epoch: {},", exec.clock.epoch);
+    let _ = writeln!(
+        w,
+        "        leader_schedule_epoch: {},",
+        exec.clock.leader_schedule_epoch
+    );
+    let _ = writeln!(w, "        unix_timestamp: {},", exec.clock.unix_timestamp);
+    let _ = writeln!(w, "    }});");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    let ixs = vec![");
+    for ix in exec.tx.instructions() {
+        let _ = writeln!(w, "        Instruction {{");
+        match key_expr(&ix.program_id).as_str() {
+            "program_id" => writeln!(w, "            program_id,"),
+            e => writeln!(w, "            program_id: {e},"),
+        }
+        .unwrap();
+        let _ = writeln!(w, "            accounts: vec![");
+        for m in &ix.accounts {
+            let ctor = if m.is_writable { "new" } else { "new_readonly" };
+            let _ = writeln!(
+                w,
+                "                AccountMeta::{ctor}({}, {}),",
+                key_expr(&m.pubkey),
+                m.is_signer
+            );
+        }
+        let _ = writeln!(w, "            ],");
+        let _ = writeln!(
+            w,
+            "            data: remap(bytes(\"{}\"), &keys),",
+            hex(&ix.data)
+        );
+        let _ = writeln!(w, "        }},");
+    }
+    let _ = writeln!(w, "    ];");
+    let signer_refs: String = signers.iter().map(|(_, v)| format!(", &{v}")).collect();
+    let _ = writeln!(
+        w,
+        "    let tx = Transaction::new_signed_with_payer(&ixs, Some(&ctx.payer.pubkey()), &[&ctx.payer{signer_refs}], ctx.last_blockhash);"
+    );
+    let _ = writeln!(
+        w,
+        "    let res = ctx.banks_client.process_transaction(tx).await;"
+    );
+    let _ = writeln!(w, "    println!(\"tx: {{res:?}}\");");
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    // delta post-state trên program thật");
+    let _ = writeln!(w, "    for (label, key, pre) in &accounts {{");
+    let _ = writeln!(
+        w,
+        "        match ctx.banks_client.get_account(*key).await.unwrap() {{"
+    );
+    let _ = writeln!(
+        w,
+        "            None => println!(\"{{label}} {{key}}: closed\"),"
+    );
+    let _ = writeln!(w, "            Some(post) => {{");
+    let _ = writeln!(w, "                if post.lamports != pre.lamports {{");
+    let _ = writeln!(
+        w,
+        "                    let delta = post.lamports as i128 - pre.lamports as i128;"
+    );
+    let _ = writeln!(w, "                    println!(\"{{label}} {{key}}: lamports {{}} -> {{}} ({{delta:+}})\", pre.lamports, post.lamports);");
+    let _ = writeln!(w, "                }}");
+    let _ = writeln!(w, "                if post.owner != pre.owner {{");
+    let _ = writeln!(w, "                    println!(\"{{label}} {{key}}: owner {{}} -> {{}}\", pre.owner, post.owner);");
+    let _ = writeln!(w, "                }}");
+    let _ = writeln!(w, "                if post.data != pre.data {{");
+    let _ = writeln!(w, "                    println!(\"{{label}} {{key}}: data changed ({{}} -> {{}} bytes)\", pre.data.len(), post.data.len());");
+    let _ = writeln!(w, "                }}");
+    let _ = writeln!(w, "            }}");
+    let _ = writeln!(w, "        }}");
+    let _ = writeln!(w, "    }}");
+
+    // post-state TraceVM dự đoán -> assert
+    let _ = writeln!(w);
+    let _ = writeln!(w, "    // TraceVM dự đoán:");
+    if exec.signals.dos {
+        let _ = writeln!(
+            w,
+            "    // vượt compute budget (compute unit xấp xỉ) -> tx fail, state không đổi"
+        );
+        let _ = writeln!(w, "    assert!(res.is_err(), \"expected compute budget exhaustion\");");
+        let _ = writeln!(w, "}}");
+        return s;
+    }
+    let _ = writeln!(w, "    res.unwrap();");
+    let mut predicted = 0;
+    for (k, pre) in fixture_accounts(exec) {
+        let label = label_of.get(&k).copied().unwrap_or("account");
+        let key = key_expr(&k);
+        let Some(post) = exec.post_snapshot.accounts.get(&k) else {
+            predicted += 1;
+            let _ = writeln!(
+                w,
+                "    assert!(ctx.banks_client.get_account({key}).await.unwrap().is_none(), \"{label} closed\");"
+            );
+            continue;
+        };
+        if post.lamports == pre.lamports && post.owner == pre.owner && post.data == pre.data {
+            continue;
+        }
+        predicted += 1;
+        let var = format!("post_{predicted}");
+        let _ = writeln!(
+            w,
+            "    let {var} = ctx.banks_client.get_account({key}).await.unwrap().expect(\"{label}\");"
+        );
+        if post.lamports != pre.lamports {
+            let _ = writeln!(
+                w,
+                "    assert_eq!({var}.lamports, {}, \"{label} lamports\"); // {} -> {}",
+                post.lamports, pre.lamports, post.lamports
+            );
+        }
+        if post.owner != pre.owner {
+            let _ = writeln!(
+                w,
+                "    assert_eq!({var}.owner, {}, \"{label} owner\");",
+                key_expr(&post.owner)
+            );
+        }
+        if post.data != pre.data {
+            let _ = writeln!(
+                w,
+                "    assert_eq!({var}.data, remap(bytes(\"{}\"), &keys), \"{label} data\");",
+                hex(&post.data)
+            );
+        }
+    }
+    if predicted == 0 {
+        let _ = writeln!(
+            w,
+            "    // không account nào đổi (finding là check thiếu): chỉ cần tx thành công"
+        );
+    }
+    let _ = writeln!(w, "}}");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzer_libafl::{FuzzConfig, FuzzTarget};
+
+    #[test]
+    fn harness_parses_and_asserts_predicted_post_state() {
+        let mut t = FuzzTarget::setup(&FuzzConfig::default()).unwrap();
+        let roles = t.roles();
+        // attacker + user ký, 3 account khác
+        let mut exec = t.execute(&[3, 0x13, 0x22, 1, 2, 3, 0xaa]);
+        let keys: Vec<Pubkey> = fixture_accounts(&exec).iter().map(|(k, _)| *k).collect();
+        assert!(keys.len() >= 3, "{keys:?}");
+        exec.post_snapshot = exec.pre_snapshot.clone();
+        let post = &mut exec.post_snapshot.accounts;
+        post.get_mut(&keys[0]).unwrap().lamports = 4_242;
+        let a = post.get_mut(&keys[1]).unwrap();
+        a.owner = roles.program_id;
+        a.data = vec![0xab; 3];
+        post.remove(&keys[2]);
+
+        let src = repro_harness("0123abcd", &exec, roles, "fuzz_target", 200_000);
+        syn::parse_file(&src).unwrap_or_else(|e| panic!("{e}\n{src}"));
+        assert!(src.contains("async fn fuzzdelsol_0123abcd()"));
+        assert!(src.contains("    res.unwrap();"));
+        assert!(src.contains("assert_eq!(post_1.lamports, 4242,"));
+        assert!(src.contains("assert_eq!(post_2.owner, program_id,"));
+        assert!(src.contains("assert_eq!(post_2.data, remap(bytes(\"ababab\"), &keys),"));
+        assert!(src.contains(".await.unwrap().is_none()"));
+        assert_eq!(src.matches("\n    assert").count(), 4);
+
+        // không account nào đổi: chỉ assert tx thành công
+        exec.post_snapshot = exec.pre_snapshot.clone();
+        let src = repro_harness("0123abcd", &exec, roles, "fuzz_target", 200_000);
+        syn::parse_file(&src).unwrap();
+        assert!(src.contains("    res.unwrap();"));
+        assert!(!src.contains("\n    assert"));
+
+        // vượt compute budget: tx phải lỗi
+        exec.signals.dos = true;
+        let src = repro_harness("0123abcd", &exec, roles, "fuzz_target", 200_000);
+        syn::parse_file(&src).unwrap();
+        assert!(src.contains("assert!(res.is_err(),"));
+        assert!(!src.contains("res.unwrap()"));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::types::VulnReport;
+use serde_json::{json, Value};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Mô tả ngắn của từng class (rule SARIF)
+fn rule_description(class: &str) -> &'static str {
+    match class {
+        "LAMPORTS_THEFT" => "Attacker gains lamports from accounts it does not own",
+        "TOKEN_THEFT" => "Attacker gains SPL tokens from accounts it does not own",
+        "SUPPLY_VIOLATION" => "Mint supply changes without a matching mint/burn",
+        "TOKEN_AUTHORITY_HIJACK" => "Token account or mint authority moved to an attacker key",
+        "TYPE_COSPLAY" => "Account data deserialized as the wrong account type",
+        "DUP_MUT" => "Same account passed twice as mutable",
+        "REINIT" => "Initialized account re-initialized",
+        "REVIVAL" => "Closed account revived within the transaction",
+        "RENT" => "Account left below the rent-exempt minimum",
+        "SYSVAR_SPOOF" => "Sysvar account key not checked",
+        "INTROSPECTION" => "Instruction introspection check can be bypassed",
+        "DOS" => "Attacker input exceeds the compute budget (approximate compute units, not metered)",
+        "MOC" => "Missing owner check",
+        "MSC" => "Missing signer check",
+        "ACPI" => "Arbitrary CPI target",
+        "MKC" => "Missing key check",
+        "IB" => "Integer overflow on attacker-controlled value",
+        _ => "fuzzdelsol finding",
+    }
+}
+
+/// Finding trộm tài sản là error, còn lại warning (giống crashes/ vs bugs/)
+fn level(class: &str) -> &'static str {
+    match class {
+        "LAMPORTS_THEFT" | "TOKEN_THEFT" => "error",
+        _ => "warning",
+    }
+}
+
+/// Đường dẫn -> URI của artifactLocation: tuyệt đối -> `file:///...`, tương đối giữ tương đối
+/// (consumer resolve theo thư mục gốc của repo). `\\` của Windows -> `/`, ký tự ngoài
+/// unreserved/`/` percent-encode (RFC 3986)
+fn artifact_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut out = String::with_capacity(path.len());
+    for b in path.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    if path.starts_with('/') {
+        format!("file://{out}")
+    } else {
+        out
+    }
+}
+
+/// SARIF 2.1.0 log, một result mỗi bucket: `(đường dẫn artifact không đuôi, vd "bugs/<bucket>", số hit, report)`.
+/// Finding có DWARF -> file + dòng đầu của handler instruction (region.startLine, logical location = function);
+/// không thì trỏ vào file ELF
+pub fn sarif_log(findings: &[(&str, u64, &VulnReport)], elf_path: &str) -> Value {
+    let mut classes: Vec<&str> = findings
+        .iter()
+        .map(|(_, _, r)| r.vuln_class.as_str())
+        .collect();
+    classes.sort();
+    classes.dedup();
+    let rules: Vec<Value> = classes
+        .iter()
+        .map(|c| {
+            json!({
+                "id": c,
+                "shortDescription": { "text": rule_description(c) },
+                "defaultConfiguration": { "level": level(c) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|&(path, hits, r)| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            let location = match &r.location {
+                // mức function: TraceVM không có PC thật, region là dòng đầu function
+                Some(l) => {
+                    let mut physical =
+                        json!({ "artifactLocation": { "uri": artifact_uri(&l.file) } });
+                    if let Some(line) = l.line {
+                        physical["region"] = json!({ "startLine": line });
+                    }
+                    json!({
+                        "physicalLocation": physical,
+                        "logicalLocations": [{ "name": l.function, "kind": "function" }],
+                    })
+                }
+                None => json!({
+                    "physicalLocation": { "artifactLocation": { "uri": artifact_uri(elf_path) } },
+                }),
+            };
+            let mut text = format!("{}: {}", r.vuln_class, rule_description(&r.vuln_class));
+            if let Some(ix) = &r.instruction {
+                text.push_str(&format!(" (instruction `{ix}`)"));
+            }
+            for d in &r.details {
+                text.push_str(&format!("\n{d}"));
+            }
+            json!({
+                "ruleId": r.vuln_class,
+                "ruleIndex": classes.iter().position(|c| *c == r.vuln_class).unwrap(),
+                "level": level(&r.vuln_class),
+                "message": { "text": text },
+                "locations": [location],
+                "partialFingerprints": { "fuzzdelsol/bucket": name },
+                "properties": {
+                    "oracles": r.oracles,
+                    "input": r.input_hex,
+                    "seed": r.seed,
+                    "hits": hits,
+                    "signature": r.bucket.as_ref().map(|b| &b.signature),
+                    "report": format!("{path}.report.json"),
+                },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "fuzzdelsol",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debuginfo::SourceLoc;
+
+    fn report(class: &str, location: Option<SourceLoc>) -> VulnReport {
+        VulnReport {
+            vuln_class: class.into(),
+            bucket: None,
+            oracles: vec![class.into()],
+            tx_payload_hex: String::new(),
+            details: vec!["detail".into()],
+            clock: String::new(),
+            account_diffs: Vec::new(),
+            trace_summary: String::new(),
+            trace_excerpt: Vec::new(),
+            input_hex: Some("00".into()),
+            seed: Some(7),
+            transaction: None,
+            instruction: Some("withdraw".into()),
+            location,
+            export: None,
+        }
+    }
+
+    #[test]
+    fn every_class_has_a_rule() {
+        for c in crate::types::CLASSES {
+            assert_ne!(rule_description(c), rule_description("NONE"), "{c}");
+        }
+    }
+
+    #[test]
+    fn uri_forms() {
+        assert_eq!(
+            artifact_uri("/work/my vault/src/lib.rs"),
+            "file:///work/my%20vault/src/lib.rs"
+        );
+        assert_eq!(
+            artifact_uri("programs/vault/src/lib.rs"),
+            "programs/vault/src/lib.rs"
+        );
+        assert_eq!(
+            artifact_uri("target\\deploy\\vault.so"),
+            "target/deploy/vault.so"
+        );
+    }
+
+    #[test]
+    fn results_rules_and_locations() {
+        let with_line = report(
+            "MSC",
+            Some(SourceLoc {
+                file: "/work/vault/src/lib.rs".into(),
+                function: "withdraw".into(),
+                line: Some(42),
+            }),
+        );
+        let without = report("LAMPORTS_THEFT", None);
+        let again = report("MSC", None);
+        let log = sarif_log(
+            &[
+                ("bugs/aa", 3, &with_line),
+                ("crashes/bb", 1, &without),
+                ("bugs/cc", 1, &again),
+            ],
+            "target/deploy/vault.so",
+        );
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        let results = run["results"].as_array().unwrap();
+        for r in results {
+            let ix = r["ruleIndex"].as_u64().unwrap() as usize;
+            assert_eq!(rules[ix]["id"], r["ruleId"]);
+        }
+
+        let loc = &results[0]["locations"][0];
+        assert_eq!(
+            loc["physicalLocation"]["artifactLocation"]["uri"],
+            "file:///work/vault/src/lib.rs"
+        );
+        assert_eq!(loc["physicalLocation"]["region"]["startLine"], 42);
+        assert_eq!(loc["logicalLocations"][0]["name"], "withdraw");
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(results[0]["partialFingerprints"]["fuzzdelsol/bucket"], "aa");
+        assert_eq!(results[0]["properties"]["hits"], 3);
+
+        // không DWARF -> trỏ vào ELF, không region
+        let loc = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(loc["artifactLocation"]["uri"], "target/deploy/vault.so");
+        assert!(loc.get("region").is_none());
+        assert_eq!(results[1]["level"], "error");
+    }
+}
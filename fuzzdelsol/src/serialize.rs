@@ -0,0 +1,256 @@
+use crate::types::{LedgerSnapshot, Transaction};
+use solana_sdk::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use solana_sdk::pubkey::Pubkey;
+
+/// Marker cho account không trùng (BPF loader ABI)
+#[cfg(test)]
+pub const NON_DUP_MARKER: u8 = u8::MAX;
+const BPF_ALIGN_OF_U128: usize = 8;
+
+/// Vị trí của một account meta trong input region
+#[derive(Clone, Debug)]
+pub struct SerializedAccount {
+    pub key: Pubkey,
+    /// Some(i) nếu meta này là dup của meta thứ i (chỉ có 1 byte marker trong region)
+    pub dup_of: Option<usize>,
+    /// offset data của account trong region (dup: offset của meta gốc)
+    pub data_offset: usize,
+    pub data_len: usize,
+}
+
+/// Input region mà entrypoint nhận (aligned serialization, loader v2/v3)
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct SerializedInput {
+    pub bytes: Vec<u8>,
+    pub accounts: Vec<SerializedAccount>,
+    pub ix_data_offset: usize,
+}
+
+/// Bố cục input region (vị trí account, instruction data, tổng độ dài) không dựng buffer
+#[derive(Clone, Debug)]
+pub struct InputLayout {
+    pub accounts: Vec<SerializedAccount>,
+    pub ix_data_offset: usize,
+    pub len: usize,
+}
+
+// dup marker + 7 byte pad
+const DUP_ACCOUNT_LEN: usize = 8;
+// marker, is_signer, is_writable, executable, pad 4, key, owner, lamports, data len
+const ACCOUNT_HEADER_LEN: usize = 8 + 32 + 32 + 8 + 8;
+
+/// Offset như `serialize_parameters`, chỉ tính từ số meta và độ dài data
+/// (TraceVM chỉ cần vị trí, không cần nội dung region)
+pub fn input_layout(tx: &Transaction, snap: &LedgerSnapshot) -> InputLayout {
+    let metas = &tx.instruction.accounts;
+    let mut accounts: Vec<SerializedAccount> = Vec::with_capacity(metas.len());
+    let mut off = 8;
+    for (i, m) in metas.iter().enumerate() {
+        if let Some(first) = metas[..i].iter().position(|x| x.pubkey == m.pubkey) {
+            off += DUP_ACCOUNT_LEN;
+            let mut dup = accounts[first].clone();
+            dup.dup_of = Some(first);
+            accounts.push(dup);
+            continue;
+        }
+        let data_len = snap
+            .accounts
+            .get(&m.pubkey)
+            .map(|a| a.data.len())
+            .unwrap_or(0);
+        let data_offset = off + ACCOUNT_HEADER_LEN;
+        off = (data_offset + data_len + MAX_PERMITTED_DATA_INCREASE)
+            .next_multiple_of(BPF_ALIGN_OF_U128)
+            + 8;
+        accounts.push(SerializedAccount {
+            key: m.pubkey,
+            dup_of: None,
+            data_offset,
+            data_len,
+        });
+    }
+    let ix_data_offset = off + 8;
+    InputLayout {
+        accounts,
+        ix_data_offset,
+        len: ix_data_offset + tx.instruction.data.len() + 32,
+    }
+}
+
+/// Serialize instruction + account state theo ABI của BPF loader:
+/// meta trùng với một meta trước đó chỉ ghi index của meta đầu tiên (dup marker).
+/// Bản tham chiếu để kiểm `input_layout`
+#[cfg(test)]
+pub fn serialize_parameters(
+    tx: &Transaction,
+    snap: &LedgerSnapshot,
+    program_id: &Pubkey,
+) -> SerializedInput {
+    let metas = &tx.instruction.accounts;
+    let mut out: Vec<u8> = Vec::new();
+    let mut accounts: Vec<SerializedAccount> = Vec::with_capacity(metas.len());
+
+    out.extend_from_slice(&(metas.len() as u64).to_le_bytes());
+    for (i, m) in metas.iter().enumerate() {
+        if let Some(first) = metas[..i].iter().position(|x| x.pubkey == m.pubkey) {
+            out.push(first as u8);
+            out.extend_from_slice(&[0u8; 7]);
+            let mut dup = accounts[first].clone();
+            dup.dup_of = Some(first);
+            accounts.push(dup);
+            continue;
+        }
+
+        let (owner, lamports, data, executable, rent_epoch) = snap
+            .accounts
+            .get(&m.pubkey)
+            .map(|a| {
+                (
+                    a.owner,
+                    a.lamports,
+                    a.data.as_slice(),
+                    a.is_executable,
+                    a.rent_epoch,
+                )
+            })
+            .unwrap_or((Pubkey::default(), 0, &[][..], false, 0));
+
+        out.push(NON_DUP_MARKER);
+        out.push(m.is_signer as u8);
+        out.push(m.is_writable as u8);
+        out.push(executable as u8);
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(m.pubkey.as_ref());
+        out.extend_from_slice(owner.as_ref());
+        out.extend_from_slice(&lamports.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        let data_offset = out.len();
+        out.extend_from_slice(data);
+        out.resize(out.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        out.resize(out.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+        out.extend_from_slice(&rent_epoch.to_le_bytes());
+
+        accounts.push(SerializedAccount {
+            key: m.pubkey,
+            dup_of: None,
+            data_offset,
+            data_len: data.len(),
+        });
+    }
+
+    out.extend_from_slice(&(tx.instruction.data.len() as u64).to_le_bytes());
+    let ix_data_offset = out.len();
+    out.extend_from_slice(&tx.instruction.data);
+    out.extend_from_slice(program_id.as_ref());
+
+    SerializedInput {
+        bytes: out,
+        accounts,
+        ix_data_offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{InstrAccountMeta, Instruction};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn meta(pubkey: Pubkey) -> InstrAccountMeta {
+        InstrAccountMeta {
+            pubkey,
+            is_signer: false,
+            is_writable: true,
+        }
+    }
+
+    #[test]
+    fn duplicate_metas_get_dup_markers() {
+        let program_id = Pubkey::new_unique();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let data = vec![7u8, 8, 9];
+        let tx = Transaction {
+            signers: BTreeSet::new(),
+            all_accounts_sorted: vec![a, b],
+            instruction: Instruction {
+                program_id,
+                accounts: vec![meta(a), meta(b), meta(a), meta(b)],
+                data: data.clone(),
+            },
+            siblings: vec![],
+            instruction_index: 0,
+            clock_override: None,
+        };
+        let snap = LedgerSnapshot {
+            program_id,
+            accounts: BTreeMap::new(),
+        };
+        let input = serialize_parameters(&tx, &snap, &program_id);
+        let bytes = &input.bytes;
+
+        assert_eq!(u64::from_le_bytes(bytes[..8].try_into().unwrap()), 4);
+        assert_eq!(bytes[8], NON_DUP_MARKER);
+        let dup_of: Vec<Option<usize>> = input.accounts.iter().map(|m| m.dup_of).collect();
+        assert_eq!(dup_of, [None, None, Some(0), Some(1)]);
+
+        // region kết thúc bằng: 2 meta dup (marker + 7 byte pad), data len, ix data, program id
+        let tail = bytes.len() - 32 - data.len() - 8;
+        assert_eq!(
+            &bytes[tail - 16..tail],
+            &[0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            u64::from_le_bytes(bytes[tail..tail + 8].try_into().unwrap()),
+            data.len() as u64
+        );
+        assert_eq!(&bytes[tail + 8..tail + 8 + data.len()], &data[..]);
+        assert_eq!(&bytes[bytes.len() - 32..], program_id.as_ref());
+    }
+
+    #[test]
+    fn layout_matches_serialized_region() {
+        let program_id = Pubkey::new_unique();
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut accounts = BTreeMap::new();
+        // độ dài data lẻ (cần pad align), rỗng, lớn; keys[3] không có trong snapshot
+        for (k, len) in keys.iter().zip([3usize, 0, 165]) {
+            accounts.insert(
+                *k,
+                crate::types::Account {
+                    owner: program_id,
+                    lamports: 1,
+                    data: vec![1; len],
+                    is_executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+        let snap = LedgerSnapshot {
+            program_id,
+            accounts,
+        };
+        for order in [vec![0, 1, 2, 3], vec![2, 0, 2, 3, 0], vec![], vec![1, 1, 1]] {
+            let tx = Transaction {
+                signers: BTreeSet::new(),
+                all_accounts_sorted: keys.clone(),
+                instruction: Instruction {
+                    program_id,
+                    accounts: order.iter().map(|&i| meta(keys[i])).collect(),
+                    data: vec![9; order.len()],
+                },
+                siblings: vec![],
+                instruction_index: 0,
+                clock_override: None,
+            };
+            let full = serialize_parameters(&tx, &snap, &program_id);
+            let layout = input_layout(&tx, &snap);
+            assert_eq!(layout.len, full.bytes.len());
+            assert_eq!(layout.ix_data_offset, full.ix_data_offset);
+            assert_eq!(
+                format!("{:?}", layout.accounts),
+                format!("{:?}", full.accounts)
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::types::LedgerSnapshot;
+use solana_sdk::pubkey::Pubkey;
+
+/// SPL Token program id (Tokenkeg...)
+pub const TOKEN_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const MINT_LEN: usize = 82;
+
+/// SPL token account (packed layout, 165 bytes)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: u8,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+}
+
+/// SPL mint (packed layout, 82 bytes)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+fn read_u64(d: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(d[off..off + 8].try_into().unwrap())
+}
+
+fn read_key(d: &[u8], off: usize) -> Pubkey {
+    Pubkey::new_from_array(d[off..off + 32].try_into().unwrap())
+}
+
+// COption<Pubkey> = u32 tag + 32 bytes
+fn read_opt_key(d: &[u8], off: usize) -> Option<Pubkey> {
+    match u32::from_le_bytes(d[off..off + 4].try_into().unwrap()) {
+        1 => Some(read_key(d, off + 4)),
+        _ => None,
+    }
+}
+
+fn write_opt_key(d: &mut [u8], off: usize, k: &Option<Pubkey>) {
+    match k {
+        Some(k) => {
+            d[off..off + 4].copy_from_slice(&1u32.to_le_bytes());
+            d[off + 4..off + 36].copy_from_slice(k.as_ref());
+        }
+        None => d[off..off + 36].fill(0),
+    }
+}
+
+impl TokenAccount {
+    /// None nếu data không phải token account đã init
+    pub fn unpack(d: &[u8]) -> Option<Self> {
+        if d.len() != TOKEN_ACCOUNT_LEN || d[108] == 0 {
+            return None;
+        }
+        let is_native = match u32::from_le_bytes(d[109..113].try_into().unwrap()) {
+            1 => Some(read_u64(d, 113)),
+            _ => None,
+        };
+        Some(Self {
+            mint: read_key(d, 0),
+            owner: read_key(d, 32),
+            amount: read_u64(d, 64),
+            delegate: read_opt_key(d, 72),
+            state: d[108],
+            is_native,
+            delegated_amount: read_u64(d, 121),
+            close_authority: read_opt_key(d, 129),
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut d = vec![0u8; TOKEN_ACCOUNT_LEN];
+        d[0..32].copy_from_slice(self.mint.as_ref());
+        d[32..64].copy_from_slice(self.owner.as_ref());
+        d[64..72].copy_from_slice(&self.amount.to_le_bytes());
+        write_opt_key(&mut d, 72, &self.delegate);
+        d[108] = self.state;
+        if let Some(n) = self.is_native {
+            d[109..113].copy_from_slice(&1u32.to_le_bytes());
+            d[113..121].copy_from_slice(&n.to_le_bytes());
+        }
+        d[121..129].copy_from_slice(&self.delegated_amount.to_le_bytes());
+        write_opt_key(&mut d, 129, &self.close_authority);
+        d
+    }
+}
+
+impl Mint {
+    pub fn unpack(d: &[u8]) -> Option<Self> {
+        if d.len() != MINT_LEN || d[45] == 0 {
+            return None;
+        }
+        Some(Self {
+            mint_authority: read_opt_key(d, 0),
+            supply: read_u64(d, 36),
+            decimals: d[44],
+            is_initialized: d[45] != 0,
+            freeze_authority: read_opt_key(d, 46),
+        })
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut d = vec![0u8; MINT_LEN];
+        write_opt_key(&mut d, 0, &self.mint_authority);
+        d[36..44].copy_from_slice(&self.supply.to_le_bytes());
+        d[44] = self.decimals;
+        d[45] = self.is_initialized as u8;
+        write_opt_key(&mut d, 46, &self.freeze_authority);
+        d
+    }
+}
+
+/// Tất cả token account (owner = token program) trong snapshot
+pub fn token_accounts(s: &LedgerSnapshot) -> Vec<(Pubkey, TokenAccount)> {
+    s.accounts
+        .iter()
+        .filter(|(_, a)| a.owner == TOKEN_PROGRAM_ID)
+        .filter_map(|(k, a)| TokenAccount::unpack(&a.data).map(|t| (*k, t)))
+        .collect()
+}
+
+/// Tất cả mint trong snapshot
+pub fn mints(s: &LedgerSnapshot) -> Vec<(Pubkey, Mint)> {
+    s.accounts
+        .iter()
+        .filter(|(_, a)| a.owner == TOKEN_PROGRAM_ID)
+        .filter_map(|(k, a)| Mint::unpack(&a.data).map(|m| (*k, m)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_account_round_trip() {
+        let t = TokenAccount {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 1_000_000,
+            delegate: Some(Pubkey::new_unique()),
+            state: 1,
+            is_native: Some(2_039_280),
+            delegated_amount: 42,
+            close_authority: None,
+        };
+        let d = t.pack();
+        assert_eq!(d.len(), TOKEN_ACCOUNT_LEN);
+        assert_eq!(TokenAccount::unpack(&d), Some(t));
+    }
+
+    #[test]
+    fn mint_round_trip() {
+        let m = Mint {
+            mint_authority: Some(Pubkey::new_unique()),
+            supply: u64::MAX,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: None,
+        };
+        let d = m.pack();
+        assert_eq!(d.len(), MINT_LEN);
+        assert_eq!(Mint::unpack(&d), Some(m));
+    }
+
+    #[test]
+    fn unpack_rejects_uninitialized_and_wrong_len() {
+        assert_eq!(TokenAccount::unpack(&[0u8; TOKEN_ACCOUNT_LEN]), None);
+        assert_eq!(Mint::unpack(&[0u8; MINT_LEN]), None);
+        let m = Mint {
+            mint_authority: None,
+            supply: 1,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: None,
+        };
+        // token account và mint không lẫn nhau theo độ dài
+        assert_eq!(TokenAccount::unpack(&m.pack()), None);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::diff::role;
+use crate::oracles::VmEvent;
+use crate::txfile::Roles;
+use crate::types::ExecResult;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeSet;
+
+/// Bucket root cause của một finding: class oracle + signature ổn định giữa các run
+/// (không phụ thuộc đường đi / coverage hash, không chứa key sinh ngẫu nhiên)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bucket {
+    pub id: String,
+    pub class: String,
+    /// `ix:<instruction dispatch>` rồi `role:kind/pc@call stack`, vd `program-owned:WriteLamports/0xe3@program`
+    pub signature: Vec<String>,
+}
+
+/// Tên loại event (site trong TraceVM)
+fn kind(ev: &VmEvent) -> &'static str {
+    match ev {
+        VmEvent::Cmp { .. } => "Cmp",
+        VmEvent::MemCmp { .. } => "MemCmp",
+        VmEvent::ReadAccountData { .. } => "ReadAccountData",
+        VmEvent::WriteLamports { .. } => "WriteLamports",
+        VmEvent::WriteData { .. } => "WriteData",
+        VmEvent::Cpi { .. } => "Cpi",
+        VmEvent::CpiRejected { .. } => "CpiRejected",
+        VmEvent::KeyAccess { .. } => "KeyAccess",
+        VmEvent::IntegerOp { .. } => "IntegerOp",
+        VmEvent::ReadAccountAs { .. } => "ReadAccountAs",
+        VmEvent::MetaTransfer { .. } => "MetaTransfer",
+        VmEvent::CreateAccount { .. } => "CreateAccount",
+        VmEvent::ReadSysvar { .. } => "ReadSysvar",
+        VmEvent::ClockRead { .. } => "ClockRead",
+        VmEvent::Introspect { .. } => "Introspect",
+        VmEvent::ComputeExhausted { .. } => "ComputeExhausted",
+        VmEvent::Realloc { .. } => "Realloc",
+        VmEvent::TokenTransfer { .. } => "TokenTransfer",
+        VmEvent::TokenSupplyOp { .. } => "TokenSupplyOp",
+        VmEvent::TokenSetAuthority { .. } => "TokenSetAuthority",
+    }
+}
+
+/// Account bị ghi bởi event (write site)
+fn written(ev: &VmEvent) -> Vec<Pubkey> {
+    match ev {
+        VmEvent::WriteLamports { acct, .. }
+        | VmEvent::WriteData { acct, .. }
+        | VmEvent::Realloc { acct, .. } => vec![*acct],
+        VmEvent::MetaTransfer { from, to, .. } => vec![*from, *to],
+        VmEvent::CreateAccount {
+            payer, new_account, ..
+        } => vec![*payer, *new_account],
+        VmEvent::TokenTransfer {
+            source,
+            destination,
+            ..
+        } => vec![*source, *destination],
+        VmEvent::TokenSupplyOp { mint, account, .. } => vec![*mint, *account],
+        VmEvent::TokenSetAuthority { account, .. } => vec![*account],
+        _ => vec![],
+    }
+}
+
+/// Account mà event đọc/kiểm tra (cho finding không ghi gì: MSC/MKC/SYSVAR_SPOOF...)
+fn touched(ev: &VmEvent) -> Option<Pubkey> {
+    match ev {
+        VmEvent::ReadAccountData { acct, .. }
+        | VmEvent::ReadAccountAs { acct, .. }
+        | VmEvent::ReadSysvar { acct, .. } => Some(*acct),
+        VmEvent::ClockRead { acct, .. } => *acct,
+        VmEvent::KeyAccess { required_key, .. } => Some(*required_key),
+        VmEvent::Cpi {
+            invoked_program, ..
+        } => Some(*invoked_program),
+        _ => None,
+    }
+}
+
+/// Loại event mà oracle của class xét (site ưu tiên); None = site ghi đầu tiên
+fn class_kinds(class: &str) -> Option<&'static [&'static str]> {
+    Some(match class {
+        "ACPI" => &["Cpi"],
+        "MKC" => &["KeyAccess"],
+        "MOC" => &["ReadAccountData"],
+        "SYSVAR_SPOOF" => &["ReadSysvar", "ClockRead"],
+        "TYPE_COSPLAY" => &["ReadAccountAs"],
+        "INTROSPECTION" => &["Introspect"],
+        "SUPPLY_VIOLATION" => &["TokenSupplyOp"],
+        "TOKEN_AUTHORITY_HIJACK" => &["TokenSetAuthority"],
+        _ => return None,
+    })
+}
+
+/// FNV-1a 64
+pub(crate) fn stable_hash(s: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for &b in s {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+/// Bucket của một lần thực thi có objective; `instruction` = instruction Anchor dispatch đã chọn.
+/// Signature = instruction + (vai trò account, site) cho từng account oracle chỉ ra (OracleSignals::flagged, bỏ program
+/// đang fuzz); site = event đầu tiên thuộc loại oracle xét (class_kinds), không có thì event ghi account đó đầu tiên,
+/// rồi event đọc/kiểm tra nó; kèm pc và call stack. Class không nêu account: theft -> account
+/// bị rút lamports, MOC -> account foreign-owned bị đọc, còn lại -> event đầu tiên không phải so sánh
+pub fn bucket(exec: &ExecResult, roles: Roles, instruction: Option<&str>) -> Bucket {
+    let class = exec.signals.class().to_string();
+    let program_label = |p: &Pubkey| {
+        if *p == roles.program_id {
+            "program".to_string()
+        } else {
+            p.to_string()
+        }
+    };
+    let role_of = |k: &Pubkey| {
+        let owner = exec
+            .pre_snapshot
+            .accounts
+            .get(k)
+            .or_else(|| exec.post_snapshot.accounts.get(k))
+            .map(|a| a.owner);
+        role(k, &owner.unwrap_or_default(), roles)
+    };
+
+    // site của từng event: loại event @ call stack (CPI đệ quy vào cùng program gộp làm một frame)
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut sites = Vec::with_capacity(exec.events.len());
+    for ev in &exec.events {
+        stack.retain(|(d, _)| *d < ev.depth);
+        let label = program_label(&ev.program_id);
+        if stack.last().map(|(_, l)| l) != Some(&label) {
+            stack.push((ev.depth, label));
+        }
+        let frames: Vec<&str> = stack.iter().map(|(_, l)| l.as_str()).collect();
+        sites.push(format!(
+            "{}/{:#04x}@{}",
+            kind(&ev.event),
+            ev.pc,
+            frames.join(">")
+        ));
+    }
+    let events = || exec.events.iter().zip(&sites);
+
+    let mut keys: BTreeSet<Pubkey> = exec
+        .signals
+        .flagged
+        .get(class.as_str())
+        .into_iter()
+        .flatten()
+        .filter(|k| **k != roles.program_id)
+        .copied()
+        .collect();
+    if keys.is_empty() {
+        keys = match class.as_str() {
+            "LAMPORTS_THEFT" | "TOKEN_THEFT" => exec
+                .pre_snapshot
+                .accounts
+                .iter()
+                .filter(|(k, a)| {
+                    **k != roles.attacker
+                        && exec.post_snapshot.accounts.get(k).map_or(0, |p| p.lamports) < a.lamports
+                })
+                .map(|(k, _)| *k)
+                .collect(),
+            "MOC" => events()
+                .filter_map(|(ev, _)| match &ev.event {
+                    VmEvent::ReadAccountData { acct, owner } if *owner != roles.program_id => {
+                        Some(*acct)
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => BTreeSet::new(),
+        };
+    }
+
+    let mut signature: BTreeSet<String> = keys
+        .iter()
+        .map(|k| {
+            let involves = |ev: &VmEvent| written(ev).contains(k) || touched(ev) == Some(*k);
+            let site = class_kinds(&class)
+                .and_then(|kinds| {
+                    events().find(|(ev, _)| kinds.contains(&kind(&ev.event)) && involves(&ev.event))
+                })
+                .or_else(|| events().find(|(ev, _)| written(&ev.event).contains(k)))
+                .or_else(|| events().find(|(ev, _)| touched(&ev.event) == Some(*k)))
+                .map_or("-", |(_, s)| s.as_str());
+            format!("{}:{site}", role_of(k))
+        })
+        .collect();
+    if signature.is_empty() {
+        if let Some((_, site)) = events()
+            .find(|(ev, _)| !matches!(ev.event, VmEvent::Cmp { .. } | VmEvent::MemCmp { .. }))
+        {
+            signature.insert(format!("-:{site}"));
+        }
+    }
+    let signature: Vec<String> = std::iter::once(format!("ix:{}", instruction.unwrap_or("-")))
+        .chain(signature)
+        .collect();
+    let id = format!(
+        "{:016x}",
+        stable_hash(format!("{class}\n{}", signature.join("\n")).as_bytes())
+    );
+    Bucket {
+        id,
+        class,
+        signature,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzer_libafl::{FuzzConfig, FuzzTarget};
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x85944171f73967e8);
+    }
+
+    /// Input giả ngẫu nhiên, cố định giữa các lần chạy test
+    fn inputs(n: usize) -> Vec<Vec<u8>> {
+        let mut s: u64 = 1;
+        (0..n)
+            .map(|_| {
+                (0..64)
+                    .map(|_| {
+                        s = s
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        (s >> 56) as u8
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bucket_is_stable_across_setups() {
+        // mỗi setup có program id / attacker / user khác nhau; signature chỉ dùng vai trò nên bucket giữ nguyên
+        let mut a = FuzzTarget::setup(&FuzzConfig::default()).unwrap();
+        let mut b = FuzzTarget::setup(&FuzzConfig::default()).unwrap();
+        assert_ne!(a.program_id, b.program_id);
+        let mut ids = BTreeSet::new();
+        for input in inputs(50) {
+            let (ea, eb) = (a.execute(&input), b.execute(&input));
+            if !ea.signals.any() {
+                continue;
+            }
+            let (ba, bb) = (a.bucket(&ea), b.bucket(&eb));
+            assert_eq!(ba.id, bb.id, "{:?} vs {:?}", ba.signature, bb.signature);
+            assert_eq!(ba.signature, bb.signature);
+            // cùng input chạy lại -> cùng bucket
+            let again = a.execute(&input);
+            assert_eq!(a.bucket(&again).id, ba.id);
+            ids.insert(ba.id);
+        }
+        // các root cause khác nhau không bị gộp chung
+        assert!(ids.len() > 1);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::types::{hex, Account, ExecResult, InstrAccountMeta, Instruction, Transaction};
+use crate::vm_rbpf::OPS_VERSION;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::str::FromStr;
+
+/// Định dạng input typed trên đĩa (JSON). Tăng version khi đổi schema, `from_json` phải
+/// đọc được mọi version cũ hơn
+pub const TX_FILE_FORMAT: &str = "fuzzdelsol-tx";
+pub const TX_FILE_VERSION: u32 = 1;
+
+/// Transaction cụ thể: key, signer/writable, data, clock và state của account (override)
+/// -> replay không phụ thuộc heuristic của TxGenerator hay key pool của emulator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxFile {
+    pub format: String,
+    pub version: u32,
+    /// key của các vai trò khi ghi file; lúc load được thay bằng key của emulator hiện tại
+    pub program_id: String,
+    pub attacker: String,
+    pub user: String,
+    pub instructions: Vec<TxFileInstruction>,
+    /// vị trí instruction của program trong `instructions`
+    pub instruction_index: usize,
+    pub clock: Option<TxFileClock>,
+    #[serde(default)]
+    pub account_overrides: Vec<TxFileAccount>,
+    /// encoding trace op của instruction data (file ghi trước khi có field này: version 1)
+    #[serde(default = "first_ext_ops_version")]
+    pub ops_version: u8,
+    /// input raw (legacy) đã sinh ra tx này, chỉ để tham khảo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxFileInstruction {
+    pub program_id: String,
+    pub accounts: Vec<TxFileMeta>,
+    /// hex
+    pub data: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxFileMeta {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct TxFileClock {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+/// State account trước tx (ghi đè account emulator tạo ra)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TxFileAccount {
+    pub pubkey: String,
+    pub owner: String,
+    pub lamports: u64,
+    /// hex
+    pub data: String,
+    #[serde(default)]
+    pub executable: bool,
+    #[serde(default)]
+    pub rent_epoch: u64,
+}
+
+/// Key của các vai trò trong emulator (program, attacker, user)
+#[derive(Clone, Copy, Debug)]
+pub struct Roles {
+    pub program_id: Pubkey,
+    pub attacker: Pubkey,
+    pub user: Pubkey,
+}
+
+fn first_ext_ops_version() -> u8 {
+    1
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_key(s: &str) -> io::Result<Pubkey> {
+    Pubkey::from_str(s).map_err(|e| invalid(format!("bad pubkey `{s}`: {e}")))
+}
+
+fn parse_hex(s: &str) -> io::Result<Vec<u8>> {
+    ::hex::decode(s).map_err(|e| invalid(format!("bad hex data: {e}")))
+}
+
+impl TxFile {
+    /// Typed file từ một lần thực thi: override = mọi account (không executable, không phải sysvar thật)
+    /// mà tx tham chiếu, lấy từ pre-snapshot
+    pub fn from_exec(exec: &ExecResult, roles: Roles, raw: Option<&[u8]>) -> Self {
+        let tx = &exec.tx;
+        let referenced: BTreeSet<Pubkey> = tx
+            .instructions()
+            .iter()
+            .flat_map(|ix| ix.accounts.iter().map(|m| m.pubkey))
+            .collect();
+        let account_overrides = referenced
+            .iter()
+            .filter_map(|k| exec.pre_snapshot.accounts.get(k).map(|a| (k, a)))
+            .filter(|(_, a)| !a.is_executable && a.owner != sysvar::id())
+            .map(|(k, a)| TxFileAccount {
+                pubkey: k.to_string(),
+                owner: a.owner.to_string(),
+                lamports: a.lamports,
+                data: hex(&a.data),
+                executable: a.is_executable,
+                rent_epoch: a.rent_epoch,
+            })
+            .collect();
+
+        Self {
+            format: TX_FILE_FORMAT.to_string(),
+            version: TX_FILE_VERSION,
+            program_id: roles.program_id.to_string(),
+            attacker: roles.attacker.to_string(),
+            user: roles.user.to_string(),
+            instructions: tx
+                .instructions()
+                .into_iter()
+                .map(|ix| TxFileInstruction {
+                    program_id: ix.program_id.to_string(),
+                    accounts: ix
+                        .accounts
+                        .iter()
+                        .map(|m| TxFileMeta {
+                            pubkey: m.pubkey.to_string(),
+                            is_signer: m.is_signer,
+                            is_writable: m.is_writable,
+                        })
+                        .collect(),
+                    data: hex(&ix.data),
+                })
+                .collect(),
+            instruction_index: tx.instruction_index,
+            clock: Some(TxFileClock {
+                slot: exec.clock.slot,
+                unix_timestamp: exec.clock.unix_timestamp,
+            }),
+            account_overrides,
+            ops_version: exec.ops_version,
+            raw: raw.map(hex),
+        }
+    }
+
+    /// Parse typed file; None nếu bytes không phải typed format (tức input raw legacy)
+    pub fn from_json(bytes: &[u8]) -> Option<io::Result<Self>> {
+        let v: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+        if v.get("format").and_then(|f| f.as_str()) != Some(TX_FILE_FORMAT) {
+            return None;
+        }
+        Some(match v.get("version").and_then(|x| x.as_u64()) {
+            // v1 = schema hiện tại
+            Some(1) => serde_json::from_value::<Self>(v)
+                .map_err(|e| invalid(format!("bad tx file: {e}")))
+                .and_then(|f| match f.ops_version {
+                    n if n > OPS_VERSION => Err(invalid(format!(
+                        "tx file ops version {n} is newer than supported ({OPS_VERSION})"
+                    ))),
+                    _ => Ok(f),
+                }),
+            Some(n) if n > TX_FILE_VERSION as u64 => Err(invalid(format!(
+                "tx file version {n} is newer than supported ({TX_FILE_VERSION})"
+            ))),
+            Some(n) => Err(invalid(format!("unknown or invalid tx file version {n}"))),
+            None => Err(invalid("tx file without a valid version".into())),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Dựng lại tx + account override; key vai trò lúc ghi được thay bằng `roles` hiện tại
+    pub fn to_transaction(
+        &self,
+        roles: Roles,
+    ) -> io::Result<(Transaction, Vec<(Pubkey, Account)>)> {
+        let remap: BTreeMap<Pubkey, Pubkey> = [
+            (parse_key(&self.program_id)?, roles.program_id),
+            (parse_key(&self.attacker)?, roles.attacker),
+            (parse_key(&self.user)?, roles.user),
+        ]
+        .into_iter()
+        .collect();
+        let key = |s: &str| -> io::Result<Pubkey> {
+            let k = parse_key(s)?;
+            Ok(remap.get(&k).copied().unwrap_or(k))
+        };
+
+        let mut ixs = Vec::new();
+        for ix in &self.instructions {
+            let mut accounts = Vec::new();
+            for m in &ix.accounts {
+                accounts.push(InstrAccountMeta {
+                    pubkey: key(&m.pubkey)?,
+                    is_signer: m.is_signer,
+                    is_writable: m.is_writable,
+                });
+            }
+            ixs.push(Instruction {
+                program_id: key(&ix.program_id)?,
+                accounts,
+                data: parse_hex(&ix.data)?,
+            });
+        }
+        if self.instruction_index >= ixs.len() {
+            return Err(invalid(format!(
+                "instruction_index {} out of range ({} instructions)",
+                self.instruction_index,
+                ixs.len()
+            )));
+        }
+        let instruction = ixs.remove(self.instruction_index);
+        if instruction.program_id != roles.program_id {
+            return Err(invalid(
+                "instruction at instruction_index does not target the program".into(),
+            ));
+        }
+
+        let metas = || {
+            ixs.iter()
+                .chain([&instruction])
+                .flat_map(|ix| ix.accounts.iter())
+        };
+        let signers: BTreeSet<Pubkey> = metas().filter(|m| m.is_signer).map(|m| m.pubkey).collect();
+        let mut all_accounts_sorted: Vec<Pubkey> = metas().map(|m| m.pubkey).collect();
+        all_accounts_sorted.sort();
+        all_accounts_sorted.dedup();
+
+        let mut overrides = Vec::new();
+        for a in &self.account_overrides {
+            let k = key(&a.pubkey)?;
+            overrides.push((
+                k,
+                Account {
+                    owner: key(&a.owner)?,
+                    lamports: a.lamports,
+                    data: parse_hex(&a.data)?,
+                    is_executable: a.executable,
+                    rent_epoch: a.rent_epoch,
+                },
+            ));
+        }
+
+        let tx = Transaction {
+            signers,
+            all_accounts_sorted,
+            instruction,
+            siblings: ixs,
+            instruction_index: self.instruction_index,
+            clock_override: self.clock.map(|c| (c.slot, c.unix_timestamp)),
+        };
+        Ok((tx, overrides))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzer_libafl::{FuzzConfig, FuzzTarget};
+
+    fn target() -> FuzzTarget {
+        FuzzTarget::setup(&FuzzConfig::default()).unwrap()
+    }
+
+    const INPUTS: &[&[u8]] = &[
+        &[2, 3, 0, 1, 2, 3, 0x10, 0x22, 0x80, 0xFF, 0x7F, 0x01],
+        &[
+            5, 0x97, 0x2c, 9, 8, 7, 6, 5, 0x01, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+            0xe0, 0x41,
+        ],
+        &[1],
+    ];
+
+    #[test]
+    fn json_round_trip() {
+        let mut t = target();
+        for input in INPUTS {
+            let exec = t.execute(input);
+            let file = TxFile::from_exec(&exec, t.roles(), Some(input));
+            let json = file.to_json();
+            let back = TxFile::from_json(json.as_bytes()).unwrap().unwrap();
+            assert_eq!(back.to_json(), json);
+            assert_eq!(back.raw.as_deref(), Some(hex(input).as_str()));
+        }
+        // JSON khác / input raw không phải typed file
+        assert!(TxFile::from_json(b"{\"format\": \"other\"}").is_none());
+        assert!(TxFile::from_json(INPUTS[0]).is_none());
+    }
+
+    #[test]
+    fn legacy_conversion_replays_same_tx() {
+        let mut t = target();
+        for input in INPUTS {
+            let exec = t.execute(input);
+            let file = TxFile::from_exec(&exec, t.roles(), Some(input));
+            let (mut tx, overrides) = file.to_transaction(t.roles()).unwrap();
+            // typed file luôn mang clock của lần chạy gốc
+            assert_eq!(
+                tx.clock_override,
+                Some((exec.clock.slot, exec.clock.unix_timestamp))
+            );
+            tx.clock_override = exec.tx.clock_override;
+            assert_eq!(format!("{tx:?}"), format!("{:?}", exec.tx));
+
+            let typed = t.execute_file(file.to_json().as_bytes()).unwrap();
+            assert_eq!(typed.coverage.hits, exec.coverage.hits);
+            assert_eq!(typed.signals.class(), exec.signals.class());
+            assert_eq!(typed.signals.details, exec.signals.details);
+            assert_eq!(typed.compute_units, exec.compute_units);
+            assert!(!overrides.is_empty());
+        }
+    }
+
+    #[test]
+    fn ops_version_is_recorded() {
+        use crate::oracles::VmEvent;
+        // 0xe0 = token op (version 1) / IntegerOp (version 0)
+        let input: &[u8] = &[2, 3, 0, 1, 2, 0xe0, 0x01];
+        let mut legacy = FuzzTarget::setup(&FuzzConfig {
+            ops_version: Some(0),
+            ..FuzzConfig::default()
+        })
+        .unwrap();
+        let exec = legacy.execute(input);
+        assert!(exec
+            .events
+            .iter()
+            .any(|ev| matches!(ev.event, VmEvent::IntegerOp { .. })));
+        let file = TxFile::from_exec(&exec, legacy.roles(), Some(input));
+        assert_eq!(file.ops_version, 0);
+
+        // target mặc định chạy typed file theo version trong file
+        let mut t = target();
+        let typed = t.execute_file(file.to_json().as_bytes()).unwrap();
+        assert_eq!(typed.ops_version, 0);
+        assert_eq!(typed.events.len(), exec.events.len());
+        assert_eq!(t.vm.ops_version, OPS_VERSION);
+
+        // file không có field: extended op
+        let json = file.to_json().replace("\"ops_version\": 0,", "");
+        let old = TxFile::from_json(json.as_bytes()).unwrap().unwrap();
+        assert_eq!(old.ops_version, 1);
+    }
+
+    #[test]
+    fn version_errors() {
+        let mut t = target();
+        let exec = t.execute(INPUTS[0]);
+        let mut file = TxFile::from_exec(&exec, t.roles(), None);
+        let err = |file: &TxFile| {
+            TxFile::from_json(file.to_json().as_bytes())
+                .unwrap()
+                .unwrap_err()
+                .to_string()
+        };
+
+        file.version = TX_FILE_VERSION + 1;
+        assert!(
+            err(&file).contains("newer than supported"),
+            "{}",
+            err(&file)
+        );
+        file.version = 0;
+        assert!(
+            err(&file).contains("unknown or invalid tx file version 0"),
+            "{}",
+            err(&file)
+        );
+
+        let json = file.to_json().replace("\"version\": 0", "\"version\": -1");
+        let e = TxFile::from_json(json.as_bytes()).unwrap().unwrap_err();
+        assert!(e.to_string().contains("without a valid version"));
+    }
+}
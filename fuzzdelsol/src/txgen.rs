@@ -1,17 +1,87 @@
 use crate::emulator::BlockchainEmulator;
 use crate::types::{InstrAccountMeta, Instruction, Transaction};
+use solana_sdk::ed25519_instruction::{
+    DATA_START, PUBKEY_SERIALIZED_SIZE, SIGNATURE_SERIALIZED_SIZE,
+};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{ed25519_program, sysvar};
 use std::collections::BTreeSet;
 
+/// Data của instruction ed25519 program (1 chữ ký, pubkey/signature/message nằm ngay trong instruction)
+pub fn ed25519_verify_data(pubkey: &Pubkey, message: &[u8]) -> Vec<u8> {
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + PUBKEY_SERIALIZED_SIZE;
+    let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE;
+
+    let mut data = vec![1u8, 0];
+    // Ed25519SignatureOffsets, u16::MAX = instruction hiện tại
+    for v in [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_data_offset as u16,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(&[0u8; SIGNATURE_SERIALIZED_SIZE]); // TraceVM không verify chữ ký
+    data.extend_from_slice(message);
+    data
+}
+
+/// signer mask bit7 (không dùng trước khi có extension): header có extension sau indices.
+/// Không dùng bit của mode byte: mọi bit mode đã đổi pattern writable từ trước
+const EXT_BIT: u8 = 0x80;
+/// Field của extension theo thứ tự (flag trong byte flags, độ dài):
+/// clock (slot u64 LE + unix_timestamp i64 LE), mask lật writable của meta ở vị trí 0..8
+const EXT_CLOCK: u8 = 0x01;
+const EXT_WRITABLE: u8 = 0x02;
+const EXT_FIELDS: [(u8, usize); 2] = [(EXT_CLOCK, 16), (EXT_WRITABLE, 1)];
+
+/// Offset (trong input) của từng field có mặt trong extension bắt đầu ở `at`;
+/// byte thiếu coi là 0. Trả về (offset từng field, offset hết extension)
+fn ext_fields(bytes: &[u8], at: usize) -> ([Option<usize>; EXT_FIELDS.len()], usize) {
+    let flags = bytes.get(at).copied().unwrap_or(0);
+    let mut offsets = [None; EXT_FIELDS.len()];
+    let mut off = at + 1;
+    for (n, &(flag, len)) in EXT_FIELDS.iter().enumerate() {
+        if (flags & flag) != 0 {
+            offsets[n] = Some(off);
+            off += len;
+        }
+    }
+    (offsets, off)
+}
+
+/// Mẫu số chọn malicious pool: index byte `b` lấy từ malicious pool khi `b % mal_mod(mode) == 0`
+pub fn mal_mod(mode: u8) -> usize {
+    match (mode >> 6) & 0x3 {
+        0 => 8,
+        1 => 4,
+        _ => 3, // ~1/3
+    }
+}
+
 pub struct TxGenerator;
 
 impl TxGenerator {
     pub fn from_bytes(bytes: &[u8], emu: &BlockchainEmulator, program_id: Pubkey) -> Transaction {
         // Layout (lite):
         // [0] = n_accounts (1..=8)
-        // [1] = signer mask (bit0=attacker, bit1=user)
-        // [2] = mode byte (controls benign/malicious ratio + writable ratio)
+        // [1] = signer mask (bit0=attacker, bit1=user,
+        //       bit2 = thêm sysvar Clock + Rent (read-only), bit3 = thay bằng look-alike của attacker,
+        //       bit4 = thêm sibling "ed25519 verify" do attacker ký + meta Instructions sysvar,
+        //       bit5 = sibling đứng sau instruction chính, bit6 = sibling gọi program giả của attacker,
+        //       bit7 = có extension)
+        // [2] = mode byte (controls benign/malicious ratio + writable ratio;
+        //       bit2 = lặp lại một account meta, cả 2 writable)
         // next n bytes = indices
+        // [signer bit7] extension: 1 byte flags, rồi các field có flag bật theo thứ tự:
+        //       bit0 = 16 bytes clock: slot (u64 LE) + unix_timestamp (i64 LE)
+        //       bit1 = 1 byte mask: bit p lật writable của account meta thứ p
         // rest = ix.data
 
         let mut idx = 0usize;
@@ -30,11 +100,7 @@ impl TxGenerator {
 
         // Ratios:
         // malicious_ratio in {1/8, 1/4, 3/8} depending on mode bits
-        let mal_mod = match (mode >> 6) & 0x3 {
-            0 => 8,
-            1 => 4,
-            _ => 3, // ~1/3
-        };
+        let mal_mod = mal_mod(mode);
 
         // writable_ratio: 1/4 or 1/3
         let writable_mod = if (mode & 0x20) != 0 { 3 } else { 4 };
@@ -45,7 +111,7 @@ impl TxGenerator {
         let mut benign_pool: Vec<Pubkey> = Vec::new();
         let mut malicious_pool: Vec<Pubkey> = Vec::new();
 
-        for &k in &emu.selectable_accounts {
+        for &k in emu.pool_accounts() {
             if k == emu.attacker || k == emu.user {
                 continue;
             }
@@ -78,13 +144,31 @@ impl TxGenerator {
             let b = bytes.get(idx).copied().unwrap_or((j as u8).wrapping_mul(17));
             idx += 1;
 
-            let take_malicious = (b as usize % mal_mod) == 0;
+            let take_malicious = (b as usize).is_multiple_of(mal_mod);
             let pool = if take_malicious { &malicious_pool } else { &benign_pool };
 
             let ai = (b as usize) % pool.len();
             chosen.push(pool[ai]);
         }
 
+        let mut clock_override = None;
+        let mut writable_flip = 0u8;
+        if (signer_mask & EXT_BIT) != 0 {
+            let (fields, end) = ext_fields(bytes, idx);
+            let at = |k: usize| bytes.get(k).copied().unwrap_or(0);
+            if let Some(off) = fields[0] {
+                let t: Vec<u8> = (off..off + 16).map(at).collect();
+                clock_override = Some((
+                    u64::from_le_bytes(t[..8].try_into().unwrap()),
+                    i64::from_le_bytes(t[8..].try_into().unwrap()),
+                ));
+            }
+            if let Some(off) = fields[1] {
+                writable_flip = at(off);
+            }
+            idx = end;
+        }
+
         // ensure attacker + user present (but not too aggressively)
         // always include user; include attacker only if bit0 set OR every ~4 cases
         chosen.push(emu.user);
@@ -92,7 +176,7 @@ impl TxGenerator {
             chosen.push(emu.attacker);
         }
 
-        // dedup + sort
+        // dedup + sort (danh sách key duy nhất; meta trùng thêm ở dưới)
         chosen.sort();
         chosen.dedup();
 
@@ -108,16 +192,20 @@ impl TxGenerator {
         // account metas:
         // - writable NOT for all (reduces MSC/MOC spam)
         // - keep attacker/user usually writable (realistic)
-        let accounts = chosen
+        let mut accounts = chosen
             .iter()
             .enumerate()
             .map(|(pos, k)| {
-                let mut is_writable = ((pos + (mode as usize)) % writable_mod) == 0;
+                let mut is_writable = (pos + mode as usize).is_multiple_of(writable_mod);
 
                 // attacker/user more likely writable
                 if *k == emu.attacker || *k == emu.user {
                     is_writable = true;
                 }
+                // extension: lật riêng từng meta
+                if pos < 8 && (writable_flip >> pos) & 1 != 0 {
+                    is_writable = !is_writable;
+                }
 
                 InstrAccountMeta {
                     pubkey: *k,
@@ -127,6 +215,59 @@ impl TxGenerator {
             })
             .collect::<Vec<_>>();
 
+        // duplicate meta: cùng account ở 2 vị trí (serializer ghi dup marker)
+        if (mode & 0x04) != 0 {
+            let j = n_raw as usize % accounts.len();
+            accounts[j].is_writable = true;
+            let dup = accounts[j].clone();
+            accounts.push(dup);
+        }
+
+        // sysvar accounts (hoặc look-alike)
+        if (signer_mask & 4) != 0 {
+            let keys = if (signer_mask & 8) != 0 {
+                [emu.spoof_clock, emu.spoof_rent]
+            } else {
+                [sysvar::clock::id(), sysvar::rent::id()]
+            };
+            for k in keys {
+                accounts.push(InstrAccountMeta {
+                    pubkey: k,
+                    is_signer: false,
+                    is_writable: false,
+                });
+                chosen.push(k);
+            }
+            chosen.sort();
+        }
+
+        // sibling instruction: attacker tự ký một ed25519 verify (hợp lệ) với key của mình
+        let mut siblings = Vec::new();
+        let mut instruction_index = 0;
+        if (signer_mask & 0x10) != 0 {
+            accounts.push(InstrAccountMeta {
+                pubkey: sysvar::instructions::id(),
+                is_signer: false,
+                is_writable: false,
+            });
+            chosen.push(sysvar::instructions::id());
+            chosen.sort();
+
+            let verifier = if (signer_mask & 0x40) != 0 {
+                emu.fake_verifier
+            } else {
+                ed25519_program::id()
+            };
+            siblings.push(Instruction {
+                program_id: verifier,
+                accounts: vec![],
+                data: ed25519_verify_data(&emu.attacker, b"fuzzdelsol"),
+            });
+            if (signer_mask & 0x20) == 0 {
+                instruction_index = 1;
+            }
+        }
+
         let data = bytes.get(idx..).unwrap_or(&[]).to_vec();
 
         let ix = Instruction {
@@ -139,6 +280,220 @@ impl TxGenerator {
             signers,
             all_accounts_sorted: chosen,
             instruction: ix,
+            siblings,
+            instruction_index,
+            clock_override,
+        }
+    }
+
+    /// Độ dài header (n_accounts, signer mask, mode, indices, extension) suy từ byte 0, signer mask
+    /// và byte flags; input ngắn hơn thì phần thiếu lấy giá trị mặc định
+    pub fn header_len(bytes: &[u8]) -> usize {
+        let n_accounts = (bytes.first().copied().unwrap_or(3) % 8).max(1) as usize;
+        let signer_mask = bytes.get(1).copied().unwrap_or(0x1);
+        if (signer_mask & EXT_BIT) != 0 {
+            ext_fields(bytes, 3 + n_accounts).1
+        } else {
+            3 + n_accounts
+        }
+    }
+
+    /// Tách input thành header đầy đủ (byte thiếu được điền mặc định như from_bytes,
+    /// nên tx sinh ra không đổi) và ix data
+    pub fn split_input(bytes: &[u8]) -> (Vec<u8>, &[u8]) {
+        let mut header = vec![
+            bytes.first().copied().unwrap_or(3),
+            bytes.get(1).copied().unwrap_or(0x1),
+            bytes.get(2).copied().unwrap_or(0x22),
+        ];
+        let n_accounts = (header[0] % 8).max(1) as usize;
+        for j in 0..n_accounts {
+            header.push(
+                bytes
+                    .get(3 + j)
+                    .copied()
+                    .unwrap_or((j as u8).wrapping_mul(17)),
+            );
         }
+        let len = Self::header_len(bytes);
+        header.extend((header.len()..len).map(|k| bytes.get(k).copied().unwrap_or(0)));
+        (header, bytes.get(len..).unwrap_or(&[]))
+    }
+
+    /// Offset của field `flag` trong extension của header đầy đủ (từ split_input);
+    /// chưa có extension / field thì thêm vào với giá trị 0 (tx sinh ra không đổi)
+    fn ext_field(header: &mut Vec<u8>, flag: u8) -> usize {
+        let at = 3 + (header[0] % 8).max(1) as usize;
+        if (header[1] & EXT_BIT) == 0 {
+            header[1] |= EXT_BIT;
+            header.push(0);
+        }
+        let n = EXT_FIELDS.iter().position(|&(f, _)| f == flag).unwrap();
+        if (header[at] & flag) == 0 {
+            header[at] |= flag;
+            let off = ext_fields(header, at).0[n].unwrap();
+            header.splice(off..off, std::iter::repeat_n(0, EXT_FIELDS[n].1));
+        }
+        ext_fields(header, at).0[n].unwrap()
+    }
+
+    /// Ghi clock vào input (bật extension + field clock, thêm/ghi đè); header thiếu
+    /// được điền giá trị mặc định nên tx sinh ra giống hệt input gốc
+    pub fn embed_clock(bytes: &[u8], slot: u64, unix_timestamp: i64) -> Vec<u8> {
+        let (mut out, data) = Self::split_input(bytes);
+        let t = Self::ext_field(&mut out, EXT_CLOCK);
+        out[t..t + 8].copy_from_slice(&slot.to_le_bytes());
+        out[t + 8..t + 16].copy_from_slice(&unix_timestamp.to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    /// Lật writable của account meta thứ `pos` (< 8) trong header đầy đủ, các meta khác giữ nguyên
+    pub fn flip_writable(header: &mut Vec<u8>, pos: usize) {
+        let off = Self::ext_field(header, EXT_WRITABLE);
+        header[off] ^= 1 << pos;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// tx sinh từ input đã embed clock = tx gốc + clock_override
+    fn assert_same_tx(emu: &BlockchainEmulator, input: &[u8], slot: u64, unix_timestamp: i64) {
+        let program_id = Pubkey::new_unique();
+        let orig = TxGenerator::from_bytes(input, emu, program_id);
+        let embedded = TxGenerator::embed_clock(input, slot, unix_timestamp);
+        let mut tx = TxGenerator::from_bytes(&embedded, emu, program_id);
+        assert_eq!(tx.clock_override, Some((slot, unix_timestamp)));
+        tx.clock_override = orig.clock_override;
+        assert_eq!(format!("{tx:?}"), format!("{orig:?}"));
+    }
+
+    #[test]
+    fn split_input_fills_header() {
+        let emu = BlockchainEmulator::new();
+        let program_id = Pubkey::new_unique();
+        // n_accounts = 2, có clock trailer
+        let input = [
+            2, 0x83, 0x28, 5, 9, 0x01, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 0xaa,
+            0xbb,
+        ];
+        let (header, data) = TxGenerator::split_input(&input);
+        assert_eq!(header.len(), TxGenerator::header_len(&input));
+        assert_eq!(header, input[..header.len()]);
+        assert_eq!(data, &[0xaa, 0xbb]);
+
+        // header thiếu: điền mặc định, tx không đổi
+        for short in [&[][..], &[4], &[2, 0x83, 0x2c, 5]] {
+            let (header, data) = TxGenerator::split_input(short);
+            assert_eq!(header.len(), TxGenerator::header_len(short));
+            assert!(data.is_empty());
+            let orig = TxGenerator::from_bytes(short, &emu, program_id);
+            let full = TxGenerator::from_bytes(&header, &emu, program_id);
+            assert_eq!(format!("{full:?}"), format!("{orig:?}"));
+        }
+    }
+
+    #[test]
+    fn embed_clock_keeps_tx() {
+        let emu = BlockchainEmulator::new();
+        assert_same_tx(
+            &emu,
+            &[2, 3, 0x24, 5, 9, 0xde, 0xad, 0xbe, 0xef],
+            1_000,
+            1_700_000_000,
+        );
+        // header thiếu -> điền mặc định
+        assert_same_tx(&emu, &[], 7, -1);
+        assert_same_tx(&emu, &[5, 1], 7, 60);
+    }
+
+    #[test]
+    fn embed_clock_overwrites_trailer() {
+        let emu = BlockchainEmulator::new();
+        let once = TxGenerator::embed_clock(&[1, 1, 0x22, 4, 0xaa, 0xbb], 10, 20);
+        let twice = TxGenerator::embed_clock(&once, 30, 40);
+        assert_eq!(once.len(), twice.len());
+        assert_eq!(twice[1] & EXT_BIT, EXT_BIT);
+        assert_eq!(&twice[twice.len() - 2..], &[0xaa, 0xbb]);
+        assert_same_tx(&emu, &once, 30, 40);
+    }
+
+    #[test]
+    fn flip_writable_toggles_one_meta() {
+        let emu = BlockchainEmulator::new();
+        let program_id = Pubkey::new_unique();
+        let input = [4, 0x03, 0x22, 5, 9, 13, 17, 0xaa];
+        let orig = TxGenerator::from_bytes(&input, &emu, program_id);
+        let writable = |tx: &Transaction| -> Vec<bool> {
+            tx.instruction
+                .accounts
+                .iter()
+                .map(|m| m.is_writable)
+                .collect()
+        };
+
+        for pos in 0..orig.instruction.accounts.len() {
+            let (mut header, data) = TxGenerator::split_input(&input);
+            TxGenerator::flip_writable(&mut header, pos);
+            header.extend_from_slice(data);
+            let tx = TxGenerator::from_bytes(&header, &emu, program_id);
+            let mut expected = writable(&orig);
+            expected[pos] = !expected[pos];
+            assert_eq!(writable(&tx), expected);
+            assert_eq!(tx.instruction.data, orig.instruction.data);
+            assert_eq!(tx.clock_override, None);
+
+            // clock thêm sau vẫn giữ mask (field clock chèn trước mask)
+            let clocked = TxGenerator::embed_clock(&header, 5, 6);
+            let tx = TxGenerator::from_bytes(&clocked, &emu, program_id);
+            assert_eq!(writable(&tx), expected);
+            assert_eq!(tx.clock_override, Some((5, 6)));
+
+            // lật lại -> như cũ
+            let (mut back, data) = TxGenerator::split_input(&clocked);
+            TxGenerator::flip_writable(&mut back, pos);
+            back.extend_from_slice(data);
+            let tx = TxGenerator::from_bytes(&back, &emu, program_id);
+            assert_eq!(writable(&tx), writable(&orig));
+        }
+    }
+
+    #[test]
+    fn dictionary_keys_do_not_shift_base_pool() {
+        let mut emu = BlockchainEmulator::new();
+        let program_id = Pubkey::new_unique();
+        let inputs: Vec<Vec<u8>> = (0..=255u8)
+            .map(|b| vec![7, 1, 0x22, b, b ^ 0x55, 3, 4, 5, 6, 7])
+            .collect();
+        let before: Vec<String> = inputs
+            .iter()
+            .map(|i| format!("{:?}", TxGenerator::from_bytes(i, &emu, program_id)))
+            .collect();
+
+        // key chẵn (benign pool) và lẻ (malicious pool)
+        let keys: Vec<Pubkey> = (0..2u8)
+            .map(|p| Pubkey::new_from_array([0x10 + p; 32]))
+            .collect();
+        emu.add_keys(&keys);
+        assert_eq!(emu.dictionary_accounts, keys);
+
+        // ops version 0: pool gốc giữ nguyên -> cùng input ra cùng tx
+        emu.dictionary_in_pool = false;
+        for (i, b) in inputs.iter().zip(&before) {
+            assert_eq!(
+                &format!("{:?}", TxGenerator::from_bytes(i, &emu, program_id)),
+                b
+            );
+        }
+
+        // bật: key dictionary chọn được
+        emu.dictionary_in_pool = true;
+        let picked: BTreeSet<Pubkey> = inputs
+            .iter()
+            .flat_map(|i| TxGenerator::from_bytes(i, &emu, program_id).all_accounts_sorted)
+            .collect();
+        assert!(keys.iter().all(|k| picked.contains(k)));
     }
 }
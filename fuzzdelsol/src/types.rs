@@ -1,3 +1,12 @@
+use crate::debuginfo::SourceLoc;
+use crate::diff::{self, AccountDiff};
+use crate::export::SolanaExport;
+use crate::oracles::FrameEvent;
+use crate::triage::Bucket;
+use crate::txfile::TxFile;
+use serde::{Deserialize, Serialize};
+use solana_rbpf::ebpf::{MM_HEAP_START, MM_INPUT_START};
+use solana_sdk::clock::Clock;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -7,9 +16,8 @@ pub struct Account {
     pub owner: Pubkey,
     pub lamports: u64,
     pub data: Vec<u8>,
-    pub is_signer: bool,
-    pub is_writable: bool,
     pub is_executable: bool,
+    pub rent_epoch: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -46,6 +54,20 @@ pub struct Transaction {
     pub signers: BTreeSet<Pubkey>,
     pub all_accounts_sorted: Vec<Pubkey>,
     pub instruction: Instruction,
+    /// các instruction khác trong tx (theo thứ tự), `instruction` nằm ở vị trí `instruction_index`
+    pub siblings: Vec<Instruction>,
+    pub instruction_index: usize,
+    /// (slot, unix_timestamp) do input chỉ định, None = theo clock schedule
+    pub clock_override: Option<(u64, i64)>,
+}
+
+impl Transaction {
+    /// Toàn bộ instruction của tx theo thứ tự thực thi
+    pub fn instructions(&self) -> Vec<&Instruction> {
+        let mut out: Vec<&Instruction> = self.siblings.iter().collect();
+        out.insert(self.instruction_index.min(out.len()), &self.instruction);
+        out
+    }
 }
 
 /// Coverage map: edge = (src + dst) % size
@@ -79,19 +101,132 @@ impl CoverageMap {
     }
 }
 
+/// Taint label: các offset byte trong input region (top-level) đã chảy vào một giá trị
+pub type TaintLabel = BTreeSet<usize>;
+
+/// r0..r10
+pub const NUM_REGS: usize = 11;
+
+/// Shadow state của một frame (register/stack/heap là riêng mỗi invocation)
+#[derive(Clone, Debug, Default)]
+pub struct FrameTaint {
+    regs: [TaintLabel; NUM_REGS],
+    stack: BTreeMap<u64, TaintLabel>,
+    heap: BTreeMap<u64, TaintLabel>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct TaintEngine {
-    // keep for future rbpf hook; currently unused in TraceVM -> allow dead_code
-    #[allow(dead_code)]
-    pub reg_taint: [bool; 16],
-    #[allow(dead_code)]
-    pub heap_taint: bool,
+    pub reg_taint: [TaintLabel; NUM_REGS],
+    // shadow memory theo vaddr, mỗi byte một label
+    pub stack_taint: BTreeMap<u64, TaintLabel>,
+    pub heap_taint: BTreeMap<u64, TaintLabel>,
+    // byte input region bị program ghi đè (label mới thay cho source)
+    pub input_written: BTreeMap<u64, TaintLabel>,
+    // source: vaddr input region của instruction data + data account attacker-owned
+    pub input_sources: BTreeSet<u64>,
 
-    pub input_taint: bool,
     pub data_acc_taint: bool,
+
+    // key đã được so với hằng số (check key) -> hết taint
+    pub checked_keys: BTreeSet<Pubkey>,
 }
 
-#[derive(Clone, Debug)]
+impl TaintEngine {
+    pub fn add_source(&mut self, addr: u64, len: usize) {
+        self.input_sources.extend((0..len as u64).map(|k| addr + k));
+    }
+
+    fn mem_label(&self, addr: u64) -> TaintLabel {
+        let shadow = if addr >= MM_INPUT_START {
+            if let Some(l) = self.input_written.get(&addr) {
+                return l.clone();
+            }
+            if self.input_sources.contains(&addr) {
+                return BTreeSet::from([(addr - MM_INPUT_START) as usize]);
+            }
+            return TaintLabel::new();
+        } else if addr >= MM_HEAP_START {
+            &self.heap_taint
+        } else {
+            &self.stack_taint
+        };
+        shadow.get(&addr).cloned().unwrap_or_default()
+    }
+
+    /// ldx: reg = hợp label của `len` byte tại `addr`
+    pub fn load(&mut self, reg: usize, addr: u64, len: usize) {
+        let mut l = TaintLabel::new();
+        for k in 0..len as u64 {
+            l.extend(self.mem_label(addr + k));
+        }
+        self.reg_taint[reg] = l;
+    }
+
+    /// stx: mỗi byte đích nhận label của reg (label rỗng = xoá taint)
+    pub fn store(&mut self, addr: u64, len: usize, reg: usize) {
+        let l = self.reg_taint[reg].clone();
+        let shadow = if addr >= MM_INPUT_START {
+            &mut self.input_written
+        } else if addr >= MM_HEAP_START {
+            &mut self.heap_taint
+        } else {
+            &mut self.stack_taint
+        };
+        for k in 0..len as u64 {
+            if l.is_empty() && addr < MM_INPUT_START {
+                shadow.remove(&(addr + k));
+            } else {
+                shadow.insert(addr + k, l.clone());
+            }
+        }
+    }
+
+    pub fn set(&mut self, reg: usize, label: TaintLabel) {
+        self.reg_taint[reg] = label;
+    }
+
+    /// mov imm
+    pub fn clear(&mut self, reg: usize) {
+        self.reg_taint[reg].clear();
+    }
+
+    /// alu dst, src: dst phụ thuộc cả 2 toán hạng
+    pub fn alu(&mut self, dst: usize, src: usize) {
+        let l = self.reg_taint[src].clone();
+        self.reg_taint[dst].extend(l);
+    }
+
+    pub fn label(&self, reg: usize) -> &TaintLabel {
+        &self.reg_taint[reg]
+    }
+
+    /// Vào frame mới (CPI): register/stack/heap của caller được cất lại
+    pub fn enter_frame(&mut self) -> FrameTaint {
+        FrameTaint {
+            regs: std::mem::take(&mut self.reg_taint),
+            stack: std::mem::take(&mut self.stack_taint),
+            heap: std::mem::take(&mut self.heap_taint),
+        }
+    }
+
+    pub fn exit_frame(&mut self, saved: FrameTaint) {
+        self.reg_taint = saved.regs;
+        self.stack_taint = saved.stack;
+        self.heap_taint = saved.heap;
+    }
+
+    pub fn sanitize_key(&mut self, key: Pubkey) {
+        self.checked_keys.insert(key);
+    }
+
+    /// Key đã được so với hằng số (không còn attacker-controlled)
+    pub fn key_checked(&self, key: &Pubkey) -> bool {
+        self.checked_keys.contains(key)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct OracleSignals {
     pub msc: bool,
     pub moc: bool,
@@ -99,29 +234,105 @@ pub struct OracleSignals {
     pub mkc: bool,
     pub ib: bool,
     pub lamports_theft: bool,
+    pub token_theft: bool,
+    pub supply_violation: bool,
+    pub token_authority_hijack: bool,
+    pub type_cosplay: bool,
+    pub dup_mut: bool,
+    pub reinit: bool,
+    pub revival: bool,
+    pub rent: bool,
+    pub sysvar_spoof: bool,
+    pub introspection: bool,
+    pub dos: bool,
+
+    // human-readable evidence from oracles (goes into the report)
+    pub details: Vec<String>,
+    // account chính mà oracle nêu, theo class (cho triage)
+    pub flagged: BTreeMap<&'static str, BTreeSet<Pubkey>>,
 }
 
+/// Mọi class của oracle, theo thứ tự ưu tiên khi nhiều oracle cùng bật (class() lấy cái đầu)
+pub const CLASSES: [&str; 17] = [
+    "LAMPORTS_THEFT",
+    "TOKEN_THEFT",
+    "SUPPLY_VIOLATION",
+    "TOKEN_AUTHORITY_HIJACK",
+    "TYPE_COSPLAY",
+    "DUP_MUT",
+    "REINIT",
+    "REVIVAL",
+    "RENT",
+    "SYSVAR_SPOOF",
+    "INTROSPECTION",
+    "DOS",
+    "MOC",
+    "MSC",
+    "ACPI",
+    "MKC",
+    "IB",
+];
+
 impl OracleSignals {
+    /// Ghi account chính oracle `class` chỉ ra (account bị rút, key không check, CPI target, mint...)
+    pub fn flag(&mut self, class: &'static str, key: Pubkey) {
+        self.flagged.entry(class).or_default().insert(key);
+    }
+
     pub fn any(&self) -> bool {
-        self.msc || self.moc || self.acpi || self.mkc || self.ib || self.lamports_theft
+        self.msc
+            || self.moc
+            || self.acpi
+            || self.mkc
+            || self.ib
+            || self.lamports_theft
+            || self.token_theft
+            || self.supply_violation
+            || self.token_authority_hijack
+            || self.type_cosplay
+            || self.dup_mut
+            || self.reinit
+            || self.revival
+            || self.rent
+            || self.sysvar_spoof
+            || self.introspection
+            || self.dos
+    }
+
+    /// Cờ của từng class, cùng thứ tự với [`CLASSES`]
+    fn flags(&self) -> [bool; CLASSES.len()] {
+        [
+            self.lamports_theft,
+            self.token_theft,
+            self.supply_violation,
+            self.token_authority_hijack,
+            self.type_cosplay,
+            self.dup_mut,
+            self.reinit,
+            self.revival,
+            self.rent,
+            self.sysvar_spoof,
+            self.introspection,
+            self.dos,
+            self.moc,
+            self.msc,
+            self.acpi,
+            self.mkc,
+            self.ib,
+        ]
+    }
+
+    /// Mọi oracle đã bật (class() chỉ là oracle ưu tiên cao nhất)
+    pub fn triggered(&self) -> Vec<&'static str> {
+        CLASSES
+            .into_iter()
+            .zip(self.flags())
+            .filter_map(|(name, on)| on.then_some(name))
+            .collect()
     }
 
     pub fn class(&self) -> &'static str {
-        if self.lamports_theft {
-            "LAMPORTS_THEFT"
-        } else if self.moc {
-            "MOC"
-        } else if self.msc {
-            "MSC"
-        } else if self.acpi {
-            "ACPI"
-        } else if self.mkc {
-            "MKC"
-        } else if self.ib {
-            "IB"
-        } else {
-            "NONE"
-        }
+        self.triggered().first().copied().unwrap_or("NONE")
     }
 }
 
@@ -144,15 +355,91 @@ pub struct ExecResult {
     pub pre_snapshot: LedgerSnapshot,
     pub post_snapshot: LedgerSnapshot,
     pub trace_summary: String,
+    pub clock: Clock,
+    pub compute_units: u64,
+    pub events: Vec<FrameEvent>,
+    /// encoding trace op đã dùng để chạy instruction data
+    pub ops_version: u8,
 }
 
-#[derive(Clone, Debug)]
+/// Số event VM giữ lại trong report
+const TRACE_EXCERPT_EVENTS: usize = 64;
+
+/// Trích đoạn trace: `depth program event`, tối đa TRACE_EXCERPT_EVENTS event đầu
+pub fn trace_excerpt(events: &[FrameEvent]) -> Vec<String> {
+    let mut out: Vec<String> = events
+        .iter()
+        .take(TRACE_EXCERPT_EVENTS)
+        .map(|ev| format!("[{}] {} {:?}", ev.depth, ev.program_id, ev.event))
+        .collect();
+    if events.len() > TRACE_EXCERPT_EVENTS {
+        out.push(format!(
+            "... {} more events",
+            events.len() - TRACE_EXCERPT_EVENTS
+        ));
+    }
+    out
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct VulnReport {
     pub vuln_class: String,
+    /// root cause (class + signature), fuzzer điền sau evaluate
+    pub bucket: Option<Bucket>,
+    /// mọi oracle đã bật
+    pub oracles: Vec<String>,
     pub tx_payload_hex: String,
-    pub global_state_before: String,
-    pub global_state_after: String,
+    pub details: Vec<String>,
+    pub clock: String,
+    /// account bị đổi (kèm vai trò, field đã decode), fuzzer điền sau evaluate
+    pub account_diffs: Vec<AccountDiff>,
     pub trace_summary: String,
+    pub trace_excerpt: Vec<String>,
+    /// input fuzzer (hex) đã sinh ra tx, fuzzer điền sau evaluate
+    pub input_hex: Option<String>,
+    /// seed RNG của campaign tìm ra finding
+    pub seed: Option<u64>,
+    /// tx đã decode (typed format)
+    pub transaction: Option<TxFile>,
+    /// handler của instruction trong source program (DWARF), nếu có debug info
+    pub instruction: Option<String>,
+    /// file + dòng đầu của handler (mức function)
+    pub location: Option<SourceLoc>,
+    /// tx thật (Message/Transaction base64 + key map), fuzzer điền sau evaluate
+    pub export: Option<SolanaExport>,
+}
+
+impl VulnReport {
+    pub fn to_text(&self) -> String {
+        let mut out = format!(
+            "Vulnerability Class: {}\n\nTX Payload (hex): {}\n\nClock: {}\n\nOracle details:\n{}\n\n=== Account Changes ===\n{}\n\nTrace:\n{}\n",
+            self.vuln_class,
+            self.tx_payload_hex,
+            self.clock,
+            self.details.join("\n"),
+            diff::render(&self.account_diffs),
+            self.trace_summary
+        );
+        if let Some(b) = &self.bucket {
+            out.push_str(&format!("\nBucket: {}\n{}\n", b.id, b.signature.join("\n")));
+        }
+        if let Some(l) = &self.location {
+            let ix = self.instruction.as_deref().unwrap_or("?");
+            let at = l.line.map(|n| format!("{}:{n}", l.file)).unwrap_or_else(|| l.file.clone());
+            out.push_str(&format!(
+                "\nLocation: {at}, function `{}` (instruction `{ix}`; function-level: the line is where the function starts, TraceVM has no real PC)\n",
+                l.function
+            ));
+        }
+        if let Some(e) = &self.export {
+            out.push_str(&format!("\n=== Solana Transaction ===\n{}\n", e.to_text()));
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
 }
 
 pub fn hex(bytes: &[u8]) -> String {
@@ -165,19 +452,83 @@ pub fn hex(bytes: &[u8]) -> String {
     out
 }
 
-pub fn snapshot_to_string(s: &LedgerSnapshot) -> String {
-    let mut lines = Vec::new();
-    for (k, a) in &s.accounts {
-        lines.push(format!(
-            "{} owner={} lamports={} data_len={} signer={} writable={} exec={}",
-            k,
-            a.owner,
-            a.lamports,
-            a.data.len(),
-            a.is_signer,
-            a.is_writable,
-            a.is_executable
-        ));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_rbpf::ebpf::MM_STACK_START;
+
+    fn label(offsets: &[usize]) -> TaintLabel {
+        offsets.iter().copied().collect()
+    }
+
+    #[test]
+    fn class_follows_priority() {
+        let mut s = OracleSignals::default();
+        assert_eq!(s.class(), "NONE");
+        s.ib = true;
+        s.moc = true;
+        s.token_theft = true;
+        assert_eq!(s.triggered(), ["TOKEN_THEFT", "MOC", "IB"]);
+        assert_eq!(s.class(), "TOKEN_THEFT");
+    }
+
+    #[test]
+    fn taint_load_from_input() {
+        let mut t = TaintEngine::default();
+        t.add_source(MM_INPUT_START + 8, 4);
+        t.load(1, MM_INPUT_START + 6, 4);
+        // chỉ byte thuộc source mang label, theo offset trong input region
+        assert_eq!(t.label(1), &label(&[8, 9]));
+        t.load(2, MM_INPUT_START, 4);
+        assert!(t.label(2).is_empty());
+    }
+
+    #[test]
+    fn taint_store_and_reload() {
+        let mut t = TaintEngine::default();
+        t.add_source(MM_INPUT_START, 8);
+        t.load(1, MM_INPUT_START, 2);
+        // qua stack và heap
+        t.store(MM_STACK_START + 16, 8, 1);
+        t.load(2, MM_STACK_START + 20, 4);
+        assert_eq!(t.label(2), &label(&[0, 1]));
+        t.store(MM_HEAP_START, 1, 2);
+        t.load(3, MM_HEAP_START, 8);
+        assert_eq!(t.label(3), &label(&[0, 1]));
+        // ghi giá trị sạch -> xoá taint
+        t.clear(4);
+        t.store(MM_STACK_START + 16, 8, 4);
+        t.load(2, MM_STACK_START + 16, 8);
+        assert!(t.label(2).is_empty());
+        // program ghi đè input region: label mới thay cho source
+        t.store(MM_INPUT_START, 1, 4);
+        t.load(5, MM_INPUT_START, 2);
+        assert_eq!(t.label(5), &label(&[1]));
+    }
+
+    #[test]
+    fn taint_alu_merges_and_mov_clears() {
+        let mut t = TaintEngine::default();
+        t.set(1, label(&[3]));
+        t.set(2, label(&[7]));
+        t.alu(1, 2);
+        assert_eq!(t.label(1), &label(&[3, 7]));
+        assert_eq!(t.label(2), &label(&[7]));
+        t.clear(1);
+        assert!(t.label(1).is_empty());
+    }
+
+    #[test]
+    fn taint_frames_are_separate() {
+        let mut t = TaintEngine::default();
+        t.set(1, label(&[0]));
+        t.store(MM_STACK_START, 1, 1);
+        let saved = t.enter_frame();
+        assert!(t.label(1).is_empty());
+        t.load(2, MM_STACK_START, 1);
+        assert!(t.label(2).is_empty());
+        t.exit_frame(saved);
+        t.load(2, MM_STACK_START, 1);
+        assert_eq!(t.label(2), &label(&[0]));
     }
-    lines.join("\n")
 }
@@ -1,28 +1,316 @@
-use crate::oracles::VmEvent;
-use crate::types::{CoverageMap, LedgerSnapshot, TaintEngine, Transaction};
+use crate::compute;
+use crate::emulator::RENT_EXEMPT_RENT_EPOCH;
+use crate::layout::LayoutModel;
+use crate::oracles::{FrameEvent, VmEvent};
+use crate::serialize::{input_layout, InputLayout};
+use crate::token::{Mint, TokenAccount, TOKEN_PROGRAM_ID};
+use crate::types::{
+    Account, CoverageMap, InstrAccountMeta, Instruction, LedgerSnapshot, TaintEngine, TaintLabel,
+    Transaction,
+};
+use solana_rbpf::ebpf::{MM_HEAP_START, MM_INPUT_START, MM_STACK_START};
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::clock::Clock;
+use solana_sdk::ed25519_program;
+use solana_sdk::hash::hashv;
+use solana_sdk::instruction::TRANSACTION_LEVEL_STACK_HEIGHT;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use solana_sdk::system_program;
+use solana_sdk::sysvar::{self, instructions};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Số extended op đã gán cho nhóm `b >> 5 == 7` (sub-op = `b & 0x1f`).
+/// Sub-op chưa gán vẫn chạy IntegerOp + KeyAccess như cũ.
+const N_EXT_OPS: u8 = 20;
+
+/// Version encoding của trace op (instruction data):
+/// - 0: encoding gốc, cả nhóm 5..7 là IntegerOp + KeyAccess
+/// - 1: sub-op `0..N_EXT_OPS` của nhóm 7 là extended op; TxGenerator chọn cả pubkey
+///   hard-code trong ELF (pool dictionary, sau pool gốc -> `index % len` của pool đổi theo ELF)
+///
+/// Cùng một input cho ra trace/tx khác nhau giữa 2 version: corpus raw ghi trước khi có
+/// extended op / dictionary phải chạy với `--ops-version 0`
+pub const OPS_VERSION: u8 = 1;
+
+/// `b` là extended op theo encoding `ops_version`
+pub fn is_ext_op(b: u8, ops_version: u8) -> bool {
+    ops_version >= 1 && (b >> 5) == 7 && (b & 0x1f) < N_EXT_OPS
+}
+
+/// pc của handler xử lý opcode `b`: op mở rộng riêng từng op, còn lại theo nhóm `b >> 5` (bit thấp là flag)
+fn site_pc(b: u8, ops_version: u8) -> u64 {
+    match b >> 5 {
+        0..=4 => (b & 0xe0) as u64,
+        7 if is_ext_op(b, ops_version) => b as u64,
+        _ => 0xa0,
+    }
+}
+
+/// Hằng số mà Cmp so với (magic value kiểu version / amount / flag)
+const CMP_MAGIC: [u64; 4] = [0xdead_beef, 1_000_000, 0xcafe_babe_0000_0001, u64::MAX - 1];
+
+/// u64 LE tại `at`, thiếu byte thì coi là 0
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    for (k, o) in buf.iter_mut().enumerate() {
+        *o = data.get(at + k).copied().unwrap_or(0);
+    }
+    u64::from_le_bytes(buf)
+}
+
+// register dùng trong dataflow của trace op
+const R_LHS: usize = 1;
+const R_RHS: usize = 2;
+const R_ACC: usize = 3;
+const R_INT: usize = 4;
+const R_KEY: usize = 5;
+
+fn stack_slot(n: u8) -> u64 {
+    MM_STACK_START + 8 * n as u64
+}
+
+fn heap_slot(n: u8) -> u64 {
+    MM_HEAP_START + 8 * n as u64
+}
+
+/// Data do attacker kiểm soát: owner không phải program, builtin hay sysvar
+fn attacker_owned(a: &Account, program_id: &Pubkey) -> bool {
+    !a.is_executable
+        && a.owner != *program_id
+        && a.owner != system_program::id()
+        && a.owner != sysvar::id()
+        && a.owner != TOKEN_PROGRAM_ID
+}
+
+/// Key tất định cho account/program mới trong một lần chạy: theo program, frame và vị trí op
+/// (replay cùng input ra cùng key, không phụ thuộc counter toàn cục như `Pubkey::new_unique`)
+fn derived_key(program_id: &Pubkey, tag: &[u8], frame: usize, i: usize) -> Pubkey {
+    let h = hashv(&[
+        program_id.as_ref(),
+        tag,
+        &(frame as u64).to_le_bytes(),
+        &(i as u64).to_le_bytes(),
+    ]);
+    Pubkey::new_from_array(h.to_bytes())
+}
+
+/// Solana max invoke stack height (top-level = 1)
+pub const MAX_INVOKE_DEPTH: usize = 5;
 
 /// RunDelSol-Lite TraceVM
 /// - Không thực thi ELF thật
 /// - Phát event có kiểm soát để objective rate “paper-like”
+/// - CPI tới program executable có trong ledger mở frame callee mô phỏng: không chạy ELF của
+///   callee, CPI data (8 byte sau opcode) được diễn giải như trace ops dưới program id callee,
+///   dùng chung account state với caller
 pub struct TraceVm;
 
+/// Tham số chạy (cố định trong một campaign)
+#[derive(Clone, Debug)]
+pub struct VmConfig {
+    /// CU limit của instruction, so với cost ước lượng
+    pub compute_budget: u64,
+    /// sighash instruction program nhận (Anchor dispatch)
+    pub sighashes: Vec<[u8; 8]>,
+    /// account layout (IDL): offset authority theo discriminator
+    pub layouts: LayoutModel,
+    /// Rent sysvar của emulator (rent-exempt minimum khi tạo account)
+    pub rent: Rent,
+    /// encoding trace op (`OPS_VERSION` hoặc cũ hơn để replay corpus cũ)
+    pub ops_version: u8,
+}
+
 pub struct VmRunOutput {
     pub coverage: CoverageMap,
     pub taint: TaintEngine,
-    pub events: Vec<VmEvent>,
+    pub events: Vec<FrameEvent>,
     pub post_snapshot: LedgerSnapshot,
     pub trace_summary: String,
+    pub compute_units: u64,
+}
+
+/// State dùng chung giữa các frame của một lần chạy
+struct VmState<'a> {
+    coverage: CoverageMap,
+    taint: TaintEngine,
+    events: Vec<FrameEvent>,
+    snap: LedgerSnapshot,
+    frames: usize,
+    max_depth: usize,
+    input_len: usize,
+    layouts: &'a LayoutModel,
+    rent: Rent,
+    // vaddr data của account trong input region top-level (label taint dùng offset top-level)
+    account_data: BTreeMap<Pubkey, u64>,
+    // cost ước lượng (compute::op_cost), dùng chung cho cả CPI như compute meter của runtime
+    compute_units: u64,
+    compute_limit: u64,
+    exhausted: bool,
+    sighashes: Vec<[u8; 8]>,
+    // handler đang chạy = site của event (ổn định, không như pc coverage phụ thuộc đường đi)
+    site: u64,
+    ops_version: u8,
+}
+
+impl VmState<'_> {
+    fn emit(&mut self, program_id: Pubkey, depth: usize, event: VmEvent) {
+        self.events.push(FrameEvent {
+            program_id,
+            depth,
+            pc: self.site,
+            event,
+        });
+    }
+
+    /// Offset authority của account theo layout; account chưa init (không classify được) dùng mặc định
+    fn authority_offset(&self, data: &[u8]) -> Option<usize> {
+        match self.layouts.classify(data) {
+            Some(l) => l.authority_offset,
+            None => Some(DEFAULT_AUTHORITY_OFFSET),
+        }
+    }
+
+    fn authority_of(&self, acct: &Pubkey) -> Option<Pubkey> {
+        let data = &self.snap.accounts.get(acct)?.data;
+        let off = self.authority_offset(data)?;
+        Pubkey::try_from(data.get(off..off + 32)?).ok()
+    }
+
+    /// Cộng cost ước lượng; false (và đánh dấu exhausted) nếu vượt budget
+    fn consume(&mut self, units: u64) -> bool {
+        self.compute_units = self.compute_units.saturating_add(units);
+        if self.compute_units > self.compute_limit {
+            self.exhausted = true;
+        }
+        !self.exhausted
+    }
 }
 
 impl TraceVm {
-    pub fn run(program_id: Pubkey, mut snap: LedgerSnapshot, tx: &Transaction) -> VmRunOutput {
-        let mut coverage = CoverageMap::new(64 * 1024);
-        let mut taint = TaintEngine::default();
-        let mut events: Vec<VmEvent> = Vec::new();
+    pub fn run(
+        program_id: Pubkey,
+        snap: LedgerSnapshot,
+        tx: &Transaction,
+        cfg: &VmConfig,
+    ) -> VmRunOutput {
+        let mut st = VmState {
+            coverage: CoverageMap::new(64 * 1024),
+            taint: TaintEngine::default(),
+            events: Vec::new(),
+            snap: snap.clone(),
+            frames: 0,
+            max_depth: 0,
+            input_len: 0,
+            layouts: &cfg.layouts,
+            rent: cfg.rent,
+            account_data: BTreeMap::new(),
+            compute_units: 0,
+            compute_limit: cfg.compute_budget,
+            exhausted: false,
+            sighashes: cfg.sighashes.clone(),
+            site: 0,
+            ops_version: cfg.ops_version,
+        };
+        Self::run_frame(&mut st, program_id, tx, 1, None);
+
+        // vượt budget (theo cost ước lượng): coi như instruction fail, runtime rollback mọi thay đổi
+        if st.exhausted {
+            st.snap = snap;
+            st.events.clear();
+            // op làm hết budget tuỳ đường đi, không phải site riêng
+            st.site = 0;
+            st.emit(
+                program_id,
+                1,
+                VmEvent::ComputeExhausted {
+                    consumed: st.compute_units,
+                    limit: st.compute_limit,
+                },
+            );
+        }
+
+        let trace_summary = format!(
+            "TraceVM: bytes={} input_region={} edges_hash={:016x} accounts={} signers={} frames={}{} max_depth={} est_cost={}",
+            tx.instruction.data.len(),
+            st.input_len,
+            st.coverage.hash16(),
+            tx.all_accounts_sorted.len(),
+            tx.signers.len(),
+            st.frames,
+            // frame CPI là mô phỏng: không chạy image của callee
+            if st.frames > 1 {
+                " (callee frames modeled, images not executed)"
+            } else {
+                ""
+            },
+            st.max_depth,
+            st.compute_units
+        );
+
+        VmRunOutput {
+            coverage: st.coverage,
+            taint: st.taint,
+            events: st.events,
+            post_snapshot: st.snap,
+            trace_summary,
+            compute_units: st.compute_units,
+        }
+    }
+
+    /// Chạy một frame: `tx` là view của instruction đang chạy (top-level hoặc CPI).
+    /// `ix_labels`: taint của từng byte instruction data (None = top-level, byte là source)
+    fn run_frame(
+        st: &mut VmState,
+        program_id: Pubkey,
+        tx: &Transaction,
+        depth: usize,
+        ix_labels: Option<&[TaintLabel]>,
+    ) {
+        st.frames += 1;
+        st.max_depth = st.max_depth.max(depth);
 
         let data = &tx.instruction.data;
-        taint.input_taint = !data.is_empty();
+
+        // vị trí trong input region của frame (dup meta trỏ về account gốc)
+        let input = input_layout(tx, &st.snap);
+        if depth == 1 {
+            st.input_len = input.len;
+
+            // taint source: instruction data + data của account attacker-owned
+            st.taint
+                .add_source(MM_INPUT_START + input.ix_data_offset as u64, data.len());
+            for (acc, m) in input.accounts.iter().zip(&tx.instruction.accounts) {
+                let vaddr = MM_INPUT_START + acc.data_offset as u64;
+                st.account_data.insert(m.pubkey, vaddr);
+                if acc.dup_of.is_none()
+                    && st
+                        .snap
+                        .accounts
+                        .get(&m.pubkey)
+                        .is_some_and(|a| attacker_owned(a, &program_id))
+                {
+                    st.taint.add_source(vaddr, acc.data_len);
+                }
+            }
+        }
+        let ix_labels: Vec<TaintLabel> = match ix_labels {
+            Some(l) => l.to_vec(),
+            None => (0..data.len())
+                .map(|k| TaintLabel::from([input.ix_data_offset + k]))
+                .collect(),
+        };
+        // label của `len` byte instruction data từ vị trí `from` (toán hạng đọc từ input)
+        let ix_operand = |from: usize, len: usize| -> TaintLabel {
+            ix_labels
+                .iter()
+                .skip(from)
+                .take(len)
+                .flatten()
+                .copied()
+                .collect()
+        };
 
         // pick any account from tx list
         let pick_acct = |b: u8, tx: &Transaction| -> Pubkey {
@@ -50,25 +338,76 @@ impl TraceVm {
         // integer bug helper
         let mut pending_big_attacker_gain = false;
 
-        let mut pc: u64 = 0x1000;
+        // giá trị thật của register/stack slot (cho CmpLog)
+        let mut acc_value: u64 = 0;
+        let mut stack_values = [0u64; 4];
+
+        // frame callee: tách coverage theo program
+        let mut pc: u64 = if depth == 1 {
+            0x1000
+        } else {
+            0x1000 ^ u64::from_le_bytes(program_id.to_bytes()[24..32].try_into().unwrap())
+        };
+
+        let cpi_bytes = tx.instruction.accounts.len() as u64 * 34 + 8;
+        // byte đã giao cho frame callee (CPI data): caller không diễn giải lại
+        let mut consumed = 0usize;
 
         for (i, &b) in data.iter().enumerate() {
+            if consumed > 0 {
+                consumed -= 1;
+                continue;
+            }
+            // cost ước lượng của byte này; frame callee (CPI) có thể đã làm hết budget
+            let next = data.get(i + 1).copied().unwrap_or(b);
+            let ext = is_ext_op(b, st.ops_version);
+            if st.exhausted || !st.consume(compute::op_cost(b, next, cpi_bytes, ext)) {
+                return;
+            }
+
+            st.site = site_pc(b, st.ops_version);
+
             // coverage edge
             let dst = pc.wrapping_add((b as u64) * 7).wrapping_add(i as u64);
-            coverage.hit_edge(pc, dst);
+            st.coverage.hit_edge(pc, dst);
             pc = dst;
 
             match b >> 5 {
                 // 0) AUTH CMP
+                // lhs: bit2=0 -> u64 từ instruction data, bit2=1 -> giá trị account data vừa đọc (R_ACC)
+                // rhs: bit1=1 -> biến đã spill lên stack (slot bit3..4), bit1=0 -> hằng số
                 0 => {
                     let used_for_auth = (b & 1) == 1; // ~50%
-                    let lhs_tainted = taint.input_taint;
-                    let rhs_tainted = (b & 2) != 0;
+                    if (b & 4) == 0 {
+                        st.taint.set(R_LHS, ix_operand(i + 1, 8));
+                    } else {
+                        st.taint.set(R_LHS, st.taint.label(R_ACC).clone());
+                    }
+                    if (b & 2) != 0 {
+                        st.taint.load(R_RHS, stack_slot((b >> 3) & 3), 8);
+                    } else {
+                        st.taint.clear(R_RHS);
+                    }
+                    let lhs_tainted = !st.taint.label(R_LHS).is_empty();
+                    let rhs_tainted = !st.taint.label(R_RHS).is_empty();
+                    let offsets = st.taint.label(R_LHS).union(st.taint.label(R_RHS)).copied().collect();
+
+                    let lhs = if (b & 4) == 0 { read_u64(data, i + 1) } else { acc_value };
+                    let rhs = if (b & 2) != 0 {
+                        stack_values[((b >> 3) & 3) as usize]
+                    } else {
+                        CMP_MAGIC[((b >> 3) & 3) as usize]
+                    };
+                    // jeq: mỗi kết quả một edge
+                    st.coverage.hit_edge(pc, pc ^ (0x7400 + (lhs == rhs) as u64));
 
-                    events.push(VmEvent::Cmp {
+                    st.emit(program_id, depth, VmEvent::Cmp {
                         lhs_tainted,
                         rhs_tainted,
                         used_for_auth,
+                        offsets,
+                        lhs,
+                        rhs,
                     });
                 }
 
@@ -80,16 +419,25 @@ impl TraceVm {
                         pick_acct(b, tx)
                     } else {
                         // benign read: MUST be program-owned account
-                        pick_benign_owned_by_program(b, tx, &snap)
+                        pick_benign_owned_by_program(b, tx, &st.snap)
                     };
 
-                    let owner = snap.accounts.get(&acct).map(|a| a.owner).unwrap_or(program_id);
+                    let owner = st.snap.accounts.get(&acct).map(|a| a.owner).unwrap_or(program_id);
 
                     if owner != program_id {
-                        taint.data_acc_taint = true;
+                        st.taint.data_acc_taint = true;
+                    }
+
+                    // ldx R_ACC, [data + 8]; spill lên stack slot bit3..4
+                    match st.account_data.get(&acct) {
+                        Some(&vaddr) => st.taint.load(R_ACC, vaddr + 8, 8),
+                        None => st.taint.clear(R_ACC),
                     }
+                    st.taint.store(stack_slot((b >> 3) & 3), 8, R_ACC);
+                    acc_value = st.snap.accounts.get(&acct).map(|a| read_u64(&a.data, 8)).unwrap_or(0);
+                    stack_values[((b >> 3) & 3) as usize] = acc_value;
 
-                    events.push(VmEvent::ReadAccountData { acct, owner });
+                    st.emit(program_id, depth, VmEvent::ReadAccountData { acct, owner });
                 }
 
                 // 2) WRITE LAMPORTS
@@ -132,7 +480,7 @@ impl TraceVm {
                         }
                     };
 
-                    if let Some(a) = snap.accounts.get_mut(&acct) {
+                    if let Some(a) = st.snap.accounts.get_mut(&acct) {
                         if delta < 0 {
                             a.lamports = a.lamports.saturating_sub((-delta) as u64);
                         } else {
@@ -140,48 +488,176 @@ impl TraceVm {
                         }
                     }
 
-                    events.push(VmEvent::WriteLamports { acct, delta });
+                    st.emit(program_id, depth, VmEvent::WriteLamports { acct, delta });
                 }
 
                 // 3) WRITE DATA
                 3 => {
                     let acct = pick_acct(b, tx);
-                    if let Some(a) = snap.accounts.get_mut(&acct) {
+                    if let Some(a) = st.snap.accounts.get_mut(&acct) {
                         let n = ((b & 0x1f) as usize).max(1);
                         for j in 0..n.min(a.data.len()) {
                             a.data[j] ^= b;
                         }
-                        events.push(VmEvent::WriteData { acct, nbytes: n });
+                        // giá trị ghi là kết quả integer op gần nhất
+                        if let Some(&vaddr) = st.account_data.get(&acct) {
+                            st.taint.store(vaddr, n.min(a.data.len()), R_INT);
+                        }
+                        st.emit(program_id, depth, VmEvent::WriteData {
+                            acct,
+                            offset: 0,
+                            nbytes: n,
+                        });
                     }
                 }
 
                 // 4) CPI (ACPI)
+                // - bit0=0: self-CPI; bit0=1 + bit3: program khác trong ledger (chọn bằng byte kế tiếp);
+                //   bit0=1 + bit4: program id lấy từ account meta (chọn bằng byte kế tiếp);
+                //   còn lại: program lạ (không có trong ledger, không mở frame)
+                // - bit2: callee đòi writable cho mọi account (privilege escalation nếu caller không có)
+                // Frame callee là mô phỏng, không chạy ELF của callee: 8 byte sau opcode là CPI data,
+                // diễn giải như trace ops dưới program id callee; caller bỏ qua các byte đó
                 4 => {
-                    let invoked = if (b & 1) == 0 { program_id } else { Pubkey::new_unique() };
+                    let arg = data.get(i + 1).copied().unwrap_or(b);
+                    let others: Vec<Pubkey> = st
+                        .snap
+                        .accounts
+                        .iter()
+                        .filter(|(k, a)| a.is_executable && **k != program_id)
+                        .map(|(k, _)| *k)
+                        .collect();
+                    // program_source: index meta cung cấp program id, chỉ khi input chọn target từ metas
+                    // (program id hard-code trùng key một meta vẫn không phải do attacker chọn)
+                    let (invoked, program_source) = if (b & 1) == 0 {
+                        (program_id, None)
+                    } else if (b & 8) != 0 && !others.is_empty() {
+                        (others[arg as usize % others.len()], None)
+                    } else if (b & 0x10) != 0 && !tx.instruction.accounts.is_empty() {
+                        let idx = arg as usize % tx.instruction.accounts.len();
+                        let key = tx.instruction.accounts[idx].pubkey;
+                        (key, Some(idx).filter(|_| !st.taint.key_checked(&key)))
+                    } else {
+                        (derived_key(&program_id, b"cpi", st.frames, i), None)
+                    };
 
-                    let mut provided = Vec::new();
+                    // account-info sharing: callee chỉ thấy account caller truyền vào, giữ nguyên privilege
+                    let mut metas: Vec<InstrAccountMeta> = Vec::new();
                     if let Some(m) = tx.instruction.accounts.first() {
-                        provided.push(m.pubkey);
+                        metas.push(m.clone());
                     }
                     // include signer sometimes
                     if (b & 2) != 0 {
                         if let Some(s) = tx.instruction.accounts.iter().find(|m| m.is_signer) {
-                            provided.push(s.pubkey);
+                            if !metas.iter().any(|x| x.pubkey == s.pubkey) {
+                                metas.push(s.clone());
+                            }
                         }
                     }
+                    let provided = metas.iter().map(|m| m.pubkey).collect();
+                    let privileged = metas
+                        .iter()
+                        .filter(|m| m.is_signer || m.is_writable)
+                        .map(|m| m.pubkey)
+                        .collect();
 
-                    events.push(VmEvent::Cpi {
+                    st.emit(program_id, depth, VmEvent::Cpi {
                         invoked_program: invoked,
                         provided,
+                        program_source,
+                        privileged,
                     });
+
+                    let executable = st.snap.accounts.get(&invoked).map(|a| a.is_executable).unwrap_or(false);
+                    if executable {
+                        let wants_writable = (b & 4) != 0;
+                        if depth >= MAX_INVOKE_DEPTH {
+                            st.emit(program_id, depth, VmEvent::CpiRejected {
+                                invoked_program: invoked,
+                                reason: "max invoke depth exceeded",
+                            });
+                        } else if wants_writable && metas.iter().any(|m| !m.is_writable) {
+                            st.emit(program_id, depth, VmEvent::CpiRejected {
+                                invoked_program: invoked,
+                                reason: "writable privilege escalated",
+                            });
+                        } else {
+                            let callee_tx = Transaction {
+                                signers: metas.iter().filter(|m| m.is_signer).map(|m| m.pubkey).collect(),
+                                all_accounts_sorted: metas
+                                    .iter()
+                                    .map(|m| m.pubkey)
+                                    .collect::<BTreeSet<_>>()
+                                    .into_iter()
+                                    .collect(),
+                                instruction: Instruction {
+                                    program_id: invoked,
+                                    accounts: metas,
+                                    data: data.iter().skip(i + 1).take(8).copied().collect(),
+                                },
+                                // Instructions sysvar vẫn là của tx top-level
+                                siblings: tx.siblings.clone(),
+                                instruction_index: tx.instruction_index,
+                                clock_override: tx.clock_override,
+                            };
+                            // callee data = 8 byte sau opcode -> giữ label của caller
+                            let callee_labels: Vec<TaintLabel> =
+                                ix_labels.iter().skip(i + 1).take(8).cloned().collect();
+                            let saved = st.taint.enter_frame();
+                            consumed = callee_tx.instruction.data.len();
+                            Self::run_frame(st, invoked, &callee_tx, depth + 1, Some(&callee_labels));
+                            st.taint.exit_frame(saved);
+                            st.site = site_pc(b, st.ops_version);
+                        }
+                    }
                 }
 
-                // 5..7) IntegerOp + KeyAccess
+                // 7) extended ops, tham số lấy từ byte kế tiếp
+                7 if ext => {
+                    let arg = data.get(i + 1).copied().unwrap_or(b);
+                    match b & 0x1f {
+                        0..=7 => token_op(st, program_id, depth, b & 0x1f, arg, tx),
+                        8..=9 => typed_read(st, program_id, depth, (b & 1) == 0, arg, tx),
+                        10 => naive_transfer(st, program_id, depth, arg, tx, &input),
+                        11 => initialize(st, program_id, depth, arg, tx),
+                        12 => close_account(st, program_id, depth, arg, tx),
+                        13 => create_account(st, program_id, depth, arg, tx, i),
+                        14 => realloc(st, program_id, depth, arg, tx),
+                        15 => read_sysvar(st, program_id, depth, arg, tx),
+                        16 => clock_op(st, program_id, depth, arg, tx, pc),
+                        17 => introspect(st, program_id, depth, arg, tx, pc),
+                        // vòng lặp theo độ dài do input quyết định (cost đã tính ở trên);
+                        // coverage theo bậc log2 số vòng -> feedback hướng tới input đắt hơn
+                        18 => {
+                            let iters = (arg as u64) << 10;
+                            st.coverage.hit_edge(pc, pc ^ (0x7300 + (64 - iters.leading_zeros()) as u64));
+                        }
+                        19 => dispatch(st, program_id, depth, data, i, pc),
+                        _ => unreachable!(),
+                    }
+                }
+
+                // 5..7) IntegerOp + KeyAccess (nhóm 7 chỉ khi không phải extended op)
                 _ => {
                     let overflowed = (b & 15) == 15; // ~1/16 (giảm IB)
-                    let tainted = taint.input_taint;
 
-                    events.push(VmEvent::IntegerOp { tainted, overflowed });
+                    // toán hạng: bit3=0 -> u64 từ instruction data, bit3=1 -> buffer heap (slot bit0..2);
+                    // kết quả lưu lại heap slot bit0..2
+                    if (b & 8) == 0 {
+                        st.taint.set(R_INT, ix_operand(i + 1, 8));
+                    } else {
+                        st.taint.load(R_INT, heap_slot(b & 7), 8);
+                    }
+                    st.taint.alu(R_INT, R_LHS);
+                    st.taint.store(heap_slot(b & 7), 8, R_INT);
+                    let offsets: Vec<usize> = st.taint.label(R_INT).iter().copied().collect();
+                    let tainted = !offsets.is_empty();
+
+                    st.emit(program_id, depth, VmEvent::IntegerOp {
+                        tainted,
+                        overflowed,
+                        offsets,
+                    });
 
                     if tainted && overflowed {
                         pending_big_attacker_gain = true;
@@ -194,30 +670,798 @@ impl TraceVm {
                     if (b & 4) != 0 {
                         provided_keys.push(required_key);
                     }
+                    let provided_keys_empty = provided_keys.is_empty();
+
+                    // index account do chính byte này chọn
+                    st.taint.set(R_KEY, ix_operand(i, 1));
+                    let offsets = st.taint.label(R_KEY).iter().copied().collect();
 
-                    events.push(VmEvent::KeyAccess {
+                    st.emit(program_id, depth, VmEvent::KeyAccess {
                         required_key,
                         provided_keys,
                         used_for_auth,
+                        offsets,
                     });
+
+                    // key đã được check với hằng số -> không còn attacker-controlled
+                    if used_for_auth && !provided_keys_empty {
+                        st.taint.sanitize_key(required_key);
+                    }
                 }
             }
         }
+    }
+}
 
-        let trace_summary = format!(
-            "TraceVM: bytes={} edges_hash={:016x} accounts={} signers={}",
-            data.len(),
-            coverage.hash16(),
-            tx.all_accounts_sorted.len(),
-            tx.signers.len()
+/// Transfer lamports giữa 2 meta kiểu "đọc cả 2 balance rồi mới ghi":
+/// nếu 2 meta alias cùng một account thì lần ghi sau thắng -> balance tăng
+fn naive_transfer(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    arg: u8,
+    tx: &Transaction,
+    input: &InputLayout,
+) {
+    let metas = &tx.instruction.accounts;
+    if metas.len() < 2 {
+        return;
+    }
+    let from_index = arg as usize % metas.len();
+    let to_index = (arg >> 4) as usize % metas.len();
+    if from_index == to_index || !metas[from_index].is_writable || !metas[to_index].is_writable {
+        return;
+    }
+    let from = input.accounts[from_index].key;
+    let to = input.accounts[to_index].key;
+    let lamports =
+        |st: &VmState, k: &Pubkey| st.snap.accounts.get(k).map(|a| a.lamports).unwrap_or(0);
+
+    let amount = ((arg as u64 & 0x0f) + 1) * 10_000;
+    let from_bal = lamports(st, &from);
+    let to_bal = lamports(st, &to);
+    if from_bal < amount {
+        return;
+    }
+    if let Some(a) = st.snap.accounts.get_mut(&from) {
+        a.lamports = from_bal - amount;
+    }
+    if let Some(a) = st.snap.accounts.get_mut(&to) {
+        a.lamports = to_bal.saturating_add(amount);
+    }
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::MetaTransfer {
+            from_index,
+            to_index,
+            from,
+            to,
+            amount,
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: from,
+            delta: -(amount as i64),
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: to,
+            delta: amount as i64,
+        },
+    );
+}
+
+/// Offset authority khi layout không biết (field đầu tiên sau discriminator)
+const DEFAULT_AUTHORITY_OFFSET: usize = 8;
+
+fn owned_writable_metas(st: &VmState, program_id: Pubkey, tx: &Transaction) -> Vec<Pubkey> {
+    tx.instruction
+        .accounts
+        .iter()
+        .filter(|m| m.is_writable)
+        .map(|m| m.pubkey)
+        .filter(|k| st.snap.accounts.get(k).map(|a| a.owner == program_id) == Some(true))
+        .collect()
+}
+
+/// "initialize": ghi discriminator + authority (signer đầu tiên) vào program-owned account.
+/// Type = type hiện tại của account (re-init), account chưa init dùng type đầu tiên của layout model.
+/// arg bit0 = có check "đã init chưa" (discriminator khác 0) trước khi ghi
+fn initialize(st: &mut VmState, program_id: Pubkey, depth: usize, arg: u8, tx: &Transaction) {
+    let owned = owned_writable_metas(st, program_id, tx);
+    let Some(authority) = tx
+        .instruction
+        .accounts
+        .iter()
+        .find(|m| m.is_signer)
+        .map(|m| m.pubkey)
+    else {
+        return;
+    };
+    if owned.is_empty() {
+        return;
+    }
+    let acct = owned[(arg >> 1) as usize % owned.len()];
+    let checks_initialized = (arg & 1) != 0;
+
+    let layouts = st.layouts;
+    let Some(a) = st.snap.accounts.get_mut(&acct) else {
+        return;
+    };
+    let Some(layout) = layouts.classify(&a.data).or(layouts.layouts.first()) else {
+        return;
+    };
+    let Some(offset) = layout.authority_offset else {
+        return;
+    };
+    if a.data.len() < offset + 32 {
+        return;
+    }
+    if checks_initialized && a.data[..8].iter().any(|&x| x != 0) {
+        return;
+    }
+    a.data[..8].copy_from_slice(&layout.discriminator);
+    a.data[offset..offset + 32].copy_from_slice(authority.as_ref());
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteData {
+            acct,
+            offset: 0,
+            nbytes: 8,
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteData {
+            acct,
+            offset,
+            nbytes: 32,
+        },
+    );
+}
+
+/// "close": chuyển hết lamports của program-owned account sang signer đầu tiên.
+/// arg bit0 = close đúng cách (zero data + trả owner về system program)
+fn close_account(st: &mut VmState, program_id: Pubkey, depth: usize, arg: u8, tx: &Transaction) {
+    let owned = owned_writable_metas(st, program_id, tx);
+    let Some(dest) = tx
+        .instruction
+        .accounts
+        .iter()
+        .find(|m| m.is_signer && m.is_writable)
+        .map(|m| m.pubkey)
+    else {
+        return;
+    };
+    if owned.is_empty() {
+        return;
+    }
+    let acct = owned[(arg >> 1) as usize % owned.len()];
+    if acct == dest {
+        return;
+    }
+    let proper = (arg & 1) != 0;
+
+    let (drained, len) = match st.snap.accounts.get_mut(&acct) {
+        Some(a) => {
+            let drained = a.lamports;
+            a.lamports = 0;
+            if proper {
+                a.data.fill(0);
+                a.owner = solana_sdk::system_program::id();
+            }
+            (drained, a.data.len())
+        }
+        None => return,
+    };
+    if let Some(d) = st.snap.accounts.get_mut(&dest) {
+        d.lamports = d.lamports.saturating_add(drained);
+    }
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct,
+            delta: -(drained as i64),
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: dest,
+            delta: drained as i64,
+        },
+    );
+    if proper {
+        st.emit(
+            program_id,
+            depth,
+            VmEvent::WriteData {
+                acct,
+                offset: 0,
+                nbytes: len,
+            },
         );
+    }
+}
 
-        VmRunOutput {
-            coverage,
-            taint,
-            events,
-            post_snapshot: snap,
-            trace_summary,
+/// "create account": program tạo account mới (owner = program, key theo vị trí op `i`),
+/// payer là meta do input chọn bằng arg.
+/// arg bit4 = nạp đủ rent-exempt minimum, không thì chỉ nạp một nửa
+fn create_account(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    arg: u8,
+    tx: &Transaction,
+    i: usize,
+) {
+    let metas = &tx.instruction.accounts;
+    if metas.is_empty() {
+        return;
+    }
+    let payer_index = (arg >> 5) as usize % metas.len();
+    let payer = metas[payer_index].pubkey;
+    let space = (arg as usize & 0x0f) * 32 + 8;
+    let min = st.rent.minimum_balance(space);
+    let lamports = if (arg & 0x10) != 0 { min } else { min / 2 };
+
+    match st.snap.accounts.get_mut(&payer) {
+        Some(p) if p.lamports >= lamports => p.lamports -= lamports,
+        _ => return,
+    }
+    let new_account = derived_key(&program_id, b"create_account", st.frames, i);
+    st.snap.accounts.insert(
+        new_account,
+        Account {
+            owner: program_id,
+            lamports,
+            data: vec![0; space],
+            is_executable: false,
+            rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+        },
+    );
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::CreateAccount {
+            payer,
+            payer_source: Some(payer_index).filter(|_| !st.taint.key_checked(&payer)),
+            new_account,
+            lamports,
+            space,
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: payer,
+            delta: -(lamports as i64),
+        },
+    );
+}
+
+/// "realloc": đổi size data của program-owned account thêm `arg * 64` byte,
+/// tối đa MAX_PERMITTED_DATA_LENGTH như runtime
+fn realloc(st: &mut VmState, program_id: Pubkey, depth: usize, arg: u8, tx: &Transaction) {
+    let owned = owned_writable_metas(st, program_id, tx);
+    if owned.is_empty() {
+        return;
+    }
+    let acct = owned[arg as usize % owned.len()];
+    let Some(a) = st.snap.accounts.get_mut(&acct) else {
+        return;
+    };
+    let old_len = a.data.len();
+    // không tự giới hạn theo MAX_PERMITTED_DATA_INCREASE: oracle quyết định
+    let new_len = (old_len + arg as usize * 64).min(MAX_PERMITTED_DATA_LENGTH as usize);
+    if new_len == old_len {
+        return;
+    }
+    a.data.resize(new_len, 0);
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::Realloc {
+            acct,
+            old_len,
+            new_len,
+        },
+    );
+}
+
+/// Đọc sysvar qua AccountInfo (không qua syscall): program lấy account theo vị trí,
+/// chọn meta có data đúng size của sysvar. arg bit0: Clock/Rent, bit7: có check key
+fn read_sysvar(st: &mut VmState, program_id: Pubkey, depth: usize, arg: u8, tx: &Transaction) {
+    let (sysvar_id, len) = if (arg & 1) == 0 {
+        (
+            sysvar::clock::id(),
+            bincode::serialized_size(&Clock::default()).unwrap() as usize,
+        )
+    } else {
+        (
+            sysvar::rent::id(),
+            bincode::serialized_size(&Rent::default()).unwrap() as usize,
+        )
+    };
+    let candidates: Vec<Pubkey> = tx
+        .instruction
+        .accounts
+        .iter()
+        .map(|m| m.pubkey)
+        .filter(|k| st.snap.accounts.get(k).map(|a| a.data.len() == len) == Some(true))
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    let acct = candidates[(arg >> 1) as usize % candidates.len()];
+    let key_checked = (arg & 0x80) != 0;
+    if key_checked && acct != sysvar_id {
+        return;
+    }
+
+    let owner = st.snap.accounts[&acct].owner;
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::ReadSysvar {
+            acct,
+            owner,
+            sysvar: sysvar_id,
+            key_checked,
+        },
+    );
+}
+
+/// Logic phụ thuộc thời gian (vesting/auction): đọc Clock qua syscall (arg bit0 = 0)
+/// hoặc AccountInfo (bit0 = 1), so với deadline `arg >> 1` (giờ / nghìn slot tính từ
+/// đầu epoch); đủ hạn thì vault (program-owned) trả lamports cho signer đầu tiên
+fn clock_op(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    arg: u8,
+    tx: &Transaction,
+    pc: u64,
+) {
+    let via_account = (arg & 1) != 0;
+    let acct = if via_account {
+        let len = bincode::serialized_size(&Clock::default()).unwrap() as usize;
+        tx.instruction
+            .accounts
+            .iter()
+            .map(|m| m.pubkey)
+            .find(|k| st.snap.accounts.get(k).map(|a| a.data.len() == len) == Some(true))
+    } else {
+        Some(sysvar::clock::id())
+    };
+    let Some(clock) = acct
+        .and_then(|k| st.snap.accounts.get(&k))
+        .and_then(|a| bincode::deserialize::<Clock>(&a.data).ok())
+    else {
+        return;
+    };
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::ClockRead {
+            acct: acct.filter(|_| via_account),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        },
+    );
+
+    // mỗi kết quả so sánh là một edge riêng -> coverage phân biệt được thời điểm
+    let deadline = arg as i64 >> 1;
+    let ts_ok = clock.unix_timestamp >= clock.epoch_start_timestamp + deadline * 3_600;
+    let slot_ok = clock.slot >= deadline as u64 * 1_000;
+    st.coverage
+        .hit_edge(pc, pc ^ (0x7100 + ((ts_ok as u64) << 1) + slot_ok as u64));
+    if !(ts_ok && slot_ok) {
+        return;
+    }
+
+    release_to_signer(st, program_id, depth, tx, 50_000);
+}
+
+/// Nhánh "đã được phép": vault (program-owned writable meta đầu tiên) trả `amount` cho signer đầu tiên
+fn release_to_signer(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    tx: &Transaction,
+    amount: u64,
+) {
+    let owned = owned_writable_metas(st, program_id, tx);
+    let Some(to) = tx
+        .instruction
+        .accounts
+        .iter()
+        .find(|m| m.is_signer)
+        .map(|m| m.pubkey)
+    else {
+        return;
+    };
+    let Some(&from) = owned.first() else {
+        return;
+    };
+    match st.snap.accounts.get_mut(&from) {
+        Some(a) if a.lamports >= amount => a.lamports -= amount,
+        _ => return,
+    }
+    if let Some(a) = st.snap.accounts.get_mut(&to) {
+        a.lamports = a.lamports.saturating_add(amount);
+    }
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: from,
+            delta: -(amount as i64),
+        },
+    );
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::WriteLamports {
+            acct: to,
+            delta: amount as i64,
+        },
+    );
+}
+
+/// Kiểm tra sibling instruction qua Instructions sysvar (vd. bắt buộc có ed25519 verify trước đó).
+/// arg bit0: `get_instruction_relative(-1)` thay vì `load_instruction_at_checked(0)`,
+/// bit1: check program_id = ed25519, bit2: check pubkey trong verify = authority (user),
+/// bit3: check `get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT`
+fn introspect(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    arg: u8,
+    tx: &Transaction,
+    pc: u64,
+) {
+    let key = sysvar::instructions::id();
+    if !tx.instruction.accounts.iter().any(|m| m.pubkey == key) {
+        return;
+    }
+    let Some(acc) = st.snap.accounts.get(&key) else {
+        return;
+    };
+    let (mut lamports, mut data, owner) = (acc.lamports, acc.data.clone(), acc.owner);
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        acc.rent_epoch,
+    );
+    let sibling = if (arg & 1) != 0 {
+        instructions::get_instruction_relative(-1, &info)
+    } else {
+        instructions::load_instruction_at_checked(0, &info)
+    };
+    // instruction ở index 0 có thể chính là instruction đang chạy
+    let sibling = sibling.ok().filter(|ix| ix.program_id != program_id);
+
+    let program_checked = (arg & 2) != 0;
+    let data_checked = (arg & 4) != 0;
+    let stack_checked = (arg & 8) != 0;
+    let stack_height = depth; // get_stack_height()
+
+    let verify_signer = sibling.as_ref().and_then(|ix| {
+        let off = u16::from_le_bytes(ix.data.get(6..8)?.try_into().ok()?) as usize;
+        Pubkey::try_from(ix.data.get(off..off + 32)?).ok()
+    });
+    // authority lưu trong vault (program-owned writable meta đầu tiên)
+    let authority = owned_writable_metas(st, program_id, tx)
+        .first()
+        .and_then(|k| st.authority_of(k));
+    let passed = sibling.as_ref().is_some_and(|ix| {
+        (!program_checked || ix.program_id == ed25519_program::id())
+            && (!data_checked || (verify_signer.is_some() && verify_signer == authority))
+            && (!stack_checked || stack_height == TRANSACTION_LEVEL_STACK_HEIGHT)
+    });
+    st.coverage.hit_edge(
+        pc,
+        pc ^ (0x7200 + ((sibling.is_some() as u64) << 1) + passed as u64),
+    );
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::Introspect {
+            sibling_program: sibling.map(|ix| ix.program_id),
+            verify_signer,
+            program_checked,
+            data_checked,
+            stack_checked,
+            stack_height,
+            passed,
+        },
+    );
+    if passed {
+        release_to_signer(st, program_id, depth, tx, 50_000);
+    }
+}
+
+/// Anchor dispatch: so 8 byte sau opcode với sighash từng instruction (sol_memcmp);
+/// khớp thì vào handler tương ứng (edge riêng)
+fn dispatch(st: &mut VmState, program_id: Pubkey, depth: usize, data: &[u8], i: usize, pc: u64) {
+    let got: Vec<u8> = data.iter().skip(i + 1).take(8).copied().collect();
+    for n in 0..st.sighashes.len() {
+        let sighash = st.sighashes[n];
+        st.emit(
+            program_id,
+            depth,
+            VmEvent::MemCmp {
+                lhs: got.clone(),
+                rhs: sighash.to_vec(),
+            },
+        );
+        if got == sighash[..] {
+            st.coverage.hit_edge(pc, pc ^ (0x7500 + n as u64 + 1));
+            return;
+        }
+    }
+    // fallback: InstructionFallbackNotFound
+    st.coverage.hit_edge(pc, pc ^ 0x7500);
+}
+
+/// Deserialize một program-owned account thành account type (chọn bằng `arg`);
+/// `checked` = program so discriminator trước khi dùng
+fn typed_read(
+    st: &mut VmState,
+    program_id: Pubkey,
+    depth: usize,
+    checked: bool,
+    arg: u8,
+    tx: &Transaction,
+) {
+    // chỉ account có discriminator của một type trong layout model (data đã bị ghi hỏng
+    // thì deserialize không ra type nào)
+    let owned: Vec<Pubkey> = tx
+        .instruction
+        .accounts
+        .iter()
+        .map(|m| m.pubkey)
+        .filter(|k| {
+            st.snap
+                .accounts
+                .get(k)
+                .is_some_and(|a| a.owner == program_id && st.layouts.classify(&a.data).is_some())
+        })
+        .collect();
+    if owned.is_empty() {
+        return;
+    }
+
+    // type program mong đợi: account type của layout model (IDL)
+    let types: Vec<[u8; 8]> = st.layouts.layouts.iter().map(|l| l.discriminator).collect();
+
+    let acct = owned[arg as usize % owned.len()];
+    let expected = types[(arg >> 4) as usize % types.len()];
+    let actual: [u8; 8] = st.snap.accounts[&acct].data[..8].try_into().unwrap();
+
+    st.emit(
+        program_id,
+        depth,
+        VmEvent::ReadAccountAs {
+            acct,
+            expected,
+            actual,
+            checked,
+        },
+    );
+}
+
+fn token_metas(tx: &Transaction, snap: &LedgerSnapshot) -> Vec<Pubkey> {
+    tx.instruction
+        .accounts
+        .iter()
+        .map(|m| m.pubkey)
+        .filter(|k| {
+            snap.accounts
+                .get(k)
+                .and_then(|a| TokenAccount::unpack(&a.data))
+                .is_some()
+        })
+        .collect()
+}
+
+fn update_token(snap: &mut LedgerSnapshot, k: &Pubkey, f: impl FnOnce(&mut TokenAccount)) {
+    if let Some(a) = snap.accounts.get_mut(k) {
+        if let Some(mut t) = TokenAccount::unpack(&a.data) {
+            f(&mut t);
+            a.data = t.pack();
+        }
+    }
+}
+
+fn update_mint(snap: &mut LedgerSnapshot, k: &Pubkey, f: impl FnOnce(&mut Mint)) {
+    if let Some(a) = snap.accounts.get_mut(k) {
+        if let Some(mut m) = Mint::unpack(&a.data) {
+            f(&mut m);
+            a.data = m.pack();
+        }
+    }
+}
+
+/// Token ops (lite): chương trình "quên" check owner/authority nên op luôn được áp dụng,
+/// oracle quyết định op nào là hợp lệ. Op không đổi state (burn 0, set authority cũ) không phát event.
+/// - 0..=3: transfer giữa 2 token account trong metas
+/// - 4: mint_to (supply += amount)
+/// - 5: credit thẳng vào amount, không cập nhật supply (bug)
+/// - 6: burn
+/// - 7: set delegate / close_authority
+fn token_op(st: &mut VmState, program_id: Pubkey, depth: usize, op: u8, arg: u8, tx: &Transaction) {
+    let metas = token_metas(tx, &st.snap);
+    if metas.is_empty() {
+        return;
+    }
+    let amount = (arg as u64 + 1) * 1_000;
+    let signer = tx
+        .instruction
+        .accounts
+        .iter()
+        .find(|m| m.is_signer)
+        .map(|m| m.pubkey);
+
+    match op {
+        0..=3 => {
+            let source = metas[arg as usize % metas.len()];
+            let destination = metas[(arg as usize + op as usize + 1) % metas.len()];
+            let bal = st
+                .snap
+                .accounts
+                .get(&source)
+                .and_then(|a| TokenAccount::unpack(&a.data))
+                .map(|t| t.amount)
+                .unwrap_or(0);
+            if source == destination || bal < amount {
+                return;
+            }
+            update_token(&mut st.snap, &source, |t| t.amount -= amount);
+            update_token(&mut st.snap, &destination, |t| {
+                t.amount = t.amount.saturating_add(amount)
+            });
+            st.emit(
+                program_id,
+                depth,
+                VmEvent::TokenTransfer {
+                    source,
+                    destination,
+                    amount,
+                },
+            );
+        }
+        4..=6 => {
+            let account = metas[arg as usize % metas.len()];
+            let Some(mint) = st
+                .snap
+                .accounts
+                .get(&account)
+                .and_then(|a| TokenAccount::unpack(&a.data))
+                .map(|t| t.mint)
+            else {
+                return;
+            };
+            let authority = signer.unwrap_or(tx.instruction.accounts[0].pubkey);
+            match op {
+                4 => {
+                    update_mint(&mut st.snap, &mint, |m| {
+                        m.supply = m.supply.saturating_add(amount)
+                    });
+                    update_token(&mut st.snap, &account, |t| {
+                        t.amount = t.amount.saturating_add(amount)
+                    });
+                    st.emit(
+                        program_id,
+                        depth,
+                        VmEvent::TokenSupplyOp {
+                            mint,
+                            account,
+                            delta: amount as i64,
+                            authority,
+                        },
+                    );
+                }
+                5 => {
+                    // không có TokenSupplyOp: supply không đổi nhưng balance tăng
+                    update_token(&mut st.snap, &account, |t| {
+                        t.amount = t.amount.saturating_add(amount)
+                    });
+                }
+                _ => {
+                    let burned = st
+                        .snap
+                        .accounts
+                        .get(&account)
+                        .and_then(|a| TokenAccount::unpack(&a.data))
+                        .map(|t| amount.min(t.amount))
+                        .unwrap_or(0);
+                    // burn 0 không đổi state -> không có event
+                    if burned == 0 {
+                        return;
+                    }
+                    update_token(&mut st.snap, &account, |t| t.amount -= burned);
+                    update_mint(&mut st.snap, &mint, |m| {
+                        m.supply = m.supply.saturating_sub(burned)
+                    });
+                    st.emit(
+                        program_id,
+                        depth,
+                        VmEvent::TokenSupplyOp {
+                            mint,
+                            account,
+                            delta: -(burned as i64),
+                            authority,
+                        },
+                    );
+                }
+            }
+        }
+        _ => {
+            let account = metas[arg as usize % metas.len()];
+            let close_authority = (arg & 1) != 0;
+            let new_authority = if (arg & 2) != 0 {
+                tx.instruction.accounts[arg as usize % tx.instruction.accounts.len()].pubkey
+            } else {
+                signer.unwrap_or(tx.instruction.accounts[0].pubkey)
+            };
+            let current = st
+                .snap
+                .accounts
+                .get(&account)
+                .and_then(|a| TokenAccount::unpack(&a.data))
+                .map(|t| {
+                    if close_authority {
+                        t.close_authority
+                    } else {
+                        t.delegate
+                    }
+                });
+            if current == Some(Some(new_authority)) {
+                return;
+            }
+            update_token(&mut st.snap, &account, |t| {
+                if close_authority {
+                    t.close_authority = Some(new_authority);
+                } else {
+                    t.delegate = Some(new_authority);
+                    t.delegated_amount = t.amount;
+                }
+            });
+            st.emit(
+                program_id,
+                depth,
+                VmEvent::TokenSetAuthority {
+                    account,
+                    close_authority,
+                    new_authority,
+                },
+            );
         }
     }
 }